@@ -67,6 +67,24 @@ impl AuthState {
         let auth = serde_json::from_str(&content).map_err(|e| {AppError::new("AuthState", eyre!(e.to_string()))} )?;
         Ok(auth)
     }
+    /// Takes over the user returned by a login, keeping the current status.
+    pub fn update(&mut self, user: AuthState) {
+        self.banned = user.banned;
+        self.id = user.id;
+        self.access_token = user.access_token;
+        self.avatar = user.avatar;
+        self.ingame_name = user.ingame_name;
+        self.locale = user.locale;
+        self.platform = user.platform;
+        self.region = user.region;
+        self.role = user.role;
+    }
+    pub fn set_status(&mut self, status: &str) -> Result<(), AppError> {
+        self.status = Some(status.to_string());
+        self.save_to_file()?;
+        self.send_to_window();
+        Ok(())
+    }
     pub fn send_to_window(&self) {
         helper::emit_update("user","SET", Some(json!(self.clone())));
     }
@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     fs::File,
     io::{Read, Write},
     path::PathBuf,
@@ -20,8 +21,7 @@ use crate::{
     error::AppError,
     helper,
     logger::{self},
-    rate_limiter::RateLimiter,
-    structs::{Item, RivenAttributeInfo, RivenTypeInfo},
+    structs::{Item, ItemDetails, RivenAttributeInfo, RivenTypeInfo},
     wfm_client::client::WFMClient,
 };
 
@@ -38,6 +38,9 @@ pub struct CacheDataStruct {
 #[allow(dead_code)]
 pub struct CacheDataItemStruct {
     pub items: Vec<Item>,
+    // Item details from warframe.market by url name, fetched when first needed
+    #[serde(default)]
+    pub details: HashMap<String, ItemDetails>,
 }
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CacheDataRivenStruct {
@@ -48,6 +51,8 @@ pub struct CacheDataRivenStruct {
 #[derive(Clone, Debug)]
 pub struct CacheClient {
     pub log_file: PathBuf,
+    // Where the cache is saved, "cache.json" in the app folder
+    file_path: PathBuf,
     pub wfm: Arc<Mutex<WFMClient>>,
     pub cache_data: Arc<Mutex<CacheDataStruct>>,
 }
@@ -56,10 +61,14 @@ impl CacheClient {
     pub fn new(wfm: Arc<Mutex<WFMClient>>) -> Self {
         CacheClient {
             log_file: PathBuf::from("cache"),
+            file_path: Self::get_file_path(),
             wfm,
             cache_data: Arc::new(Mutex::new(CacheDataStruct {
                 last_refresh: None,
-                item: CacheDataItemStruct { items: vec![] },
+                item: CacheDataItemStruct {
+                    items: vec![],
+                    details: HashMap::new(),
+                },
                 riven: CacheDataRivenStruct {
                     items: vec![],
                     attributes: vec![],
@@ -73,11 +82,18 @@ impl CacheClient {
         settings_path
     }
 
+    /// Saves the cache to `file_path` instead, for tests.
+    #[cfg(test)]
+    pub fn with_file_path(mut self, file_path: PathBuf) -> Self {
+        self.file_path = file_path;
+        self
+    }
+
     pub async fn load(&self) -> Result<CacheDataStruct, AppError> {
-        let path_ref = Self::get_file_path();
+        let path_ref = self.file_path.clone();
 
         if path_ref.exists() {
-            let (se, vaild) = Self::read_from_file()?;
+            let (se, vaild) = self.read_from_file()?;
             if vaild {
                 let last_refresh = se.last_refresh.clone();
                 match last_refresh {
@@ -144,7 +160,7 @@ impl CacheClient {
         let json = serde_json::to_string_pretty(&chache_data)
             .map_err(|e| AppError::new("Cache", eyre!(e.to_string())))?;
 
-        let mut file = File::create(Self::get_file_path())
+        let mut file = File::create(&self.file_path)
             .map_err(|e| AppError::new("Cache", eyre!(e.to_string())))?;
 
         file.write_all(json.as_bytes())
@@ -153,8 +169,8 @@ impl CacheClient {
         Ok(())
     }
 
-    pub fn read_from_file() -> Result<(CacheDataStruct, bool), AppError> {
-        let mut file = File::open(Self::get_file_path())
+    pub fn read_from_file(&self) -> Result<(CacheDataStruct, bool), AppError> {
+        let mut file = File::open(&self.file_path)
             .map_err(|e| AppError::new("Cache", eyre!(e.to_string())))?;
         let mut content = String::new();
         file.read_to_string(&mut content)
@@ -182,6 +198,16 @@ impl CacheClient {
                 item_data["items"] = json!([]);
                 is_valid = false;
             }
+            // Details cached by older versions have no url names, they are fetched again
+            if let Some(details) = item_data.get_mut("details").and_then(|d| d.as_object_mut()) {
+                details.retain(|_, detail| {
+                    detail["items_in_set"].as_array().map_or(false, |items| {
+                        items.iter().all(|item| {
+                            item["url_name"].as_str().map_or(false, |u| !u.is_empty())
+                        })
+                    })
+                });
+            }
         }
 
         // Check for nested properties within 'riven'
@@ -202,3 +228,38 @@ impl CacheClient {
         Ok((deserialized, is_valid))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::CacheClient;
+
+    #[test]
+    fn drops_item_details_without_url_names() {
+        let part = |url_name: &str| {
+            json!({
+                "id": "primed_flow_id",
+                "url_name": url_name,
+                "tags": [],
+                "en": { "item_name": "Primed Flow" },
+            })
+        };
+        let cache = json!({
+            "last_refresh": "2024-01-01T00:00:00+00:00",
+            "item": {
+                "items": [],
+                "details": {
+                    "primed_flow": { "id": "primed_flow_id", "items_in_set": [part("primed_flow")] },
+                    "old_item": { "id": "old_item_id", "items_in_set": [part("")] },
+                },
+            },
+            "riven": { "items": [], "attributes": [] },
+        });
+
+        let (data, valid) = CacheClient::validate_json(&cache.to_string()).unwrap();
+        assert!(valid);
+        assert!(data.item.details.contains_key("primed_flow"));
+        assert!(!data.item.details.contains_key("old_item"));
+    }
+}
@@ -10,7 +10,7 @@ use crate::{
     cache::client::CacheClient,
     error::AppError,
     helper, logger,
-    structs::{Item, ItemDetails},
+    structs::{Item, ItemDetails, ItemInfo},
 };
 
 pub struct ItemModule<'a> {
@@ -42,8 +42,9 @@ impl<'a> ItemModule<'a> {
                 .map_err(|e| AppError::new("CacheItems", eyre!(e.to_string())))?;
 
         helper::emit_undate_initializ_status("Downloading Item Data from Relics.Run...", None);
+        let relics_run = wfm.settings.lock()?.endpoints.get_relics_run();
         let response: HashMap<String, Value> =
-            reqwest::get("http://relics.run/history/item_data/item_info.json")
+            reqwest::get(format!("{}item_data/item_info.json", relics_run))
                 .await
                 .map_err(|e| AppError::new("CacheItems", eyre!(e.to_string())))?
                 .json()
@@ -106,6 +107,8 @@ impl<'a> ItemModule<'a> {
         let arced_mutex = Arc::clone(&self.client.cache_data);
         let mut my_lock = arced_mutex.lock()?;
         my_lock.item.items = items.clone();
+        // Details are fetched again after each refresh
+        my_lock.item.details.clear();
         Ok(items)
     }
 
@@ -126,6 +129,99 @@ impl<'a> ItemModule<'a> {
         Ok(item_type)
     }
 
+    /// Returns the warframe.market details of an item, they are only fetched once per cache refresh.
+    /// Call `save_to_file` on the cache client to keep new details.
+    pub async fn get_details(&self, url_name: &str) -> Result<ItemDetails, AppError> {
+        if let Some(details) = self.client.cache_data.lock()?.item.details.get(url_name) {
+            return Ok(details.clone());
+        }
+        let wfm = self.client.wfm.lock()?.clone();
+        let details = wfm.items().get_item(url_name.to_string()).await?;
+        let mut cache_data = self.client.cache_data.lock()?;
+        cache_data
+            .item
+            .details
+            .insert(url_name.to_string(), details.clone());
+        // The other items of the set share the same details
+        for item in details.items_in_set.iter() {
+            if item.url_name.is_empty() || cache_data.item.details.contains_key(&item.url_name) {
+                continue;
+            }
+            let mut part = details.clone();
+            part.id = item.id.clone();
+            cache_data.item.details.insert(item.url_name.clone(), part);
+        }
+        Ok(details)
+    }
+
+    /// Returns the max rank of a mod or arcane, None for items without ranks.
+    pub async fn get_mod_max_rank(&self, url_name: &str) -> Result<Option<f64>, AppError> {
+        if let Some(rank) = self.find_type(url_name)?.and_then(|item| item.mod_max_rank) {
+            return Ok(Some(rank as f64));
+        }
+        let details = self.get_details(url_name).await?;
+        Ok(details
+            .find(url_name)
+            .or(details.item())
+            .and_then(|item| item.mod_max_rank))
+    }
+
+    /// The parts of a set with the quantity the set needs, empty for items that are no set.
+    pub async fn get_set_parts(&self, url_name: &str) -> Result<Vec<ItemInfo>, AppError> {
+        let details = self.get_details(url_name).await?;
+        if !details.is_set() {
+            return Ok(vec![]);
+        }
+        Ok(details.parts().into_iter().cloned().collect())
+    }
+
+    /// The ducats an item is worth, for a set the ducats of all its parts.
+    pub async fn get_ducats(&self, url_name: &str) -> Result<Option<i64>, AppError> {
+        let details = self.get_details(url_name).await?;
+        match details.find(url_name) {
+            Some(item) if item.set_root && details.is_set() => Ok(Some(details.set_ducats())),
+            Some(item) => Ok(item.ducats),
+            None => Ok(None),
+        }
+    }
+
+    /// Finds an item by its name in any language warframe.market translates to.
+    /// Only items whose details were fetched have translations.
+    pub fn find_type_by_localized_name(&self, item_name: &str) -> Result<Option<Item>, AppError> {
+        if let Some(item) = self.find_type_by_name(item_name)? {
+            return Ok(Some(item));
+        }
+        let item_name = item_name.to_lowercase();
+        let url_name = self
+            .client
+            .cache_data
+            .lock()?
+            .item
+            .details
+            .values()
+            .flat_map(|details| details.items_in_set.iter())
+            .find(|item| {
+                item.translations
+                    .values()
+                    .any(|translation| translation.item_name.to_lowercase() == item_name)
+            })
+            .map(|item| item.url_name.clone());
+        match url_name {
+            Some(url_name) => self.find_type(&url_name),
+            None => Ok(None),
+        }
+    }
+
+    pub fn find_type_by_name(&self, item_name: &str) -> Result<Option<Item>, AppError> {
+        let types = self.client.cache_data.lock()?.clone().item.items;
+        let item_name = item_name.to_lowercase();
+        let item_type = types
+            .iter()
+            .find(|&x| x.item_name.to_lowercase() == item_name)
+            .cloned();
+        Ok(item_type)
+    }
+
     pub fn emit(&self) {
         let types = self.client.cache_data.lock().unwrap().clone().item.items;
         helper::send_message_to_window(
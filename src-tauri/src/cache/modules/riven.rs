@@ -66,6 +66,16 @@ impl<'a> RivenModule<'a> {
         Ok(riven_type)
     }
 
+    /// Finds a riven weapon by its in-game name, like the ones shown in the trade window.
+    pub fn find_type_by_name(&self, item_name: &str) -> Result<Option<RivenTypeInfo>, AppError> {
+        let types = self.client.cache_data.lock()?.clone().riven.items;
+        let riven_type = types
+            .iter()
+            .find(|&x| x.item_name.eq_ignore_ascii_case(item_name))
+            .cloned();
+        Ok(riven_type)
+    }
+
     pub fn find_attribute(
         &self,
         url_name: &str,
@@ -6,6 +6,7 @@ use eyre::eyre;
 
 use crate::{
     auth::AuthState,
+    credentials::Credentials,
     error::{self, AppError},
     logger,
     wfm_client::client::WFMClient,
@@ -18,11 +19,12 @@ static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_auth.log
 pub async fn login(
     email: String,
     password: String,
+    remember: Option<bool>,
     auth: tauri::State<'_, Arc<Mutex<AuthState>>>,
     wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
 ) -> Result<AuthState, AppError> {
     let wfm = wfm.lock().expect("Could not lock wfm").clone();
-    match wfm.auth().login(email, password).await {
+    match wfm.auth().login(email.clone(), password.clone()).await {
         Ok(user) => {
             if user.access_token.is_none() {
                 logger::critical(
@@ -37,19 +39,23 @@ pub async fn login(
                 ));
             }
 
+            // Kept to log in again when the session expires
+            let credentials = wfm.credentials();
+            let stored = if remember.unwrap_or(false) {
+                credentials.save(&Credentials { email, password })
+            } else {
+                credentials.clear()
+            };
+            if let Err(e) = stored {
+                error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            }
+
             let arced_mutex = Arc::clone(&auth);
             let mut auth = arced_mutex.lock().expect("Could not lock auth");
-            auth.banned = user.banned;
-            auth.id = user.id;
-            auth.access_token = user.access_token;
-            auth.avatar = user.avatar;
-            auth.ingame_name = user.ingame_name;
-            auth.locale = user.locale;
-            auth.platform = user.platform;
-            auth.region = user.region;
-            auth.role = user.role;
+            auth.update(user);
             auth.save_to_file()?;
             auth.send_to_window();
+            wfm.resume_session();
             return Ok(auth.clone());
         }
         Err(e) => {
@@ -65,15 +71,18 @@ pub async fn update_user_status(
 ) -> Result<(), AppError> {
     let arced_mutex = Arc::clone(&auth);
     let mut auth = arced_mutex.lock().expect("Could not lock auth");
-    auth.status = Some(status);
-    auth.save_to_file()?;
-    auth.send_to_window();
+    auth.set_status(&status)?;
     Ok(())
 }
 #[tauri::command]
 pub async fn logout(
     auth: tauri::State<'_, Arc<Mutex<AuthState>>>,
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
 ) -> Result<(), AppError> {
+    let wfm = wfm.lock()?.clone();
+    if let Err(e) = wfm.credentials().clear() {
+        error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+    }
     let arced_mutex = Arc::clone(&auth);
     let mut auth = arced_mutex.lock().expect("Could not lock auth");
     auth.access_token = None;
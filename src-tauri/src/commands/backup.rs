@@ -0,0 +1,71 @@
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    database::{
+        client::DBClient,
+        modules::backup::{BackupInfo, IntegrityReport},
+    },
+    error::{self, AppError},
+};
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_backup.log".to_string()));
+
+#[tauri::command]
+pub async fn create_backup(
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<BackupInfo, AppError> {
+    let db = db.lock()?.clone();
+    match db.backups().create("manual").await {
+        Ok(backup) => Ok(backup),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+pub fn list_backups(
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Vec<BackupInfo>, AppError> {
+    let db = db.lock()?.clone();
+    match db.backups().list() {
+        Ok(backups) => Ok(backups),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn restore_backup(
+    name: String,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<BackupInfo, AppError> {
+    let db = db.lock()?.clone();
+    match db.backups().restore(&name).await {
+        Ok(backup) => Ok(backup),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn check_database_integrity(
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<IntegrityReport, AppError> {
+    let db = db.lock()?.clone();
+    match db.backups().check_integrity().await {
+        Ok(report) => Ok(report),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            Err(e)
+        }
+    }
+}
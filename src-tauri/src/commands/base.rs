@@ -24,9 +24,13 @@ use crate::{
     handler::MonitorHandler,
     helper, logger,
     price_scraper::PriceScraper,
+    request_scheduler::SchedulerStatus,
     settings::SettingsState,
     wf_ee_log_parser::client::EELogParser,
-    wfm_client::client::WFMClient,
+    wfm_client::{
+        client::WFMClient,
+        websocket::WFMSocket,
+    },
     PACKAGEINFO,
 };
 
@@ -168,6 +172,7 @@ pub async fn init(
     if !ee_log.is_running() {
         ee_log.start_loop();
     }
+    response["ee_log"] = ee_log.get_status()?;
 
     Ok(response)
 }
@@ -176,7 +181,11 @@ pub async fn init(
 pub async fn update_settings(
     settings: SettingsState,
     settings_state: tauri::State<'_, Arc<std::sync::Mutex<SettingsState>>>,
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
 ) -> Result<(), AppError> {
+    // A typo in an endpoint would break every request made with it
+    settings.endpoints.validate()?;
+
     let arced_mutex = Arc::clone(&settings_state);
     let mut my_lock = arced_mutex.lock()?;
 
@@ -189,6 +198,20 @@ pub async fn update_settings(
     // Set Whisper Scraper Settings
     my_lock.notifications = settings.notifications;
 
+    // Set EE Log Settings
+    my_lock.ee_log = settings.ee_log;
+
+    // Set Endpoint Settings
+    my_lock.endpoints = settings.endpoints;
+
+    // Set Backup Settings
+    my_lock.backup = settings.backup;
+
+    // Set Request Settings
+    wfm.lock()?
+        .set_request_limits(settings.requests.rate, settings.requests.burst);
+    my_lock.requests = settings.requests;
+
     my_lock.save_to_file().expect("Could not save settings");
     Ok(())
 }
@@ -218,41 +241,6 @@ pub fn show_notification(
     );
 }
 
-#[tauri::command]
-pub fn on_new_wfm_message(
-    message: crate::wfm_client::modules::chat::ChatMessage,
-    auth: tauri::State<'_, Arc<Mutex<AuthState>>>,  
-    settings: tauri::State<'_, Arc<std::sync::Mutex<SettingsState>>>,
-    mh: tauri::State<'_, Arc<std::sync::Mutex<MonitorHandler>>>,
-) {
-    let mh = mh.lock().unwrap();
-    let auth = auth.lock().unwrap().clone();
-    let settings = settings.lock().unwrap().clone().notifications.on_wfm_chat_message;
-
-    if auth.id == message.message_from {
-        return;
-    }
-
-    let content = settings.content.replace("<WFM_MESSAGE>", &message.raw_message.unwrap_or("".to_string()));
-    if settings.system_notify {
-        mh.show_notification(
-            &settings.title,
-            &content,
-            Some("https://i.imgur.com/UggEVVI.jpeg"),
-            Some("Default"),
-        );
-    }
-
-    if settings.discord_notify  && settings.webhook.is_some() {
-        crate::helper::send_message_to_discord(
-            settings.webhook.unwrap_or("".to_string()),
-            settings.title,
-            content,
-            settings.user_ids,
-        );
-    }
-}
-
 #[tauri::command]
 pub fn log(
     component: String,
@@ -281,3 +269,20 @@ pub fn export_logs(mh: tauri::State<'_, Arc<std::sync::Mutex<MonitorHandler>>>)
         mh,
     );
 }
+
+#[tauri::command]
+pub fn get_request_status(
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+) -> Result<SchedulerStatus, AppError> {
+    let wfm = wfm.lock()?.clone();
+    Ok(wfm.get_scheduler_status())
+}
+
+#[tauri::command]
+pub fn send_wfm_socket_message(
+    message: Value,
+    socket: tauri::State<'_, Arc<Mutex<WFMSocket>>>,
+) -> Result<(), AppError> {
+    let socket = socket.lock()?.clone();
+    socket.send(message)
+}
@@ -12,6 +12,7 @@ static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_chat.log
 use crate::{
     error::{self, AppError},
     wfm_client::{
+        chat_outbox::{ChatOutbox, OutboxMessage, SendStatus},
         client::WFMClient,
         modules::chat::{ChatData, ChatMessage},
     },
@@ -68,3 +69,63 @@ pub async fn delete_chat(
         }
     }
 }
+
+#[tauri::command]
+pub async fn send_chat_message(
+    id: String,
+    chat_id: String,
+    message: String,
+    outbox: tauri::State<'_, Arc<Mutex<ChatOutbox>>>,
+) -> Result<SendStatus, AppError> {
+    let outbox = outbox.lock()?.clone();
+    match outbox.send(&id, &chat_id, &message).await {
+        Ok(status) => Ok(status),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+#[tauri::command]
+pub async fn mark_chat_read(
+    id: String,
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+) -> Result<String, AppError> {
+    let wfm = wfm.lock()?.clone();
+    match wfm.chat().mark_read(&id).await {
+        Ok(chat_id) => Ok(chat_id),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+#[tauri::command]
+pub async fn start_chat(
+    user_name: String,
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+) -> Result<ChatData, AppError> {
+    let wfm = wfm.lock()?.clone();
+    match wfm.chat().start_chat(&user_name).await {
+        Ok(chat) => Ok(chat),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+#[tauri::command]
+pub fn get_chat_outbox(
+    outbox: tauri::State<'_, Arc<Mutex<ChatOutbox>>>,
+) -> Result<Vec<OutboxMessage>, AppError> {
+    let outbox = outbox.lock()?.clone();
+    outbox.get_messages()
+}
+#[tauri::command]
+pub fn delete_chat_outbox_message(
+    id: String,
+    outbox: tauri::State<'_, Arc<Mutex<ChatOutbox>>>,
+) -> Result<(), AppError> {
+    let outbox = outbox.lock()?.clone();
+    outbox.remove(&id)
+}
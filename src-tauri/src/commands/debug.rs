@@ -1,10 +1,15 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 
 use once_cell::sync::Lazy;
 
 use crate::{
     debug::DebugClient,
     error::{self, AppError},
+    http_recorder::{HttpRecorder, RecorderStatus},
+    wfm_client::client::WFMClient,
 };
 
 // Create a static variable to store the log file name
@@ -38,3 +43,54 @@ pub async fn reset_data(
     debug.reset_data(reset_type).await?;
     Ok(())
 }
+
+#[tauri::command]
+pub fn start_http_recording(
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+) -> Result<RecorderStatus, AppError> {
+    let recorder = wfm.lock()?.recorder();
+    match recorder.start_recording(HttpRecorder::new_session_path()) {
+        Ok(_) => Ok(recorder.get_status()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn start_http_replay(
+    path: String,
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+) -> Result<RecorderStatus, AppError> {
+    let recorder = wfm.lock()?.recorder();
+    match recorder.start_replay(PathBuf::from(path)) {
+        Ok(_) => Ok(recorder.get_status()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+/// Stops recording or replaying, returns the path of the written session.
+#[tauri::command]
+pub fn stop_http_recorder(
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+) -> Result<Option<String>, AppError> {
+    let recorder = wfm.lock()?.recorder();
+    match recorder.stop() {
+        Ok(path) => Ok(path.map(|path| path.to_string_lossy().to_string())),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_http_recorder_status(
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+) -> Result<RecorderStatus, AppError> {
+    Ok(wfm.lock()?.recorder().get_status())
+}
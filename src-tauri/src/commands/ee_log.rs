@@ -0,0 +1,57 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use once_cell::sync::Lazy;
+
+use crate::{
+    error::{self, AppError},
+    helper,
+    wf_ee_log_parser::{client::EELogParser, modules::backfill::BackfillPreview},
+};
+use serde_json::json;
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_ee_log.log".to_string()));
+
+#[tauri::command]
+pub async fn preview_ee_log_backfill(
+    path: Option<String>,
+    from_date: Option<String>,
+    to_date: Option<String>,
+    ee_log: tauri::State<'_, Arc<Mutex<EELogParser>>>,
+) -> Result<BackfillPreview, AppError> {
+    let ee_log = ee_log.lock()?.clone();
+    match ee_log
+        .backfill()
+        .preview(path.map(PathBuf::from), from_date, to_date)
+        .await
+    {
+        Ok(preview) => Ok(preview),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn commit_ee_log_backfill(
+    ee_log: tauri::State<'_, Arc<Mutex<EELogParser>>>,
+) -> Result<usize, AppError> {
+    let ee_log = ee_log.lock()?.clone();
+    match ee_log.backfill().commit().await {
+        Ok(count) => {
+            helper::send_message_to_window(
+                "EELogParser:Backfill",
+                Some(json!({ "imported": count })),
+            );
+            Ok(count)
+        }
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use once_cell::sync::Lazy;
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> =
+    Lazy::new(|| Mutex::new("command_import_export.log".to_string()));
+
+use crate::{
+    database::{
+        client::DBClient,
+        modules::import_export::{
+            DataFormat, DataTable, ImportExportModule, ImportMode, ImportReport,
+        },
+    },
+    error::{self, AppError},
+};
+
+/// Returns the path the rows were written to.
+#[tauri::command]
+pub async fn export_data(
+    table: DataTable,
+    format: DataFormat,
+    path: Option<String>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<String, AppError> {
+    let db = db.lock()?.clone();
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => ImportExportModule::new_export_path(table, format),
+    };
+    match db.import_export().export(table, format, path.clone()).await {
+        Ok(_) => Ok(path.to_string_lossy().to_string()),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn import_data(
+    table: DataTable,
+    path: String,
+    mapping: Option<HashMap<String, String>>,
+    mode: ImportMode,
+    dry_run: bool,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<ImportReport, AppError> {
+    let db = db.lock()?.clone();
+    match db
+        .import_export()
+        .import(
+            table,
+            PathBuf::from(path),
+            mapping.unwrap_or_default(),
+            mode,
+            dry_run,
+        )
+        .await
+    {
+        Ok(report) => Ok(report),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            Err(e)
+        }
+    }
+}
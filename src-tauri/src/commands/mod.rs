@@ -1,10 +1,15 @@
 pub mod auctions;
 pub mod auth;
+pub mod backup;
 pub mod base;
 pub mod debug;
+pub mod ee_log;
+pub mod import_export;
 pub mod live_scraper;
 pub mod orders;
 pub mod price_scraper;
+pub mod profile;
 pub mod stock;
 pub mod chat;
+pub mod trade;
 pub mod transaction;
@@ -0,0 +1,45 @@
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> =
+    Lazy::new(|| Mutex::new("command_profile.log".to_string()));
+
+use crate::{
+    error::{self, AppError},
+    wfm_client::{
+        client::WFMClient,
+        modules::profile::{UserReview, UserSummary},
+    },
+};
+
+#[tauri::command]
+pub async fn get_user_summary(
+    ingame_name: String,
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+) -> Result<UserSummary, AppError> {
+    let wfm = wfm.lock()?.clone();
+    match wfm.profile().get_summary(&ingame_name).await {
+        Ok(summary) => Ok(summary),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+#[tauri::command]
+pub async fn get_user_reviews(
+    ingame_name: String,
+    page: i64,
+    wfm: tauri::State<'_, Arc<Mutex<WFMClient>>>,
+) -> Result<Vec<UserReview>, AppError> {
+    let wfm = wfm.lock()?.clone();
+    match wfm.profile().get_reviews(&ingame_name, page).await {
+        Ok(reviews) => Ok(reviews),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
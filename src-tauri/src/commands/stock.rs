@@ -8,10 +8,15 @@ static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_stock.lo
 use crate::{
     database::{
         client::DBClient,
-        modules::{stock_item, stock_riven::MatchRivenStruct},
+        modules::{
+            stock_event::{StockEventSource, StockEventStruct},
+            stock_item,
+            stock_lot::StockLotStruct,
+            stock_riven::MatchRivenStruct,
+        },
     },
-    enums::{LogLevel, OrderType},
-    error::{self, AppError},
+    enums::OrderType,
+    error::{self, AppError, WfmError},
     logger,
     structs::{Order, RivenAttribute},
     wfm_client::client::WFMClient,
@@ -59,7 +64,10 @@ pub async fn create_item_stock(
         .create(&url_name, "item", "buy", quantity, price as i32, rank, None)
         .await
     {
-        Ok(_) => {
+        Ok(transaction) => {
+            db.stock_lot()
+                .link_transaction(stockitem.id, transaction.id)
+                .await?;
             // Send Close Event to Warframe Market API if enabled
             if !settings.live_scraper.stock_item.report_to_wfm {
                 return Ok(serde_json::to_value(stockitem).unwrap());
@@ -112,6 +120,36 @@ pub async fn update_item_stock(
     }
 }
 #[tauri::command]
+pub async fn get_item_stock_lots(
+    id: i64,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Vec<StockLotStruct>, AppError> {
+    let db = db.lock()?.clone();
+    match db.stock_lot().get_by_stock_item(id).await {
+        Ok(lots) => Ok(lots),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            Err(e)
+        }
+    }
+}
+/// The status and price changes of a stock row, `stock_type` is "item" or "riven".
+#[tauri::command]
+pub async fn get_stock_timeline(
+    stock_type: String,
+    id: i64,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<Vec<StockEventStruct>, AppError> {
+    let db = db.lock()?.clone();
+    match db.stock_event().get_timeline(&stock_type, id).await {
+        Ok(events) => Ok(events),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            Err(e)
+        }
+    }
+}
+#[tauri::command]
 pub async fn delete_item_stock(
     id: i64,
     db: tauri::State<'_, Arc<Mutex<DBClient>>>,
@@ -174,7 +212,12 @@ pub async fn sell_item_stock(
     let settings = settings.lock()?.clone();
 
     // Sell Item in Stock DB
-    let invantory = match db.stock_item().sell_item(id, quantity).await {
+    let invantory = match db
+        .stock_item()
+        .with_source(StockEventSource::User, Some(&format!("Sold for {}", price)))
+        .sell_item(id, quantity, &settings.live_scraper.stock_item.lot_order)
+        .await
+    {
         Ok(invantory) => invantory,
         Err(e) => {
             error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
@@ -248,11 +291,11 @@ pub async fn sell_item_stock(
             }
             Err(e) => {
                 error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
-                if e.log_level() != LogLevel::Error {
-                    return Err(e);
-                } else {
+                // The order is already gone from warframe.market
+                if let Some(WfmError::NotFound { .. }) = e.wfm_error() {
                     return Ok(json!(invantory.clone()));
                 }
+                return Err(e);
             }
         }
     } else {
@@ -349,6 +392,7 @@ pub async fn create_riven_stock(
             re_rolls,
             polarity,
             minium_price,
+            "pending",
         )
         .await
     {
@@ -542,7 +586,10 @@ pub async fn sell_riven_stock(
     let stock = stock.unwrap().clone();
 
     // Delete Riven from Stock
-    db.stock_riven().delete(id).await?;
+    db.stock_riven()
+        .with_source(StockEventSource::User, Some(&format!("Sold for {}", price)))
+        .delete(id)
+        .await?;
 
     let json_stock = serde_json::to_value(&stock).unwrap();
 
@@ -0,0 +1,78 @@
+use crate::{
+    database::{
+        client::DBClient,
+        modules::trade::{TradeFilter, TradePage, TradeStruct},
+    },
+    error::{self, AppError},
+    wf_ee_log_parser::events::helper::parse_date,
+};
+use eyre::eyre;
+use once_cell::sync::Lazy;
+use std::sync::{Arc, Mutex};
+// Create a static variable to store the log file name
+static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_trade.log".to_string()));
+
+#[tauri::command]
+pub async fn get_trades(
+    partner: Option<String>,
+    item: Option<String>,
+    trade_type: Option<String>,
+    from_date: Option<String>,
+    to_date: Option<String>,
+    page: Option<i64>,
+    limit: Option<i64>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<TradePage, AppError> {
+    let db = db.lock()?.clone();
+    let filter = TradeFilter {
+        partner,
+        item,
+        trade_type,
+        from_date: match from_date {
+            Some(date) => Some(parse_date(&date, false)?),
+            None => None,
+        },
+        to_date: match to_date {
+            Some(date) => Some(parse_date(&date, true)?),
+            None => None,
+        },
+    };
+    match db
+        .trade()
+        .get_trades(filter, page.unwrap_or(1), limit.unwrap_or(25))
+        .await
+    {
+        Ok(page) => Ok(page),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_trade(
+    id: i64,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<TradeStruct, AppError> {
+    let db = db.lock()?.clone();
+    match db.trade().get_by_id(id).await? {
+        Some(trade) => Ok(trade),
+        None => Err(AppError::new("Command:Trade", eyre!("Trade not found"))),
+    }
+}
+
+#[tauri::command]
+pub async fn delete_trade(
+    id: i64,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+) -> Result<TradeStruct, AppError> {
+    let db = db.lock()?.clone();
+    match db.trade().delete(id).await {
+        Ok(trade) => Ok(trade),
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            return Err(e);
+        }
+    }
+}
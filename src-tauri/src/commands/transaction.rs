@@ -1,10 +1,22 @@
 use crate::{
-    database::{client::DBClient, modules::transaction::TransactionStruct},
+    database::{
+        client::DBClient,
+        modules::{
+            profit::{CostMethod, ProfitPeriod, ProfitRange, ProfitReport},
+            transaction::TransactionStruct,
+        },
+    },
     error::{self, AppError},
+    logger,
+    price_scraper::PriceScraper,
+    wf_ee_log_parser::events::helper::parse_date,
 };
 use eyre::eyre;
 use once_cell::sync::Lazy;
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 // Create a static variable to store the log file name
 static LOG_FILE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new("command_transaction.log".to_string()));
 
@@ -88,3 +100,57 @@ pub async fn delete_transaction_entry(
         .emit("DELETE", serde_json::to_value(transaction.clone()).unwrap());
     Ok(transaction)
 }
+
+#[tauri::command]
+pub async fn get_profit_report(
+    method: Option<CostMethod>,
+    period: Option<ProfitPeriod>,
+    from_date: Option<String>,
+    to_date: Option<String>,
+    db: tauri::State<'_, Arc<Mutex<DBClient>>>,
+    price_scraper: tauri::State<'_, Arc<Mutex<PriceScraper>>>,
+) -> Result<ProfitReport, AppError> {
+    let db = db.lock()?.clone();
+    let range = ProfitRange {
+        from: match from_date {
+            Some(date) => Some(parse_date(&date, false)?),
+            None => None,
+        },
+        to: match to_date {
+            Some(date) => Some(parse_date(&date, true)?),
+            None => None,
+        },
+    };
+    // Without a price history the open stock is not valued
+    let closed_averages = match price_scraper.lock()?.get_closed_averages() {
+        Ok(closed_averages) => closed_averages,
+        Err(e) => {
+            logger::warning_con("Profit", format!("No closed averages: {}", e.cause()).as_str());
+            HashMap::new()
+        }
+    };
+    match db
+        .profit()
+        .get_report(
+            method.unwrap_or(CostMethod::Fifo),
+            period.unwrap_or(ProfitPeriod::Month),
+            range,
+            closed_averages,
+        )
+        .await
+    {
+        Ok(report) => {
+            if !report.skipped.is_empty() {
+                logger::warning_con(
+                    "Profit",
+                    format!("Transactions with unreadable dates: {:?}", report.skipped).as_str(),
+                );
+            }
+            Ok(report)
+        }
+        Err(e) => {
+            error::create_log_file(LOG_FILE.lock().unwrap().to_owned(), &e);
+            Err(e)
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use std::{
+    fs::{self, File},
+    io::{Read, Write},
+    path::PathBuf,
+};
+
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+const KEYRING_SERVICE: &str = "dev.kenya.quantframe";
+const KEYRING_USER: &str = "warframe.market";
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Credentials {
+    pub email: String,
+    pub password: String,
+}
+
+/// Where the warframe.market credentials are kept when the user asks to stay logged in.
+/// Tests use a file since there is no keyring to talk to.
+#[derive(Clone, Debug)]
+pub enum CredentialStore {
+    Keyring,
+    File(PathBuf),
+}
+
+impl Default for CredentialStore {
+    fn default() -> Self {
+        CredentialStore::Keyring
+    }
+}
+
+impl CredentialStore {
+    fn error(e: impl std::fmt::Display) -> AppError {
+        AppError::new("CredentialStore", eyre!(e.to_string()))
+    }
+
+    fn entry() -> Result<keyring::Entry, AppError> {
+        keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).map_err(Self::error)
+    }
+
+    pub fn load(&self) -> Result<Option<Credentials>, AppError> {
+        let content = match self {
+            CredentialStore::Keyring => match Self::entry()?.get_password() {
+                Ok(content) => content,
+                Err(keyring::Error::NoEntry) => return Ok(None),
+                Err(e) => return Err(Self::error(e)),
+            },
+            CredentialStore::File(path) => {
+                if !path.exists() {
+                    return Ok(None);
+                }
+                let mut content = String::new();
+                File::open(path)
+                    .and_then(|mut file| file.read_to_string(&mut content))
+                    .map_err(Self::error)?;
+                content
+            }
+        };
+        let credentials = serde_json::from_str(&content).map_err(Self::error)?;
+        Ok(Some(credentials))
+    }
+
+    pub fn save(&self, credentials: &Credentials) -> Result<(), AppError> {
+        let content = serde_json::to_string(credentials).map_err(Self::error)?;
+        match self {
+            CredentialStore::Keyring => Self::entry()?.set_password(&content).map_err(Self::error),
+            CredentialStore::File(path) => File::create(path)
+                .and_then(|mut file| file.write_all(content.as_bytes()))
+                .map_err(Self::error),
+        }
+    }
+
+    pub fn clear(&self) -> Result<(), AppError> {
+        match self {
+            CredentialStore::Keyring => match Self::entry()?.delete_password() {
+                Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+                Err(e) => Err(Self::error(e)),
+            },
+            CredentialStore::File(path) => {
+                if path.exists() {
+                    fs::remove_file(path).map_err(Self::error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
@@ -19,7 +19,7 @@ use crate::{
     wfm_client::client::WFMClient,
 };
 
-use super::modules::{ transaction::TransactionModule, stock_item::StockItemModule, stock_riven::StockRivenModule};
+use super::modules::{ backup::BackupModule, import_export::ImportExportModule, migration::MigrationModule, profit::ProfitModule, transaction::TransactionModule, stock_event::{StockEventModule, StockEventSource}, stock_item::StockItemModule, stock_lot::StockLotModule, stock_riven::StockRivenModule, trade::TradeModule, whisper::WhisperModule};
 #[derive(Clone, Debug)]
 pub struct DBClient {
     pub log_file: String,
@@ -62,21 +62,95 @@ impl DBClient {
         self.stock_item().initialize().await?;
         self.stock_riven().initialize().await?;
         self.transaction().initialize().await?;
+        self.whisper().initialize().await?;
+        self.trade().initialize().await?;
+        self.migrations().run().await?;
         Ok(true)
     }
     pub fn get_connection(&self) -> Arc<Mutex<Pool<Sqlite>>> {
         self.connection.clone()
     }
 
+    pub fn backups(&self) -> BackupModule {
+        BackupModule { client: self }
+    }
+
+    pub fn import_export(&self) -> ImportExportModule {
+        ImportExportModule { client: self }
+    }
+
+    pub fn migrations(&self) -> MigrationModule {
+        MigrationModule { client: self }
+    }
+
     pub fn transaction(&self) -> TransactionModule {
         TransactionModule { client: self }
     }
 
+    pub fn profit(&self) -> ProfitModule {
+        ProfitModule { client: self }
+    }
+
     pub fn stock_item(&self) -> StockItemModule {
-        StockItemModule { client: self }
+        StockItemModule {
+            client: self,
+            source: StockEventSource::User,
+            reason: None,
+        }
+    }
+
+    pub fn stock_event(&self) -> StockEventModule {
+        StockEventModule { client: self }
+    }
+
+    pub fn stock_lot(&self) -> StockLotModule {
+        StockLotModule { client: self }
     }
 
     pub fn stock_riven(&self) -> StockRivenModule {
-        StockRivenModule { client: self }
+        StockRivenModule {
+            client: self,
+            source: StockEventSource::User,
+            reason: None,
+        }
+    }
+
+    pub fn whisper(&self) -> WhisperModule {
+        WhisperModule { client: self }
+    }
+
+    pub fn trade(&self) -> TradeModule {
+        TradeModule { client: self }
+    }
+}
+
+#[cfg(test)]
+impl DBClient {
+    /// A new database in memory with every table and migration, for tests.
+    pub async fn in_memory(cache: Arc<Mutex<CacheClient>>, wfm: Arc<Mutex<WFMClient>>) -> Self {
+        // Every connection to ":memory:" opens its own database, the pool keeps just one
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let db = DBClient {
+            log_file: "db.log".to_string(),
+            connection: Arc::new(Mutex::new(pool)),
+            cache,
+            wfm,
+        };
+        db.stock_item().initialize().await.unwrap();
+        db.stock_riven().initialize().await.unwrap();
+        db.transaction().initialize().await.unwrap();
+        db.whisper().initialize().await.unwrap();
+        db.trade().create_tables().await.unwrap();
+        db.migrations()
+            .run_migrations(super::modules::migration::migrations(), false)
+            .await
+            .unwrap();
+        db
     }
 }
@@ -0,0 +1,539 @@
+use std::{
+    ffi::{c_char, CStr, CString},
+    path::PathBuf,
+    ptr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use eyre::eyre;
+use libsqlite3_sys as ffi;
+use serde::Serialize;
+use serde_json::json;
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqliteConnection},
+    Connection, Row,
+};
+
+use crate::{
+    database::client::DBClient, error::AppError, helper, logger, settings::SettingsState,
+};
+
+const DATABASE_NAME: &[u8] = b"main\0";
+// How often the schedule looks at the age of the newest backup
+const SCHEDULE_INTERVAL: Duration = Duration::from_secs(600);
+// How long a copy waits for a locked database before it gives up
+const BUSY_RETRIES: u32 = 100;
+const BUSY_SLEEP_MS: i32 = 50;
+// How many backups taken before migrations, restores, resets and imports are kept of each kind
+const AUTOMATIC_KEEP: usize = 5;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BackupInfo {
+    pub name: String,
+    pub path: String,
+    // What made the backup, like "scheduled", "manual" or "reset"
+    pub reason: String,
+    pub size: u64,
+    pub created: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct IntegrityIssue {
+    pub kind: String,
+    pub table: String,
+    pub id: Option<i64>,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct IntegrityReport {
+    pub ok: bool,
+    // The rows of `PRAGMA integrity_check`, just "ok" for a healthy file
+    pub integrity_check: Vec<String>,
+    pub issues: Vec<IntegrityIssue>,
+}
+
+fn backup_error(e: impl std::fmt::Display) -> AppError {
+    AppError::new("DatabaseBackup", eyre!(e.to_string()))
+}
+
+unsafe fn sqlite_error(handle: *mut ffi::sqlite3) -> AppError {
+    let message = CStr::from_ptr(ffi::sqlite3_errmsg(handle))
+        .to_string_lossy()
+        .to_string();
+    backup_error(message)
+}
+
+/// Opens a database file outside of the pool.
+unsafe fn open_file(path: &PathBuf, flags: i32) -> Result<*mut ffi::sqlite3, AppError> {
+    let path = CString::new(path.to_string_lossy().as_bytes()).map_err(backup_error)?;
+    let mut handle = ptr::null_mut();
+    if ffi::sqlite3_open_v2(path.as_ptr(), &mut handle, flags, ptr::null()) != ffi::SQLITE_OK {
+        let e = sqlite_error(handle);
+        ffi::sqlite3_close(handle);
+        return Err(e);
+    }
+    Ok(handle)
+}
+
+/// Copies every page of `source` to `destination` with SQLite's online backup.
+unsafe fn copy_database(
+    source: *mut ffi::sqlite3,
+    destination: *mut ffi::sqlite3,
+) -> Result<(), AppError> {
+    let name = DATABASE_NAME.as_ptr() as *const c_char;
+    let backup = ffi::sqlite3_backup_init(destination, name, source, name);
+    if backup.is_null() {
+        return Err(sqlite_error(destination));
+    }
+    let mut retries = 0;
+    loop {
+        match ffi::sqlite3_backup_step(backup, -1) {
+            ffi::SQLITE_DONE => break,
+            ffi::SQLITE_OK | ffi::SQLITE_BUSY | ffi::SQLITE_LOCKED if retries < BUSY_RETRIES => {
+                retries += 1;
+                ffi::sqlite3_sleep(BUSY_SLEEP_MS);
+            }
+            ffi::SQLITE_OK | ffi::SQLITE_BUSY | ffi::SQLITE_LOCKED => {
+                ffi::sqlite3_backup_finish(backup);
+                return Err(backup_error(format!(
+                    "The database stayed locked for {} ms",
+                    BUSY_RETRIES as i32 * BUSY_SLEEP_MS
+                )));
+            }
+            _ => {
+                ffi::sqlite3_backup_finish(backup);
+                return Err(sqlite_error(destination));
+            }
+        }
+    }
+    if ffi::sqlite3_backup_finish(backup) != ffi::SQLITE_OK {
+        return Err(sqlite_error(destination));
+    }
+    Ok(())
+}
+
+pub struct BackupModule<'a> {
+    pub client: &'a DBClient,
+}
+
+impl<'a> BackupModule<'a> {
+    pub fn get_folder() -> Result<PathBuf, AppError> {
+        // Tests keep their backups away from the real ones
+        let folder = if cfg!(test) {
+            std::env::temp_dir().join("qf_mock_backups")
+        } else {
+            helper::get_app_roaming_path().join("backups")
+        };
+        if !folder.exists() {
+            std::fs::create_dir_all(&folder).map_err(backup_error)?;
+        }
+        Ok(folder)
+    }
+
+    /// Copies the live database to "backups/quantframe_{reason}_{date}_{time}.sqlite",
+    /// the time has milliseconds so backups made one after another do not overwrite each other.
+    /// Manual and scheduled backups are left alone, of any other reason only the newest are kept.
+    pub async fn create(&self, reason: &str) -> Result<BackupInfo, AppError> {
+        let backup = self.create_file(reason).await?;
+        if reason != "manual" && reason != "scheduled" {
+            self.rotate(reason, AUTOMATIC_KEEP)?;
+        }
+        Ok(backup)
+    }
+
+    async fn create_file(&self, reason: &str) -> Result<BackupInfo, AppError> {
+        let folder = Self::get_folder()?;
+        let path = loop {
+            let path = folder.join(format!(
+                "quantframe_{}_{}.sqlite",
+                reason,
+                chrono::Local::now().format("%Y%m%d_%H%M%S%3f")
+            ));
+            if !path.exists() {
+                break path;
+            }
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        };
+        let pool = self.client.connection.lock().unwrap().clone();
+        let mut connection = pool.acquire().await.map_err(backup_error)?;
+        {
+            let mut handle = connection.lock_handle().await.map_err(backup_error)?;
+            let source = handle.as_raw_handle().as_ptr();
+            unsafe {
+                let destination = open_file(
+                    &path,
+                    ffi::SQLITE_OPEN_READWRITE | ffi::SQLITE_OPEN_CREATE,
+                )?;
+                let result = copy_database(source, destination);
+                ffi::sqlite3_close(destination);
+                result?;
+            }
+        }
+        logger::info_con(
+            "DatabaseBackup",
+            format!("Database backed up to {:?}", path).as_str(),
+        );
+        Self::get_info(&path)
+    }
+
+    fn get_info(path: &PathBuf) -> Result<BackupInfo, AppError> {
+        let metadata = std::fs::metadata(path).map_err(backup_error)?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        // quantframe_{reason}_{date}_{time}.sqlite
+        let stem = name.trim_end_matches(".sqlite");
+        let parts: Vec<&str> = stem.split('_').collect();
+        let reason = if parts.len() > 3 {
+            parts[1..parts.len() - 2].join("_")
+        } else {
+            "unknown".to_string()
+        };
+        let created: chrono::DateTime<chrono::Local> = metadata
+            .modified()
+            .map(|modified| modified.into())
+            .unwrap_or_else(|_| chrono::Local::now());
+        Ok(BackupInfo {
+            name,
+            path: path.to_string_lossy().to_string(),
+            reason,
+            size: metadata.len(),
+            created: created.to_rfc3339(),
+        })
+    }
+
+    /// The backups in the backup folder, newest first.
+    pub fn list(&self) -> Result<Vec<BackupInfo>, AppError> {
+        let mut backups = vec![];
+        for entry in std::fs::read_dir(Self::get_folder()?).map_err(backup_error)? {
+            let path = entry.map_err(backup_error)?.path();
+            let is_backup = path
+                .file_name()
+                .map(|name| name.to_string_lossy())
+                .map_or(false, |name| {
+                    name.starts_with("quantframe_") && name.ends_with(".sqlite")
+                });
+            if is_backup {
+                backups.push(Self::get_info(&path)?);
+            }
+        }
+        backups.sort_by(|a, b| b.created.cmp(&a.created));
+        Ok(backups)
+    }
+
+    /// The reason without what changes between backups of the same kind, "migration_v3" is a "migration".
+    fn get_kind(reason: &str) -> &str {
+        if reason.starts_with("migration_v") {
+            "migration"
+        } else {
+            reason
+        }
+    }
+
+    /// Deletes the oldest backups made for the kind of `reason` until `keep` are left.
+    pub fn rotate(&self, reason: &str, keep: usize) -> Result<Vec<BackupInfo>, AppError> {
+        let kind = Self::get_kind(reason);
+        let removed: Vec<BackupInfo> = self
+            .list()?
+            .into_iter()
+            .filter(|backup| Self::get_kind(&backup.reason) == kind)
+            .skip(keep)
+            .collect();
+        for backup in removed.iter() {
+            std::fs::remove_file(&backup.path).map_err(backup_error)?;
+        }
+        Ok(removed)
+    }
+
+    /// Runs `PRAGMA integrity_check` on a backup without opening it for writing.
+    async fn check_file(backup: &BackupInfo) -> Result<(), AppError> {
+        let options = SqliteConnectOptions::new()
+            .filename(&backup.path)
+            .read_only(true);
+        let mut connection = SqliteConnection::connect_with(&options)
+            .await
+            .map_err(backup_error)?;
+        let result: Vec<String> = sqlx::query("PRAGMA integrity_check")
+            .fetch_all(&mut connection)
+            .await
+            .map_err(backup_error)?
+            .iter()
+            .map(|row| row.get::<String, _>(0))
+            .collect();
+        connection.close().await.map_err(backup_error)?;
+        if result != vec!["ok".to_string()] {
+            return Err(backup_error(format!(
+                "Backup {} is damaged: {}",
+                backup.name,
+                result.join(", ")
+            )));
+        }
+        Ok(())
+    }
+
+    /// Replaces the live database with the backup called `name`.
+    /// The backup is checked and the current database backed up first,
+    /// and migrated after in case the backup is older.
+    pub async fn restore(&self, name: &str) -> Result<BackupInfo, AppError> {
+        let backup = self
+            .list()?
+            .into_iter()
+            .find(|backup| backup.name == name)
+            .ok_or(backup_error(format!("Backup {} not found", name)))?;
+        Self::check_file(&backup).await?;
+        // Rotated once the backup was copied, it may be one of the oldest
+        self.create_file("pre_restore").await?;
+
+        let pool = self.client.connection.lock().unwrap().clone();
+        {
+            let mut connection = pool.acquire().await.map_err(backup_error)?;
+            let mut handle = connection.lock_handle().await.map_err(backup_error)?;
+            let destination = handle.as_raw_handle().as_ptr();
+            unsafe {
+                let source = open_file(&PathBuf::from(&backup.path), ffi::SQLITE_OPEN_READONLY)?;
+                let result = copy_database(source, destination);
+                ffi::sqlite3_close(source);
+                result?;
+            }
+        }
+
+        self.rotate("pre_restore", AUTOMATIC_KEEP)?;
+        self.client.migrations().run().await?;
+        self.client
+            .stock_item()
+            .emit("SET", json!(self.client.stock_item().get_items().await?));
+        self.client
+            .stock_riven()
+            .emit("SET", json!(self.client.stock_riven().get_rivens().await?));
+        self.client
+            .transaction()
+            .emit("SET", json!(self.client.transaction().get_items().await?));
+        logger::info_con(
+            "DatabaseBackup",
+            format!("Database restored from {}", backup.name).as_str(),
+        );
+        Ok(backup)
+    }
+
+    /// Checks the file with `PRAGMA integrity_check` and the stock against the lots and transactions.
+    pub async fn check_integrity(&self) -> Result<IntegrityReport, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let integrity_check: Vec<String> = sqlx::query("PRAGMA integrity_check")
+            .fetch_all(&connection)
+            .await
+            .map_err(backup_error)?
+            .iter()
+            .map(|row| row.get::<String, _>(0))
+            .collect();
+
+        let mut issues = vec![];
+        for row in sqlx::query("PRAGMA foreign_key_check")
+            .fetch_all(&connection)
+            .await
+            .map_err(backup_error)?
+        {
+            let table: String = row.get(0);
+            let parent: String = row.get(2);
+            issues.push(IntegrityIssue {
+                kind: "foreign_key".to_string(),
+                table,
+                id: row.get(1),
+                message: format!("References a missing row in {}", parent),
+            });
+        }
+
+        let checks = [
+            (
+                "lot_mismatch",
+                "stock_item",
+                "SELECT s.id, s.url || ' owns ' || s.owned || ' but its lots hold ' || COALESCE(SUM(l.remaining), 0)
+                FROM stock_item s LEFT JOIN stock_lot l ON l.stock_item_id = s.id
+                GROUP BY s.id HAVING s.owned != COALESCE(SUM(l.remaining), 0)",
+            ),
+            (
+                "empty_stock",
+                "stock_item",
+                "SELECT id, url || ' has no units left' FROM stock_item WHERE owned <= 0",
+            ),
+            (
+                "orphan_lot",
+                "stock_lot",
+                "SELECT l.id, 'Linked to missing transaction ' || l.transaction_id
+                FROM stock_lot l LEFT JOIN \"transaction\" t ON t.id = l.transaction_id
+                WHERE l.transaction_id IS NOT NULL AND t.id IS NULL",
+            ),
+            (
+                "missing_buy",
+                "stock_item",
+                "SELECT s.id, s.url || ' was bought for platinum but has no buy transaction'
+                FROM stock_item s WHERE s.price > 0 AND NOT EXISTS (
+                    SELECT 1 FROM \"transaction\" t
+                    WHERE t.url = s.url AND t.item_type = 'item' AND t.transaction_type = 'buy'
+                )",
+            ),
+            (
+                "oversold",
+                "transaction",
+                "SELECT NULL, url || ' was sold ' || SUM(CASE WHEN transaction_type = 'sell' THEN quantity ELSE 0 END)
+                    || ' times but bought ' || SUM(CASE WHEN transaction_type = 'buy' THEN quantity ELSE 0 END)
+                FROM \"transaction\" WHERE item_type = 'item' GROUP BY url
+                HAVING SUM(CASE WHEN transaction_type = 'sell' THEN quantity ELSE 0 END)
+                    > SUM(CASE WHEN transaction_type = 'buy' THEN quantity ELSE 0 END)",
+            ),
+        ];
+        for (kind, table, sql) in checks {
+            for row in sqlx::query(sql)
+                .fetch_all(&connection)
+                .await
+                .map_err(backup_error)?
+            {
+                issues.push(IntegrityIssue {
+                    kind: kind.to_string(),
+                    table: table.to_string(),
+                    id: row.get(0),
+                    message: row.get(1),
+                });
+            }
+        }
+
+        Ok(IntegrityReport {
+            ok: integrity_check == vec!["ok".to_string()],
+            integrity_check,
+            issues,
+        })
+    }
+}
+
+/// Backs the database up every `backup.interval_hours` and keeps the newest `backup.keep`.
+pub fn start_schedule(db: Arc<Mutex<DBClient>>, settings: Arc<Mutex<SettingsState>>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            // The first look happens after the database was initialized
+            tokio::time::sleep(SCHEDULE_INTERVAL).await;
+            let backup_settings = settings.lock().unwrap().backup.clone();
+            let db = db.lock().unwrap().clone();
+            if backup_settings.enabled {
+                let newest = db
+                    .backups()
+                    .list()
+                    .ok()
+                    .and_then(|backups| {
+                        backups
+                            .into_iter()
+                            .find(|backup| backup.reason == "scheduled")
+                    })
+                    .and_then(|backup| chrono::DateTime::parse_from_rfc3339(&backup.created).ok());
+                let due = newest.map_or(true, |created| {
+                    chrono::Local::now().signed_duration_since(created)
+                        >= chrono::Duration::hours(backup_settings.interval_hours.max(1))
+                });
+                if due {
+                    let result = match db.backups().create("scheduled").await {
+                        Ok(_) => db
+                            .backups()
+                            .rotate("scheduled", backup_settings.keep.max(1) as usize)
+                            .map(|_| ()),
+                        Err(e) => Err(e),
+                    };
+                    if let Err(e) = result {
+                        logger::warning(
+                            "DatabaseBackup",
+                            format!("Scheduled backup failed: {}", e.cause()).as_str(),
+                            true,
+                            Some(db.log_file.as_str()),
+                        );
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::File,
+        time::{Duration, SystemTime},
+    };
+
+    use super::{BackupModule, AUTOMATIC_KEEP};
+    use crate::wfm_client::mock_server::MockWfmServer;
+
+    #[tokio::test]
+    async fn keeps_the_newest_automatic_backups_of_each_kind() {
+        let server = MockWfmServer::start().await;
+        let db = server.database().await;
+        // Older backups, one made an hour before the next
+        let folder = BackupModule::get_folder().unwrap();
+        let mut names = vec!["quantframe_manual_20240101_000000.sqlite".to_string()];
+        for version in 0..6 {
+            names.push(format!(
+                "quantframe_migration_v{}_20240101_00000{}.sqlite",
+                version, version
+            ));
+        }
+        for (age, name) in names.iter().rev().enumerate() {
+            let file = File::create(folder.join(name)).unwrap();
+            file.set_modified(SystemTime::now() - Duration::from_secs(3600 * (age as u64 + 1)))
+                .unwrap();
+        }
+
+        let backup = db.backups().create("migration_v6").await.unwrap();
+
+        let left: Vec<String> = db
+            .backups()
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|backup| backup.name)
+            .filter(|name| name == &backup.name || names.contains(name))
+            .collect();
+        for name in left.iter() {
+            std::fs::remove_file(folder.join(name)).unwrap();
+        }
+        assert_eq!(left.len(), AUTOMATIC_KEEP + 1);
+        assert_eq!(left[0], backup.name);
+        assert!(left.contains(&names[0]));
+        // The two oldest migrations are gone
+        assert!(!left.contains(&names[1]));
+        assert!(!left.contains(&names[2]));
+    }
+
+    #[tokio::test]
+    async fn backups_made_one_after_another_do_not_collide() {
+        let server = MockWfmServer::start().await;
+        let db = server.database().await;
+
+        let first = db.backups().create("manual").await.unwrap();
+        let second = db.backups().create("manual").await.unwrap();
+
+        std::fs::remove_file(&first.path).unwrap();
+        std::fs::remove_file(&second.path).unwrap();
+        assert_ne!(first.name, second.name);
+        assert_eq!(first.reason, "manual");
+    }
+
+    #[tokio::test]
+    async fn does_not_restore_a_damaged_backup() {
+        let server = MockWfmServer::start().await;
+        server.add_item("primed_flow_id", "primed_flow", "Primed Flow");
+        let db = server.database().await;
+        db.stock_item()
+            .create("primed_flow", 1, 10.0, None, 0, None)
+            .await
+            .unwrap();
+        let port = server.address.rsplit(':').next().unwrap_or_default();
+        let name = format!("quantframe_damaged{}_20240101_000000000.sqlite", port);
+        let path = BackupModule::get_folder().unwrap().join(&name);
+        std::fs::write(&path, "not a database").unwrap();
+
+        let result = db.backups().restore(&name).await;
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+        assert_eq!(db.stock_item().get_items().await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,840 @@
+use std::{collections::HashMap, fs::File, path::PathBuf};
+
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    database::client::DBClient,
+    error::AppError,
+    helper,
+    structs::{Item, RivenAttribute, RivenTypeInfo},
+    wf_ee_log_parser::events::helper::parse_date,
+};
+
+use super::{
+    stock_event::StockEventSource,
+    stock_riven::{MatchRivenStruct, StockRivenStruct},
+    transaction::TransactionStruct,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataTable {
+    StockItem,
+    StockRiven,
+    Transaction,
+}
+
+impl DataTable {
+    pub fn as_str(&self) -> &str {
+        match self {
+            DataTable::StockItem => "stock_item",
+            DataTable::StockRiven => "stock_riven",
+            DataTable::Transaction => "transaction",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataFormat {
+    Csv,
+    Json,
+}
+
+impl DataFormat {
+    /// Guesses the format from the file extension, anything but ".json" is read as CSV.
+    pub fn from_path(path: &PathBuf) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => DataFormat::Json,
+            _ => DataFormat::Csv,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    // Stock items already in stock get the imported quantity added at the imported price,
+    // duplicate rivens and transactions are skipped
+    Merge,
+    // The table is emptied before the rows are added
+    Replace,
+}
+
+/// A row that could not be imported, `row` is the line of the data starting at 1.
+#[derive(Clone, Debug, Serialize)]
+pub struct UnresolvedRow {
+    pub row: usize,
+    pub name: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ImportReport {
+    pub table: DataTable,
+    pub mode: ImportMode,
+    pub dry_run: bool,
+    pub total: usize,
+    pub imported: usize,
+    pub skipped: usize,
+    // The rows deleted first in replace mode
+    pub replaced: usize,
+    // The backup taken before the rows were deleted
+    pub backup: Option<String>,
+    pub unresolved: Vec<UnresolvedRow>,
+}
+
+/// The rows already stored, loaded once per import to find the duplicates.
+#[derive(Default)]
+struct StoredRows {
+    rivens: Vec<StockRivenStruct>,
+    transactions: Vec<TransactionStruct>,
+}
+
+/// Whether two rivens have the same rolls.
+fn same_attributes(a: &[RivenAttribute], b: &[RivenAttribute]) -> bool {
+    a.len() == b.len()
+        && a.iter().all(|x| {
+            b.iter()
+                .any(|y| x.url_name == y.url_name && x.positive == y.positive && x.value == y.value)
+        })
+}
+
+enum ImportRow {
+    Item {
+        url: String,
+        quantity: i32,
+        // For one unit, like the exported price
+        price: f64,
+        minium_price: Option<i32>,
+        rank: i32,
+        sub_type: Option<String>,
+    },
+    Riven {
+        url: String,
+        mod_name: String,
+        price: f64,
+        rank: i32,
+        attributes: Vec<RivenAttribute>,
+        match_riven: Option<MatchRivenStruct>,
+        mastery_rank: i32,
+        re_rolls: i32,
+        polarity: String,
+        minium_price: Option<i32>,
+    },
+    Transaction {
+        url: String,
+        item_type: String,
+        transaction_type: String,
+        quantity: i32,
+        price: i32,
+        rank: i32,
+        created: Option<String>,
+        properties: Option<Value>,
+    },
+}
+
+fn io_error(e: impl std::fmt::Display) -> AppError {
+    AppError::new("ImportExport", eyre!(e.to_string()))
+}
+
+/// The first of `keys` that has a non empty value.
+fn get_value<'r>(row: &'r Map<String, Value>, keys: &[&str]) -> Option<&'r Value> {
+    keys.iter()
+        .filter_map(|key| row.get(*key))
+        .find(|value| !value.is_null() && value.as_str().map_or(true, |s| !s.trim().is_empty()))
+}
+
+fn get_str(row: &Map<String, Value>, keys: &[&str]) -> Option<String> {
+    get_value(row, keys).map(|value| match value {
+        Value::String(s) => s.trim().to_string(),
+        value => value.to_string(),
+    })
+}
+
+fn get_f64(row: &Map<String, Value>, keys: &[&str]) -> Option<f64> {
+    match get_value(row, keys)? {
+        Value::Number(number) => number.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn get_i32(row: &Map<String, Value>, keys: &[&str]) -> Option<i32> {
+    get_f64(row, keys).map(|value| value.round() as i32)
+}
+
+/// A transaction date as RFC 3339 in UTC, like the dates the app writes.
+/// Dates without a time start at midnight and times without a zone are read as UTC.
+pub fn parse_created(input: &str) -> Option<String> {
+    if let Ok(date) = parse_date(input, false) {
+        return Some(date.to_rfc3339());
+    }
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+        .map(|date| Utc.from_utc_datetime(&date).to_rfc3339())
+}
+
+/// Values that are JSON in a CSV cell, like riven attributes.
+fn get_json<T: serde::de::DeserializeOwned>(row: &Map<String, Value>, keys: &[&str]) -> Option<T> {
+    match get_value(row, keys)? {
+        Value::String(s) => serde_json::from_str(s).ok(),
+        value => serde_json::from_value(value.clone()).ok(),
+    }
+}
+
+pub struct ImportExportModule<'a> {
+    pub client: &'a DBClient,
+}
+
+impl<'a> ImportExportModule<'a> {
+    /// Where exports go when no path is given, like "exports/stock_item_20240101_120000.csv".
+    pub fn new_export_path(table: DataTable, format: DataFormat) -> PathBuf {
+        let folder = helper::get_app_roaming_path().join("exports");
+        if !folder.exists() {
+            std::fs::create_dir_all(&folder).unwrap();
+        }
+        folder.join(format!(
+            "{}_{}.{}",
+            table.as_str(),
+            chrono::Local::now().format("%Y%m%d_%H%M%S"),
+            match format {
+                DataFormat::Csv => "csv",
+                DataFormat::Json => "json",
+            }
+        ))
+    }
+
+    async fn get_rows(&self, table: DataTable) -> Result<Vec<Value>, AppError> {
+        let rows = match table {
+            DataTable::StockItem => json!(self.client.stock_item().get_items().await?),
+            DataTable::StockRiven => json!(self.client.stock_riven().get_rivens().await?),
+            DataTable::Transaction => json!(self.client.transaction().get_items().await?),
+        };
+        Ok(rows.as_array().cloned().unwrap_or_default())
+    }
+
+    /// Writes every row of `table` to `path`, nested values are JSON strings in a CSV.
+    pub async fn export(
+        &self,
+        table: DataTable,
+        format: DataFormat,
+        path: PathBuf,
+    ) -> Result<usize, AppError> {
+        let rows = self.get_rows(table).await?;
+        match format {
+            DataFormat::Json => {
+                let file = File::create(&path).map_err(io_error)?;
+                serde_json::to_writer_pretty(file, &rows).map_err(io_error)?;
+            }
+            DataFormat::Csv => {
+                let mut writer = csv::Writer::from_path(&path).map_err(io_error)?;
+                let headers: Vec<String> = match rows.first() {
+                    Some(Value::Object(row)) => row.keys().cloned().collect(),
+                    _ => vec![],
+                };
+                writer.write_record(&headers).map_err(io_error)?;
+                for row in rows.iter() {
+                    let record: Vec<String> = headers
+                        .iter()
+                        .map(|header| match row.get(header) {
+                            None | Some(Value::Null) => "".to_string(),
+                            Some(Value::String(s)) => s.clone(),
+                            Some(value) => value.to_string(),
+                        })
+                        .collect();
+                    writer.write_record(&record).map_err(io_error)?;
+                }
+                writer.flush().map_err(io_error)?;
+            }
+        }
+        Ok(rows.len())
+    }
+
+    /// Reads the rows of a file and renames their columns by `mapping` (file column to field).
+    fn read_rows(
+        path: &PathBuf,
+        format: DataFormat,
+        mapping: &HashMap<String, String>,
+    ) -> Result<Vec<Map<String, Value>>, AppError> {
+        let rows: Vec<Map<String, Value>> = match format {
+            DataFormat::Json => {
+                let file = File::open(path).map_err(io_error)?;
+                let rows: Vec<Value> = serde_json::from_reader(file).map_err(io_error)?;
+                rows.into_iter()
+                    .filter_map(|row| row.as_object().cloned())
+                    .collect()
+            }
+            DataFormat::Csv => {
+                let mut reader = csv::ReaderBuilder::new()
+                    .flexible(true)
+                    .trim(csv::Trim::All)
+                    .from_path(path)
+                    .map_err(io_error)?;
+                let headers = reader.headers().map_err(io_error)?.clone();
+                let mut rows = vec![];
+                for record in reader.records() {
+                    let record = record.map_err(io_error)?;
+                    let mut row = Map::new();
+                    for (header, value) in headers.iter().zip(record.iter()) {
+                        row.insert(header.to_string(), Value::String(value.to_string()));
+                    }
+                    rows.push(row);
+                }
+                rows
+            }
+        };
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|(key, value)| (mapping.get(&key).cloned().unwrap_or(key), value))
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// Finds an item by its url name, its English name or a translated name.
+    fn resolve_item(&self, name: &str) -> Result<Option<Item>, AppError> {
+        let cache = self.client.cache.lock()?.clone();
+        let items = cache.items().get_types()?;
+        if let Some(item) = items
+            .iter()
+            .find(|item| item.url_name == name || item.item_name.eq_ignore_ascii_case(name))
+        {
+            return Ok(Some(item.clone()));
+        }
+        cache.items().find_type_by_localized_name(name)
+    }
+
+    fn resolve_riven(&self, name: &str) -> Result<Option<RivenTypeInfo>, AppError> {
+        let cache = self.client.cache.lock()?.clone();
+        let rivens = cache.riven().get_types()?;
+        Ok(rivens
+            .into_iter()
+            .find(|riven| riven.url_name == name || riven.item_name.eq_ignore_ascii_case(name)))
+    }
+
+    fn resolve_row(
+        &self,
+        table: DataTable,
+        mode: ImportMode,
+        row: &Map<String, Value>,
+    ) -> Result<Result<ImportRow, (Option<String>, String)>, AppError> {
+        match table {
+            DataTable::StockItem => {
+                let Some(name) = get_str(row, &["url", "url_name", "name", "item_name"]) else {
+                    return Ok(Err((None, "The row has no item name".to_string())));
+                };
+                let Some(item) = self.resolve_item(&name)? else {
+                    return Ok(Err((Some(name), "Item not found".to_string())));
+                };
+                Ok(Ok(ImportRow::Item {
+                    url: item.url_name,
+                    quantity: get_i32(row, &["owned", "quantity"]).unwrap_or(1).max(1),
+                    price: get_f64(row, &["price"]).unwrap_or(0.0),
+                    minium_price: get_i32(row, &["minium_price", "minimum_price"]),
+                    rank: get_i32(row, &["rank"]).unwrap_or(0),
+                    sub_type: get_str(row, &["sub_type"]),
+                }))
+            }
+            DataTable::StockRiven => {
+                let Some(name) = get_str(row, &["weapon_url", "weapon_name", "url", "name"]) else {
+                    return Ok(Err((None, "The row has no weapon name".to_string())));
+                };
+                let Some(weapon) = self.resolve_riven(&name)? else {
+                    return Ok(Err((Some(name), "Riven weapon not found".to_string())));
+                };
+                let Some(mod_name) = get_str(row, &["mod_name"]) else {
+                    return Ok(Err((Some(name), "The row has no mod name".to_string())));
+                };
+                Ok(Ok(ImportRow::Riven {
+                    url: weapon.url_name,
+                    mod_name,
+                    price: get_f64(row, &["price"]).unwrap_or(0.0),
+                    rank: get_i32(row, &["rank", "mod_rank"]).unwrap_or(0),
+                    attributes: get_json(row, &["attributes"]).unwrap_or_default(),
+                    match_riven: get_json(row, &["match_riven"]),
+                    mastery_rank: get_i32(row, &["mastery_rank"]).unwrap_or(8),
+                    re_rolls: get_i32(row, &["re_rolls"]).unwrap_or(0),
+                    polarity: get_str(row, &["polarity"]).unwrap_or_default(),
+                    minium_price: get_i32(row, &["minium_price", "minimum_price"]),
+                }))
+            }
+            DataTable::Transaction => {
+                let Some(name) = get_str(row, &["url", "url_name", "name", "item_name"]) else {
+                    return Ok(Err((None, "The row has no item name".to_string())));
+                };
+                let item_type = get_str(row, &["item_type"]).unwrap_or("item".to_string());
+                let url = if item_type == "riven" {
+                    self.resolve_riven(&name)?.map(|riven| riven.url_name)
+                } else {
+                    self.resolve_item(&name)?.map(|item| item.url_name)
+                };
+                let Some(url) = url else {
+                    return Ok(Err((Some(name), format!("{} not found", item_type))));
+                };
+                let transaction_type = get_str(row, &["transaction_type", "type"])
+                    .unwrap_or_default()
+                    .to_lowercase();
+                if transaction_type != "buy" && transaction_type != "sell" {
+                    return Ok(Err((
+                        Some(name),
+                        format!("Unknown transaction type \"{}\"", transaction_type),
+                    )));
+                }
+                let created = match get_str(row, &["created", "date", "datetime"]) {
+                    Some(created) => match parse_created(&created) {
+                        Some(created) => Some(created),
+                        None => {
+                            return Ok(Err((Some(name), format!("Unknown date \"{}\"", created))))
+                        }
+                    },
+                    // Without a date the row can not be told apart from one imported before
+                    None if mode == ImportMode::Merge => {
+                        return Ok(Err((
+                            Some(name),
+                            "The row has no date, merging needs it to find duplicates".to_string(),
+                        )))
+                    }
+                    None => None,
+                };
+                Ok(Ok(ImportRow::Transaction {
+                    url,
+                    item_type,
+                    transaction_type,
+                    quantity: get_i32(row, &["quantity"]).unwrap_or(1).max(1),
+                    price: get_i32(row, &["price"]).unwrap_or(0),
+                    rank: get_i32(row, &["rank"]).unwrap_or(0),
+                    created,
+                    properties: get_json(row, &["properties"]),
+                }))
+            }
+        }
+    }
+
+    async fn clear_table(&self, table: DataTable) -> Result<(), AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        sqlx::query(format!("DELETE FROM \"{}\"", table.as_str()).as_str())
+            .execute(&connection)
+            .await
+            .map_err(io_error)?;
+        Ok(())
+    }
+
+    /// Adds a resolved row, returns false when it was skipped as a duplicate.
+    async fn import_row(&self, row: ImportRow, stored: &mut StoredRows) -> Result<bool, AppError> {
+        match row {
+            ImportRow::Item {
+                url,
+                quantity,
+                price,
+                minium_price,
+                rank,
+                sub_type,
+            } => {
+                // An item already in stock gets the quantity as a new lot at the imported price
+                let stock_item = self.client.stock_item().with_source(StockEventSource::Import, None);
+                let item = stock_item
+                    .create(
+                        &url,
+                        quantity,
+                        price * quantity as f64,
+                        minium_price,
+                        rank,
+                        sub_type.as_deref(),
+                    )
+                    .await?;
+                if minium_price.is_some() && item.minium_price != minium_price {
+                    stock_item
+                        .update_by_id(item.id, None, None, minium_price, None, None, None)
+                        .await?;
+                }
+                Ok(true)
+            }
+            ImportRow::Riven {
+                url,
+                mod_name,
+                price,
+                rank,
+                attributes,
+                match_riven,
+                mastery_rank,
+                re_rolls,
+                polarity,
+                minium_price,
+            } => {
+                let duplicate = stored.rivens.iter().any(|riven| {
+                    riven.weapon_url == url
+                        && riven.mod_name.eq_ignore_ascii_case(&mod_name)
+                        && riven.rank == rank
+                        && riven.re_rolls == re_rolls
+                        && same_attributes(&riven.attributes, &attributes)
+                });
+                if duplicate {
+                    return Ok(false);
+                }
+                let riven = self
+                    .client
+                    .stock_riven()
+                    .with_source(StockEventSource::Import, None)
+                    .create(
+                        None,
+                        &url,
+                        &mod_name,
+                        price,
+                        rank,
+                        attributes,
+                        match_riven,
+                        mastery_rank,
+                        re_rolls,
+                        &polarity,
+                        minium_price,
+                        "pending",
+                    )
+                    .await?;
+                stored.rivens.push(riven);
+                Ok(true)
+            }
+            ImportRow::Transaction {
+                url,
+                item_type,
+                transaction_type,
+                quantity,
+                price,
+                rank,
+                created,
+                properties,
+            } => {
+                let duplicate = stored.transactions.iter().any(|transaction| {
+                    transaction.url == url
+                        && transaction.transaction_type == transaction_type
+                        && transaction.quantity == quantity
+                        && transaction.price == price
+                        && created.as_ref() == Some(&transaction.created)
+                });
+                if duplicate {
+                    return Ok(false);
+                }
+                let mut transaction = self
+                    .client
+                    .transaction()
+                    .create(
+                        &url,
+                        &item_type,
+                        &transaction_type,
+                        quantity,
+                        price,
+                        rank,
+                        properties,
+                    )
+                    .await?;
+                if let Some(created) = created {
+                    self.client
+                        .transaction()
+                        .set_created(transaction.id, &created)
+                        .await?;
+                    transaction.created = created;
+                }
+                stored.transactions.push(transaction);
+                Ok(true)
+            }
+        }
+    }
+
+    /// Imports the rows of `path` into `table`.
+    /// A dry run only resolves the rows and reports the ones that would not be imported.
+    pub async fn import(
+        &self,
+        table: DataTable,
+        path: PathBuf,
+        mapping: HashMap<String, String>,
+        mode: ImportMode,
+        dry_run: bool,
+    ) -> Result<ImportReport, AppError> {
+        let rows = Self::read_rows(&path, DataFormat::from_path(&path), &mapping)?;
+        let mut report = ImportReport {
+            table,
+            mode,
+            dry_run,
+            total: rows.len(),
+            imported: 0,
+            skipped: 0,
+            replaced: 0,
+            backup: None,
+            unresolved: vec![],
+        };
+
+        let mut resolved = vec![];
+        for (index, row) in rows.iter().enumerate() {
+            match self.resolve_row(table, mode, row)? {
+                Ok(row) => resolved.push(row),
+                Err((name, reason)) => report.unresolved.push(UnresolvedRow {
+                    row: index + 1,
+                    name,
+                    reason,
+                }),
+            }
+        }
+
+        if mode == ImportMode::Replace {
+            report.replaced = self.get_rows(table).await?.len();
+        }
+        if dry_run {
+            report.imported = resolved.len();
+            return Ok(report);
+        }
+
+        if mode == ImportMode::Replace {
+            // The rows are added one by one, a failed import is undone by restoring this
+            report.backup = Some(self.client.backups().create("import").await?.name);
+            self.clear_table(table).await?;
+        }
+        let mut stored = StoredRows::default();
+        match table {
+            DataTable::StockItem => {}
+            DataTable::StockRiven => stored.rivens = self.client.stock_riven().get_rivens().await?,
+            DataTable::Transaction => {
+                stored.transactions = self.client.transaction().get_items().await?
+            }
+        }
+        for row in resolved {
+            match self.import_row(row, &mut stored).await {
+                Ok(true) => report.imported += 1,
+                Ok(false) => report.skipped += 1,
+                Err(e) => match &report.backup {
+                    Some(backup) => {
+                        return Err(AppError::new(
+                            "ImportExport",
+                            eyre!(
+                                "Import stopped after {} rows: {}, restore {} to undo it",
+                                report.imported + report.skipped,
+                                e.cause(),
+                                backup
+                            ),
+                        ))
+                    }
+                    None => return Err(e),
+                },
+            }
+        }
+
+        match table {
+            DataTable::StockItem => self
+                .client
+                .stock_item()
+                .emit("SET", json!(self.client.stock_item().get_items().await?)),
+            DataTable::StockRiven => self
+                .client
+                .stock_riven()
+                .emit("SET", json!(self.client.stock_riven().get_rivens().await?)),
+            DataTable::Transaction => self
+                .client
+                .transaction()
+                .emit("SET", json!(self.client.transaction().get_items().await?)),
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{same_attributes, DataTable, ImportMode};
+    use crate::structs::RivenAttribute;
+    use crate::wfm_client::mock_server::MockWfmServer;
+
+    // Writes `contents` to a temporary file named after the mock server
+    fn write_file(server: &MockWfmServer, name: &str, contents: &str) -> std::path::PathBuf {
+        let port = server.address.rsplit(':').next().unwrap_or_default();
+        let path = std::env::temp_dir().join(format!("qf_mock_{}_{}", port, name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn normalizes_transaction_dates() {
+        let server = MockWfmServer::start().await;
+        server.add_item("primed_flow_id", "primed_flow", "Primed Flow");
+        let db = server.database().await;
+        let path = write_file(
+            &server,
+            "transactions.csv",
+            "name,transaction_type,quantity,price,created\n\
+             primed_flow,buy,1,10,2024-01-01\n\
+             Primed Flow,buy,1,12,2024-01-02 10:30:00\n\
+             primed_flow,sell,1,20,01/02/2024\n\
+             primed_flow,sell,1,25,2024-01-03T12:00:00+02:00\n",
+        );
+
+        let report = db
+            .import_export()
+            .import(
+                DataTable::Transaction,
+                path,
+                HashMap::new(),
+                ImportMode::Merge,
+                false,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(report.imported, 3);
+        assert_eq!(report.unresolved.len(), 1);
+        assert_eq!(report.unresolved[0].row, 3);
+        assert_eq!(report.unresolved[0].reason, "Unknown date \"01/02/2024\"");
+        let mut created: Vec<String> = db
+            .transaction()
+            .get_items()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|transaction| transaction.created)
+            .collect();
+        created.sort();
+        assert_eq!(
+            created,
+            vec![
+                "2024-01-01T00:00:00+00:00",
+                "2024-01-02T10:30:00+00:00",
+                "2024-01-03T10:00:00+00:00",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn backs_the_table_up_before_replacing_it() {
+        let server = MockWfmServer::start().await;
+        server.add_item("primed_flow_id", "primed_flow", "Primed Flow");
+        server.add_item("primed_reach_id", "primed_reach", "Primed Reach");
+        let db = server.database().await;
+        db.stock_item()
+            .create("primed_flow", 2, 20.0, None, 0, None)
+            .await
+            .unwrap();
+        let path = write_file(
+            &server,
+            "stock.json",
+            r#"[{"url": "primed_reach", "owned": 1, "price": 30}]"#,
+        );
+
+        let report = db
+            .import_export()
+            .import(
+                DataTable::StockItem,
+                path,
+                HashMap::new(),
+                ImportMode::Replace,
+                false,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(report.replaced, 1);
+        assert_eq!(report.imported, 1);
+        let backup = db
+            .backups()
+            .list()
+            .unwrap()
+            .into_iter()
+            .find(|backup| Some(&backup.name) == report.backup.as_ref())
+            .unwrap();
+        assert_eq!(backup.reason, "import");
+        std::fs::remove_file(&backup.path).unwrap();
+        let items = db.stock_item().get_items().await.unwrap();
+        assert_eq!(
+            items
+                .iter()
+                .map(|item| item.url.as_str())
+                .collect::<Vec<_>>(),
+            vec!["primed_reach"]
+        );
+    }
+
+    #[tokio::test]
+    async fn merging_adds_the_quantity_at_the_imported_price() {
+        let server = MockWfmServer::start().await;
+        server.add_item("primed_flow_id", "primed_flow", "Primed Flow");
+        let db = server.database().await;
+        let item = db
+            .stock_item()
+            .create("primed_flow", 2, 20.0, None, 0, None)
+            .await
+            .unwrap();
+        let path = write_file(
+            &server,
+            "merge.json",
+            r#"[{"url": "primed_flow", "owned": 2, "price": 30, "minium_price": 25}]"#,
+        );
+
+        db.import_export()
+            .import(
+                DataTable::StockItem,
+                path,
+                HashMap::new(),
+                ImportMode::Merge,
+                false,
+            )
+            .await
+            .unwrap();
+
+        let stored = db.stock_item().get_by_id(item.id).await.unwrap().unwrap();
+        assert_eq!((stored.owned, stored.price), (4, 20.0));
+        assert_eq!(stored.minium_price, Some(25));
+        assert_eq!(
+            db.stock_lot().get_aggregate(item.id).await.unwrap(),
+            (4, 20.0)
+        );
+    }
+
+    #[tokio::test]
+    async fn merging_flags_transactions_without_a_date() {
+        let server = MockWfmServer::start().await;
+        server.add_item("primed_flow_id", "primed_flow", "Primed Flow");
+        let db = server.database().await;
+        let contents = "name,transaction_type,quantity,price,created\n\
+                        primed_flow,buy,1,10,2024-01-01\n\
+                        primed_flow,buy,1,10,\n";
+
+        for _ in 0..2 {
+            let path = write_file(&server, "undated.csv", contents);
+            let report = db
+                .import_export()
+                .import(
+                    DataTable::Transaction,
+                    path,
+                    HashMap::new(),
+                    ImportMode::Merge,
+                    false,
+                )
+                .await
+                .unwrap();
+            assert_eq!(report.unresolved.len(), 1);
+            assert_eq!(report.unresolved[0].row, 2);
+        }
+        // The dated row is only imported once
+        assert_eq!(db.transaction().get_items().await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rivens_with_other_rolls_are_not_duplicates() {
+        let attribute = |url_name: &str, value: f64| RivenAttribute {
+            positive: true,
+            value,
+            url_name: url_name.to_string(),
+            match_type: None,
+        };
+        let rolls = vec![attribute("critical_chance", 120.5), attribute("multishot", 80.0)];
+        let shuffled = vec![attribute("multishot", 80.0), attribute("critical_chance", 120.5)];
+        let rerolled = vec![attribute("critical_chance", 120.5), attribute("damage", 80.0)];
+
+        assert!(same_attributes(&rolls, &shuffled));
+        assert!(!same_attributes(&rolls, &rerolled));
+        assert!(!same_attributes(&rolls, &rolls[..1]));
+    }
+}
@@ -0,0 +1,777 @@
+use eyre::eyre;
+use sea_query::{ColumnDef, Index, SqliteQueryBuilder, Table, Value};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::{Connection, Row, SqliteConnection};
+
+use crate::{database::client::DBClient, error::AppError, logger};
+
+use super::{
+    stock_event, stock_item::StockItem, stock_lot, stock_riven::StockRiven, transaction::Transaction,
+};
+
+/// A change to the schema or the data, run inside the transaction of its migration.
+#[derive(Clone, Debug)]
+pub enum MigrationStep {
+    /// Any statement, like a backfill `UPDATE`
+    Sql(String),
+    /// Adds a column unless it is there already, databases from before the migrations
+    /// may have it from the old `ALTER TABLE` checks
+    AddColumn {
+        table: String,
+        column: String,
+        sql: String,
+    },
+    RenameColumn {
+        table: String,
+        from: String,
+        to: String,
+    },
+    /// SQLite can not change the type of a column, the table is copied to a new one.
+    /// Foreign keys are off while migrating, so rows referring to the table are kept
+    ChangeColumnType {
+        table: String,
+        column: String,
+        sql_type: String,
+    },
+    /// A `CREATE INDEX IF NOT EXISTS` statement
+    CreateIndex(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct Migration {
+    pub version: i64,
+    pub name: String,
+    pub steps: Vec<MigrationStep>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SchemaVersion {
+    pub version: i64,
+    pub name: String,
+    pub applied: String,
+}
+
+/// The migrations in the order they are applied, never change one that was released,
+/// add a new one with the next version instead.
+pub fn migrations() -> Vec<Migration> {
+    vec![
+        Migration {
+            version: 1,
+            name: "stock_item_columns".to_string(),
+            steps: vec![
+                add_column(
+                    "stock_item",
+                    "minium_price",
+                    Table::alter()
+                        .table(StockItem::Table)
+                        .add_column(
+                            ColumnDef::new(StockItem::MiniumPrice)
+                                .integer()
+                                .default(Value::Int(None)),
+                        )
+                        .to_string(SqliteQueryBuilder),
+                ),
+                add_column(
+                    "stock_item",
+                    "hidden",
+                    Table::alter()
+                        .table(StockItem::Table)
+                        .add_column(
+                            ColumnDef::new(StockItem::Hidden)
+                                .boolean()
+                                .not_null()
+                                .default(Value::Bool(Some(false))),
+                        )
+                        .to_string(SqliteQueryBuilder),
+                ),
+                add_column(
+                    "stock_item",
+                    "status",
+                    Table::alter()
+                        .table(StockItem::Table)
+                        .add_column(
+                            ColumnDef::new(StockItem::Status)
+                                .string()
+                                .not_null()
+                                .default("pending"),
+                        )
+                        .to_string(SqliteQueryBuilder),
+                ),
+                MigrationStep::Sql(
+                    "UPDATE stock_item SET status = 'pending' WHERE status IS NULL OR status = ''"
+                        .to_string(),
+                ),
+            ],
+        },
+        Migration {
+            version: 2,
+            name: "stock_riven_columns".to_string(),
+            steps: vec![
+                add_column(
+                    "stock_riven",
+                    "match_riven",
+                    Table::alter()
+                        .table(StockRiven::Table)
+                        .add_column(
+                            ColumnDef::new(StockRiven::MatchRiven)
+                                .json()
+                                .not_null()
+                                .default(json!({})),
+                        )
+                        .to_string(SqliteQueryBuilder),
+                ),
+                add_column(
+                    "stock_riven",
+                    "minium_price",
+                    Table::alter()
+                        .table(StockRiven::Table)
+                        .add_column(
+                            ColumnDef::new(StockRiven::MiniumPrice)
+                                .integer()
+                                .default(Value::Int(None)),
+                        )
+                        .to_string(SqliteQueryBuilder),
+                ),
+                add_column(
+                    "stock_riven",
+                    "status",
+                    Table::alter()
+                        .table(StockRiven::Table)
+                        .add_column(
+                            ColumnDef::new(StockRiven::Status)
+                                .string()
+                                .not_null()
+                                .default("pending"),
+                        )
+                        .to_string(SqliteQueryBuilder),
+                ),
+            ],
+        },
+        Migration {
+            version: 3,
+            name: "indices".to_string(),
+            steps: vec![
+                MigrationStep::CreateIndex(
+                    Index::create()
+                        .if_not_exists()
+                        .name("idx_transaction_url")
+                        .table(Transaction::Table)
+                        .col(Transaction::Url)
+                        .to_string(SqliteQueryBuilder),
+                ),
+                MigrationStep::CreateIndex(
+                    Index::create()
+                        .if_not_exists()
+                        .name("idx_transaction_created")
+                        .table(Transaction::Table)
+                        .col(Transaction::Created)
+                        .to_string(SqliteQueryBuilder),
+                ),
+                MigrationStep::CreateIndex(
+                    Index::create()
+                        .if_not_exists()
+                        .name("idx_stock_item_url")
+                        .table(StockItem::Table)
+                        .col(StockItem::Url)
+                        .to_string(SqliteQueryBuilder),
+                ),
+            ],
+        },
+        Migration {
+            version: 4,
+            name: "stock_lot".to_string(),
+            steps: vec![
+                MigrationStep::Sql(stock_lot::create_table_sql()),
+                MigrationStep::CreateIndex(
+                    "CREATE INDEX IF NOT EXISTS idx_stock_lot_stock_item_id ON stock_lot (stock_item_id)"
+                        .to_string(),
+                ),
+                // Every stock item starts with one lot holding what it owns at its average price
+                MigrationStep::Sql(
+                    "INSERT INTO stock_lot (stock_item_id, quantity, remaining, unit_price, acquired)
+                    SELECT id, owned, owned, price, created FROM stock_item WHERE owned > 0"
+                        .to_string(),
+                ),
+            ],
+        },
+        Migration {
+            version: 5,
+            name: "stock_event".to_string(),
+            steps: vec![
+                MigrationStep::Sql(stock_event::create_table_sql()),
+                MigrationStep::CreateIndex(
+                    "CREATE INDEX IF NOT EXISTS idx_stock_event_stock ON stock_event (stock_type, stock_id)"
+                        .to_string(),
+                ),
+            ],
+        },
+        Migration {
+            version: 6,
+            name: "stock_event_append_only".to_string(),
+            steps: stock_event::append_only_triggers_sql()
+                .into_iter()
+                .map(MigrationStep::Sql)
+                .collect(),
+        },
+    ]
+}
+
+fn add_column(table: &str, column: &str, sql: String) -> MigrationStep {
+    MigrationStep::AddColumn {
+        table: table.to_string(),
+        column: column.to_string(),
+        sql,
+    }
+}
+
+fn db_error(e: impl std::fmt::Display) -> AppError {
+    AppError::new("Database", eyre!(e.to_string()))
+}
+
+async fn column_names(
+    connection: &mut SqliteConnection,
+    table: &str,
+) -> Result<Vec<String>, AppError> {
+    let rows = sqlx::query(format!("PRAGMA table_info(\"{}\")", table).as_str())
+        .fetch_all(&mut *connection)
+        .await
+        .map_err(db_error)?;
+    Ok(rows.iter().map(|row| row.get::<String, _>("name")).collect())
+}
+
+// Words that end the type of a column definition and start its constraints
+const CONSTRAINT_KEYWORDS: [&str; 11] = [
+    "CONSTRAINT",
+    "PRIMARY",
+    "NOT",
+    "NULL",
+    "UNIQUE",
+    "CHECK",
+    "DEFAULT",
+    "COLLATE",
+    "REFERENCES",
+    "GENERATED",
+    "AS",
+];
+
+/// Splits the body of a `CREATE TABLE` at the commas between its definitions.
+fn split_definitions(body: &str) -> Vec<String> {
+    let mut definitions = vec![];
+    let mut current = String::new();
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    for c in body.chars() {
+        match quote {
+            Some(end) if c == end => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' | '`' => quote = Some(c),
+                '[' => quote = Some(']'),
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    definitions.push(current.trim().to_string());
+                    current.clear();
+                    continue;
+                }
+                _ => {}
+            },
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        definitions.push(current.trim().to_string());
+    }
+    definitions
+}
+
+/// The name a definition starts with as written and without its quotes.
+fn split_name(definition: &str) -> (&str, String, &str) {
+    let end = match definition.chars().next() {
+        Some(quote @ ('"' | '`')) => definition[1..].find(quote).map(|i| i + 2),
+        Some('[') => definition.find(']').map(|i| i + 1),
+        _ => definition.find(char::is_whitespace),
+    }
+    .unwrap_or(definition.len());
+    let name = &definition[..end];
+    let unquoted = name.trim_matches(|c| matches!(c, '"' | '`' | '[' | ']'));
+    (name, unquoted.to_string(), &definition[end..])
+}
+
+/// Gives the column of `definition` the type `sql_type` and keeps its constraints.
+fn retype_column(definition: &str, sql_type: &str) -> String {
+    let (name, _, rest) = split_name(definition);
+    let constraints = rest
+        .split_whitespace()
+        .find(|word| {
+            let word = word.split('(').next().unwrap_or_default().to_uppercase();
+            CONSTRAINT_KEYWORDS.contains(&word.as_str())
+        })
+        // The word is a slice of `rest`, so the constraints start where it does
+        .map(|word| &rest[word.as_ptr() as usize - rest.as_ptr() as usize..])
+        .unwrap_or_default();
+    format!("{} {} {}", name, sql_type, constraints)
+        .trim_end()
+        .to_string()
+}
+
+/// The `CREATE TABLE` statement of `table` for `new_table`, with `column` of type `sql_type`.
+/// Everything else, like table constraints and foreign keys, is kept as it was written.
+fn retype_create_sql(
+    create_sql: &str,
+    new_table: &str,
+    column: &str,
+    sql_type: &str,
+) -> Result<String, AppError> {
+    let (Some(open), Some(close)) = (create_sql.find('('), create_sql.rfind(')')) else {
+        return Err(db_error(format!("Can not read {}", create_sql)));
+    };
+    let mut found = false;
+    let definitions: Vec<String> = split_definitions(&create_sql[open + 1..close])
+        .into_iter()
+        .map(|definition| {
+            if split_name(&definition).1.eq_ignore_ascii_case(column) {
+                found = true;
+                retype_column(&definition, sql_type)
+            } else {
+                definition
+            }
+        })
+        .collect();
+    if !found {
+        return Err(db_error(format!("Column {} not found", column)));
+    }
+    Ok(format!(
+        "CREATE TABLE \"{}\" ({}){}",
+        new_table,
+        definitions.join(", "),
+        &create_sql[close + 1..]
+    ))
+}
+
+/// Copies `table` to a new table where `column` has `sql_type`, keeping its rows,
+/// constraints, indices and triggers.
+async fn change_column_type(
+    connection: &mut SqliteConnection,
+    table: &str,
+    column: &str,
+    sql_type: &str,
+) -> Result<(), AppError> {
+    let create_sql: String =
+        sqlx::query("SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?")
+            .bind(table)
+            .fetch_one(&mut *connection)
+            .await
+            .map_err(db_error)?
+            .get("sql");
+    // Dropping the table drops these too
+    let dependents: Vec<String> = sqlx::query(
+        "SELECT sql FROM sqlite_master WHERE type IN ('index', 'trigger') AND tbl_name = ? AND sql IS NOT NULL",
+    )
+    .bind(table)
+    .fetch_all(&mut *connection)
+    .await
+    .map_err(db_error)?
+    .iter()
+    .map(|row| row.get("sql"))
+    .collect();
+    let selects: Vec<String> = column_names(connection, table)
+        .await?
+        .iter()
+        .map(|name| {
+            if name.eq_ignore_ascii_case(column) {
+                format!("CAST(\"{}\" AS {})", name, sql_type)
+            } else {
+                format!("\"{}\"", name)
+            }
+        })
+        .collect();
+
+    let new_table = format!("{}_migration", table);
+    let statements = [
+        retype_create_sql(&create_sql, &new_table, column, sql_type)?,
+        format!(
+            "INSERT INTO \"{}\" SELECT {} FROM \"{}\"",
+            new_table,
+            selects.join(", "),
+            table
+        ),
+        format!("DROP TABLE \"{}\"", table),
+        format!("ALTER TABLE \"{}\" RENAME TO \"{}\"", new_table, table),
+    ];
+    for sql in statements.iter().chain(dependents.iter()) {
+        sqlx::query(sql)
+            .execute(&mut *connection)
+            .await
+            .map_err(db_error)?;
+    }
+    Ok(())
+}
+
+async fn run_step(connection: &mut SqliteConnection, step: &MigrationStep) -> Result<(), AppError> {
+    let sql = match step {
+        MigrationStep::Sql(sql) | MigrationStep::CreateIndex(sql) => sql.clone(),
+        MigrationStep::AddColumn { table, column, sql } => {
+            if column_names(connection, table).await?.contains(column) {
+                return Ok(());
+            }
+            sql.clone()
+        }
+        MigrationStep::RenameColumn { table, from, to } => format!(
+            "ALTER TABLE \"{}\" RENAME COLUMN \"{}\" TO \"{}\"",
+            table, from, to
+        ),
+        MigrationStep::ChangeColumnType {
+            table,
+            column,
+            sql_type,
+        } => return change_column_type(connection, table, column, sql_type).await,
+    };
+    sqlx::query(&sql)
+        .execute(&mut *connection)
+        .await
+        .map_err(db_error)?;
+    Ok(())
+}
+
+pub struct MigrationModule<'a> {
+    pub client: &'a DBClient,
+}
+
+impl<'a> MigrationModule<'a> {
+    async fn create_version_table(&self) -> Result<(), AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS schema_version (
+                version INTEGER PRIMARY KEY NOT NULL,
+                name TEXT NOT NULL,
+                applied TEXT NOT NULL
+            )",
+        )
+        .execute(&connection)
+        .await
+        .map_err(db_error)?;
+        Ok(())
+    }
+
+    pub async fn get_version(&self) -> Result<i64, AppError> {
+        self.create_version_table().await?;
+        let connection = self.client.connection.lock().unwrap().clone();
+        let version: Option<i64> = sqlx::query("SELECT MAX(version) AS version FROM schema_version")
+            .fetch_one(&connection)
+            .await
+            .map_err(db_error)?
+            .get("version");
+        Ok(version.unwrap_or(0))
+    }
+
+    pub async fn get_history(&self) -> Result<Vec<SchemaVersion>, AppError> {
+        self.create_version_table().await?;
+        let connection = self.client.connection.lock().unwrap().clone();
+        let rows = sqlx::query("SELECT version, name, applied FROM schema_version ORDER BY version")
+            .fetch_all(&connection)
+            .await
+            .map_err(db_error)?;
+        Ok(rows
+            .iter()
+            .map(|row| SchemaVersion {
+                version: row.get("version"),
+                name: row.get("name"),
+                applied: row.get("applied"),
+            })
+            .collect())
+    }
+
+    /// Applies the migrations newer than the database, each in its own transaction.
+    /// Returns the version the database is at afterwards.
+    pub async fn run(&self) -> Result<i64, AppError> {
+        self.run_migrations(migrations(), true).await
+    }
+
+    pub async fn run_migrations(
+        &self,
+        migrations: Vec<Migration>,
+        backup: bool,
+    ) -> Result<i64, AppError> {
+        let version = self.get_version().await?;
+        let pending: Vec<Migration> = migrations
+            .into_iter()
+            .filter(|migration| migration.version > version)
+            .collect();
+        if pending.is_empty() {
+            return Ok(version);
+        }
+
+        let backup_path = if backup {
+            let name = format!("migration_v{}", version);
+            Some(self.client.backups().create(&name).await?.path)
+        } else {
+            None
+        };
+
+        // Copying a table drops the old one, which would delete the rows referring to it.
+        // The pragma does nothing inside a transaction, so it is set on the connection around them
+        let pool = self.client.connection.lock().unwrap().clone();
+        let mut connection = pool.acquire().await.map_err(db_error)?;
+        let foreign_keys: i64 = sqlx::query("PRAGMA foreign_keys")
+            .fetch_one(&mut *connection)
+            .await
+            .map_err(db_error)?
+            .get(0);
+        sqlx::query("PRAGMA foreign_keys = OFF")
+            .execute(&mut *connection)
+            .await
+            .map_err(db_error)?;
+        let result = apply_migrations(&mut connection, pending, foreign_keys == 1).await;
+        sqlx::query(format!("PRAGMA foreign_keys = {}", foreign_keys).as_str())
+            .execute(&mut *connection)
+            .await
+            .map_err(db_error)?;
+        result.map_err(|(migration, e)| {
+            AppError::new(
+                "Database",
+                eyre!(
+                    "Migration {} {} failed: {}, a backup is at {:?}",
+                    migration.version,
+                    migration.name,
+                    e.cause(),
+                    backup_path
+                ),
+            )
+        })
+    }
+}
+
+/// Applies each migration in its own transaction, returns the version the database is at
+/// or the migration that failed. Dropping the transaction rolls a failed migration back.
+async fn apply_migrations(
+    connection: &mut SqliteConnection,
+    pending: Vec<Migration>,
+    check_foreign_keys: bool,
+) -> Result<i64, (Migration, AppError)> {
+    let mut version = 0;
+    for migration in pending {
+        let result = apply_migration(connection, &migration, check_foreign_keys).await;
+        if let Err(e) = result {
+            return Err((migration, e));
+        }
+        version = migration.version;
+        logger::info_con(
+            "Database",
+            format!("Applied migration {} {}", migration.version, migration.name).as_str(),
+        );
+    }
+    Ok(version)
+}
+
+async fn apply_migration(
+    connection: &mut SqliteConnection,
+    migration: &Migration,
+    check_foreign_keys: bool,
+) -> Result<(), AppError> {
+    let mut tx = connection.begin().await.map_err(db_error)?;
+    for step in migration.steps.iter() {
+        run_step(&mut tx, step).await?;
+    }
+    if check_foreign_keys {
+        let violations = sqlx::query("PRAGMA foreign_key_check")
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        if !violations.is_empty() {
+            return Err(db_error(format!(
+                "{} rows would break their foreign keys",
+                violations.len()
+            )));
+        }
+    }
+    sqlx::query("INSERT INTO schema_version (version, name, applied) VALUES (?, ?, ?)")
+        .bind(migration.version)
+        .bind(&migration.name)
+        .bind(chrono::Local::now().naive_local().to_string())
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::Row;
+
+    use super::{migrations, retype_create_sql, Migration, MigrationStep};
+    use crate::wfm_client::mock_server::MockWfmServer;
+
+    fn migration(version: i64, steps: Vec<MigrationStep>) -> Migration {
+        Migration {
+            version,
+            name: format!("test_{}", version),
+            steps,
+        }
+    }
+
+    #[tokio::test]
+    async fn applies_each_migration_once() {
+        let server = MockWfmServer::start().await;
+        let db = server.database().await;
+        let latest = migrations().last().unwrap().version;
+
+        assert_eq!(db.migrations().get_version().await.unwrap(), latest);
+        assert_eq!(
+            db.migrations().run_migrations(migrations(), false).await.unwrap(),
+            latest
+        );
+        assert_eq!(db.migrations().get_history().await.unwrap().len(), migrations().len());
+    }
+
+    #[tokio::test]
+    async fn rolls_back_a_failing_migration() {
+        let server = MockWfmServer::start().await;
+        let db = server.database().await;
+        let latest = migrations().last().unwrap().version;
+
+        let failing = migration(
+            latest + 1,
+            vec![
+                MigrationStep::Sql("CREATE TABLE migration_test (id INTEGER)".to_string()),
+                MigrationStep::Sql("NOT A STATEMENT".to_string()),
+            ],
+        );
+        assert!(db.migrations().run_migrations(vec![failing], false).await.is_err());
+
+        assert_eq!(db.migrations().get_version().await.unwrap(), latest);
+        let connection = db.connection.lock().unwrap().clone();
+        let tables = sqlx::query("SELECT name FROM sqlite_master WHERE name = 'migration_test'")
+            .fetch_all(&connection)
+            .await
+            .unwrap();
+        assert!(tables.is_empty());
+    }
+
+    #[tokio::test]
+    async fn changes_column_types_without_losing_referring_rows() {
+        let server = MockWfmServer::start().await;
+        server.add_item("primed_flow_id", "primed_flow", "Primed Flow");
+        let db = server.database().await;
+        db.stock_item()
+            .create("primed_flow", 3, 60.0, None, 0, None)
+            .await
+            .unwrap();
+        let latest = migrations().last().unwrap().version;
+
+        let steps = vec![
+            MigrationStep::ChangeColumnType {
+                table: "stock_item".to_string(),
+                column: "price".to_string(),
+                sql_type: "INTEGER".to_string(),
+            },
+            MigrationStep::RenameColumn {
+                table: "stock_item".to_string(),
+                from: "tags".to_string(),
+                to: "item_tags".to_string(),
+            },
+        ];
+        let version = db
+            .migrations()
+            .run_migrations(vec![migration(latest + 1, steps)], false)
+            .await
+            .unwrap();
+        assert_eq!(version, latest + 1);
+
+        let connection = db.connection.lock().unwrap().clone();
+        let columns = sqlx::query("PRAGMA table_info(stock_item)")
+            .fetch_all(&connection)
+            .await
+            .unwrap();
+        let price = columns
+            .iter()
+            .find(|row| row.get::<String, _>("name") == "price")
+            .unwrap();
+        assert_eq!(price.get::<String, _>("type"), "INTEGER");
+        assert!(columns
+            .iter()
+            .any(|row| row.get::<String, _>("name") == "item_tags"));
+        let lots: i64 = sqlx::query("SELECT COUNT(*) FROM stock_lot")
+            .fetch_one(&connection)
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(lots, 1);
+        let foreign_keys: i64 = sqlx::query("PRAGMA foreign_keys")
+            .fetch_one(&connection)
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(foreign_keys, 1);
+    }
+
+    #[test]
+    fn retypes_one_column_and_keeps_the_constraints() {
+        let sql = r#"CREATE TABLE "stock_lot" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "unit_price" double NOT NULL, "note" varchar(10, 2) DEFAULT 'a, b', [size] text, FOREIGN KEY ("stock_item_id") REFERENCES "stock_item" ("id") ON DELETE CASCADE )"#;
+
+        assert_eq!(
+            retype_create_sql(sql, "stock_lot_migration", "unit_price", "REAL").unwrap(),
+            r#"CREATE TABLE "stock_lot_migration" ("id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "unit_price" REAL NOT NULL, "note" varchar(10, 2) DEFAULT 'a, b', [size] text, FOREIGN KEY ("stock_item_id") REFERENCES "stock_item" ("id") ON DELETE CASCADE)"#
+        );
+        assert_eq!(
+            retype_create_sql(sql, "stock_lot_migration", "size", "INTEGER").unwrap(),
+            r#"CREATE TABLE "stock_lot_migration" ("id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "unit_price" double NOT NULL, "note" varchar(10, 2) DEFAULT 'a, b', [size] INTEGER, FOREIGN KEY ("stock_item_id") REFERENCES "stock_item" ("id") ON DELETE CASCADE)"#
+        );
+        assert!(retype_create_sql(sql, "stock_lot_migration", "missing", "REAL").is_err());
+    }
+
+    #[tokio::test]
+    async fn a_retyped_table_keeps_its_foreign_keys() {
+        let server = MockWfmServer::start().await;
+        server.add_item("primed_flow_id", "primed_flow", "Primed Flow");
+        let db = server.database().await;
+        let item = db
+            .stock_item()
+            .create("primed_flow", 3, 60.0, None, 0, None)
+            .await
+            .unwrap();
+        let latest = migrations().last().unwrap().version;
+
+        let steps = vec![MigrationStep::ChangeColumnType {
+            table: "stock_lot".to_string(),
+            column: "unit_price".to_string(),
+            sql_type: "REAL".to_string(),
+        }];
+        db.migrations()
+            .run_migrations(vec![migration(latest + 1, steps)], false)
+            .await
+            .unwrap();
+
+        let connection = db.connection.lock().unwrap().clone();
+        let foreign_keys = sqlx::query("PRAGMA foreign_key_list(stock_lot)")
+            .fetch_all(&connection)
+            .await
+            .unwrap();
+        assert_eq!(foreign_keys.len(), 1);
+        assert_eq!(foreign_keys[0].get::<String, _>("table"), "stock_item");
+        assert_eq!(foreign_keys[0].get::<String, _>("on_delete"), "CASCADE");
+        let indices: i64 = sqlx::query(
+            "SELECT COUNT(*) FROM sqlite_master WHERE name = 'idx_stock_lot_stock_item_id'",
+        )
+        .fetch_one(&connection)
+        .await
+        .unwrap()
+        .get(0);
+        assert_eq!(indices, 1);
+
+        // Deleting the stock item still deletes its lots
+        sqlx::query("DELETE FROM stock_item WHERE id = ?")
+            .bind(item.id)
+            .execute(&connection)
+            .await
+            .unwrap();
+        let lots: i64 = sqlx::query("SELECT COUNT(*) FROM stock_lot")
+            .fetch_one(&connection)
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(lots, 0);
+    }
+}
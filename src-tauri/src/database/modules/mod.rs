@@ -1,3 +1,11 @@
+pub mod backup;
+pub mod import_export;
+pub mod migration;
+pub mod profit;
+pub mod stock_event;
 pub mod stock_item;
+pub mod stock_lot;
 pub mod stock_riven;
+pub mod trade;
 pub mod transaction;
+pub mod whisper;
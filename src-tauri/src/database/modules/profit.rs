@@ -0,0 +1,453 @@
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{database::client::DBClient, error::AppError, helper};
+
+use super::transaction::TransactionStruct;
+
+/// How the cost of a sold item is taken from what was bought before it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostMethod {
+    // The oldest purchase is sold first
+    Fifo,
+    // Every unit costs the average price of the units held
+    Average,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfitPeriod {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl ProfitPeriod {
+    fn key(&self, date: DateTime<Utc>) -> String {
+        match self {
+            ProfitPeriod::Day => date.format("%Y-%m-%d").to_string(),
+            ProfitPeriod::Week => {
+                let week = date.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            ProfitPeriod::Month => date.format("%Y-%m").to_string(),
+            ProfitPeriod::Year => date.format("%Y").to_string(),
+        }
+    }
+}
+
+/// The totals of one item, tag or period. Prices are platinum, the trade tax is credits.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ProfitSummary {
+    pub key: String,
+    pub bought: i64,
+    pub sold: i64,
+    pub spent: i64,
+    pub revenue: i64,
+    // What the sold units cost
+    pub cost_of_sales: i64,
+    pub realized_profit: i64,
+    pub trade_tax: i64,
+    // Units sold without a purchase before them, they count as free
+    pub unmatched: i64,
+    pub open_quantity: i64,
+    pub open_cost: i64,
+    // The open quantity valued at the closed average of the price history, None without one
+    pub unrealized_profit: Option<i64>,
+}
+
+impl ProfitSummary {
+    fn new(key: &str) -> Self {
+        ProfitSummary {
+            key: key.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn add(&mut self, other: &ProfitSummary) {
+        self.bought += other.bought;
+        self.sold += other.sold;
+        self.spent += other.spent;
+        self.revenue += other.revenue;
+        self.cost_of_sales += other.cost_of_sales;
+        self.realized_profit += other.realized_profit;
+        self.trade_tax += other.trade_tax;
+        self.unmatched += other.unmatched;
+        self.open_quantity += other.open_quantity;
+        self.open_cost += other.open_cost;
+        self.unrealized_profit = match (self.unrealized_profit, other.unrealized_profit) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+        };
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ProfitItemSummary {
+    pub url: String,
+    pub name: String,
+    pub item_type: String,
+    pub rank: i32,
+    pub tags: Vec<String>,
+    #[serde(flatten)]
+    pub summary: ProfitSummary,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ProfitReport {
+    pub method: CostMethod,
+    pub period: ProfitPeriod,
+    pub total: ProfitSummary,
+    pub items: Vec<ProfitItemSummary>,
+    pub tags: Vec<ProfitSummary>,
+    // Only sales count towards a period, the open stock has no date
+    pub periods: Vec<ProfitSummary>,
+    // The ids of transactions left out because their date could not be read
+    pub skipped: Vec<i64>,
+}
+
+/// The transactions that count towards the totals. Purchases and sales before `from`
+/// are still matched, so the cost of a sale does not depend on where the range starts.
+#[derive(Clone, Debug, Default)]
+pub struct ProfitRange {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+struct ItemBook {
+    item: ProfitItemSummary,
+    // (quantity, total cost) of what is still held, oldest first
+    lots: VecDeque<(i64, i64)>,
+}
+
+impl ItemBook {
+    /// Takes `quantity` units out of the held lots, returns what they cost and how many were not held.
+    fn consume(&mut self, method: CostMethod, quantity: i64) -> (i64, i64) {
+        let held: i64 = self.lots.iter().map(|(quantity, _)| quantity).sum();
+        let matched = quantity.min(held);
+        let unmatched = quantity - matched;
+        match method {
+            CostMethod::Average => {
+                if held == 0 {
+                    return (0, unmatched);
+                }
+                let total: i64 = self.lots.iter().map(|(_, cost)| cost).sum();
+                let cost = total * matched / held;
+                self.lots.clear();
+                if held > matched {
+                    self.lots.push_back((held - matched, total - cost));
+                }
+                (cost, unmatched)
+            }
+            CostMethod::Fifo => {
+                let mut left = matched;
+                let mut cost = 0;
+                while left > 0 {
+                    let Some((lot_quantity, lot_cost)) = self.lots.front_mut() else {
+                        break;
+                    };
+                    let taken = left.min(*lot_quantity);
+                    let taken_cost = *lot_cost * taken / *lot_quantity;
+                    cost += taken_cost;
+                    *lot_cost -= taken_cost;
+                    *lot_quantity -= taken;
+                    left -= taken;
+                    if *lot_quantity == 0 {
+                        self.lots.pop_front();
+                    }
+                }
+                (cost, unmatched)
+            }
+        }
+    }
+}
+
+/// Builds the report from transactions in any order, the price of a transaction is for all of its units.
+/// `closed_averages` are the closed averages of the price history by url name and rank.
+pub fn calculate(
+    transactions: &[TransactionStruct],
+    method: CostMethod,
+    period: ProfitPeriod,
+    range: &ProfitRange,
+    closed_averages: &HashMap<(String, i64), f64>,
+) -> ProfitReport {
+    let mut transactions = transactions.to_vec();
+    transactions.sort_by(|a, b| a.created.cmp(&b.created).then(a.id.cmp(&b.id)));
+
+    let mut books: HashMap<String, ItemBook> = HashMap::new();
+    let mut periods: HashMap<String, ProfitSummary> = HashMap::new();
+    let mut skipped = vec![];
+    for transaction in transactions.iter() {
+        let Ok(created) = DateTime::parse_from_rfc3339(&transaction.created) else {
+            skipped.push(transaction.id);
+            continue;
+        };
+        let created = created.with_timezone(&Utc);
+        // Later transactions do not change what was held at the end of the range
+        if range.to.map_or(false, |to| created > to) {
+            continue;
+        }
+        let counted = range.from.map_or(true, |from| created >= from);
+        let key = format!(
+            "{}:{}:{}",
+            transaction.item_type, transaction.url, transaction.rank
+        );
+        let tags: Vec<String> = transaction
+            .tags
+            .split(',')
+            .filter(|tag| !tag.is_empty())
+            .map(|tag| tag.to_string())
+            .collect();
+        let book = books.entry(key.clone()).or_insert_with(|| ItemBook {
+            item: ProfitItemSummary {
+                url: transaction.url.clone(),
+                name: transaction.name.clone(),
+                item_type: transaction.item_type.clone(),
+                rank: transaction.rank,
+                tags: tags.clone(),
+                summary: ProfitSummary::new(&key),
+            },
+            lots: VecDeque::new(),
+        });
+        let quantity = transaction.quantity.max(1) as i64;
+        let price = transaction.price as i64;
+        match transaction.transaction_type.as_str() {
+            "buy" => {
+                book.lots.push_back((quantity, price));
+                if counted {
+                    book.item.summary.bought += quantity;
+                    book.item.summary.spent += price;
+                }
+            }
+            "sell" => {
+                let (cost, unmatched) = book.consume(method, quantity);
+                if !counted {
+                    continue;
+                }
+                let tax = if transaction.item_type == "item" {
+                    helper::calculate_trade_tax(tags.clone(), Some(transaction.rank as i64))
+                        * quantity
+                } else {
+                    0
+                };
+                let sale = ProfitSummary {
+                    sold: quantity,
+                    revenue: price,
+                    cost_of_sales: cost,
+                    realized_profit: price - cost,
+                    trade_tax: tax,
+                    unmatched,
+                    ..Default::default()
+                };
+                book.item.summary.add(&sale);
+                let period_key = period.key(created);
+                periods
+                    .entry(period_key.clone())
+                    .or_insert_with(|| ProfitSummary::new(&period_key))
+                    .add(&sale);
+            }
+            _ => {}
+        }
+    }
+
+    let mut items: Vec<ProfitItemSummary> = books
+        .into_values()
+        .map(|mut book| {
+            // Rivens have no closed average
+            let closed_average = match book.item.item_type.as_str() {
+                "item" => closed_averages
+                    .get(&(book.item.url.clone(), book.item.rank as i64))
+                    .copied(),
+                _ => None,
+            };
+            let summary = &mut book.item.summary;
+            summary.open_quantity = book.lots.iter().map(|(quantity, _)| quantity).sum();
+            summary.open_cost = book.lots.iter().map(|(_, cost)| cost).sum();
+            match closed_average {
+                Some(closed_average) if summary.open_quantity > 0 => {
+                    let value = (closed_average * summary.open_quantity as f64).round() as i64;
+                    summary.unrealized_profit = Some(value - summary.open_cost);
+                }
+                _ => {}
+            }
+            book.item
+        })
+        .filter(|item| {
+            let summary = &item.summary;
+            summary.bought > 0 || summary.sold > 0 || summary.open_quantity > 0
+        })
+        .collect();
+    items.sort_by(|a, b| b.summary.realized_profit.cmp(&a.summary.realized_profit));
+
+    let mut total = ProfitSummary::new("total");
+    let mut tags: HashMap<String, ProfitSummary> = HashMap::new();
+    for item in items.iter() {
+        total.add(&item.summary);
+        for tag in item.tags.iter() {
+            tags.entry(tag.clone())
+                .or_insert_with(|| ProfitSummary::new(tag))
+                .add(&item.summary);
+        }
+    }
+    let mut tags: Vec<ProfitSummary> = tags.into_values().collect();
+    tags.sort_by(|a, b| b.realized_profit.cmp(&a.realized_profit));
+    let mut periods: Vec<ProfitSummary> = periods.into_values().collect();
+    periods.sort_by(|a, b| a.key.cmp(&b.key));
+
+    ProfitReport {
+        method,
+        period,
+        total,
+        items,
+        tags,
+        periods,
+        skipped,
+    }
+}
+
+pub struct ProfitModule<'a> {
+    pub client: &'a DBClient,
+}
+
+impl<'a> ProfitModule<'a> {
+    /// The report over the transactions in `range`, with open stock valued at `closed_averages`.
+    pub async fn get_report(
+        &self,
+        method: CostMethod,
+        period: ProfitPeriod,
+        range: ProfitRange,
+        closed_averages: HashMap<(String, i64), f64>,
+    ) -> Result<ProfitReport, AppError> {
+        let transactions = self.client.transaction().get_items().await?;
+        Ok(calculate(
+            &transactions,
+            method,
+            period,
+            &range,
+            &closed_averages,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(id: i64, kind: &str, quantity: i32, price: i32, created: &str) -> TransactionStruct {
+        TransactionStruct {
+            id,
+            wfm_id: "arcane_energize".to_string(),
+            url: "arcane_energize".to_string(),
+            name: "Arcane Energize".to_string(),
+            item_type: "item".to_string(),
+            tags: "arcane_enhancement,legendary".to_string(),
+            transaction_type: kind.to_string(),
+            quantity,
+            rank: 0,
+            price,
+            created: created.to_string(),
+            properties: None,
+        }
+    }
+
+    fn date(input: &str) -> Option<DateTime<Utc>> {
+        Some(DateTime::parse_from_rfc3339(input).unwrap().with_timezone(&Utc))
+    }
+
+    #[test]
+    fn matches_sales_to_purchases() {
+        let transactions = vec![
+            transaction(1, "buy", 2, 20, "2024-01-01T00:00:00+00:00"),
+            transaction(2, "buy", 1, 16, "2024-01-02T00:00:00+00:00"),
+            transaction(3, "sell", 2, 40, "2024-02-01T00:00:00+00:00"),
+        ];
+        let closed_averages = HashMap::from([
+            (("arcane_energize".to_string(), 0), 25.0),
+            (("arcane_energize".to_string(), 5), 400.0),
+        ]);
+        let range = ProfitRange::default();
+
+        let fifo = calculate(
+            &transactions,
+            CostMethod::Fifo,
+            ProfitPeriod::Month,
+            &range,
+            &closed_averages,
+        );
+        assert_eq!(fifo.total.realized_profit, 20);
+        assert_eq!(fifo.total.open_cost, 16);
+        assert_eq!(fifo.total.unrealized_profit, Some(9));
+        assert_eq!(fifo.total.trade_tax, 200000);
+        assert_eq!(fifo.periods[0].key, "2024-02");
+        assert_eq!(fifo.tags.len(), 2);
+
+        let average = calculate(
+            &transactions,
+            CostMethod::Average,
+            ProfitPeriod::Month,
+            &range,
+            &HashMap::new(),
+        );
+        assert_eq!(average.total.realized_profit, 16);
+        assert_eq!(average.total.open_cost, 12);
+        assert_eq!(average.total.unrealized_profit, None);
+    }
+
+    #[test]
+    fn matches_sales_in_the_range_to_earlier_purchases() {
+        let transactions = vec![
+            transaction(1, "buy", 2, 20, "2024-01-01T00:00:00+00:00"),
+            transaction(2, "sell", 1, 30, "2024-01-15T00:00:00+00:00"),
+            transaction(3, "buy", 1, 50, "2024-02-01T00:00:00+00:00"),
+            transaction(4, "sell", 1, 30, "2024-02-10T00:00:00+00:00"),
+            transaction(5, "sell", 1, 90, "2024-03-01T00:00:00+00:00"),
+        ];
+        let range = ProfitRange {
+            from: date("2024-02-01T00:00:00+00:00"),
+            to: date("2024-02-29T23:59:59+00:00"),
+        };
+
+        let report = calculate(
+            &transactions,
+            CostMethod::Fifo,
+            ProfitPeriod::Month,
+            &range,
+            &HashMap::new(),
+        );
+        // The February sale takes the second unit bought in January
+        assert_eq!(report.total.sold, 1);
+        assert_eq!(report.total.bought, 1);
+        assert_eq!(report.total.cost_of_sales, 10);
+        assert_eq!(report.total.realized_profit, 20);
+        assert_eq!(report.total.unmatched, 0);
+        // The March sale is after the range, the February purchase is still held
+        assert_eq!(report.total.open_quantity, 1);
+        assert_eq!(report.total.open_cost, 50);
+        assert_eq!(report.periods.len(), 1);
+    }
+
+    #[test]
+    fn skips_transactions_with_unreadable_dates() {
+        let transactions = vec![
+            transaction(1, "buy", 1, 20, "2024-01-01T00:00:00+00:00"),
+            transaction(2, "sell", 1, 30, "2024-01-02 10:00:00"),
+        ];
+
+        let report = calculate(
+            &transactions,
+            CostMethod::Fifo,
+            ProfitPeriod::Year,
+            &ProfitRange::default(),
+            &HashMap::new(),
+        );
+
+        assert_eq!(report.skipped, vec![2]);
+        assert_eq!(report.total.sold, 0);
+        assert_eq!(report.total.open_quantity, 1);
+        assert!(report.periods.is_empty());
+    }
+}
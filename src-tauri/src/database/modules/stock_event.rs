@@ -0,0 +1,274 @@
+use crate::{database::client::DBClient, error::AppError};
+use eyre::eyre;
+use sea_query::{ColumnDef, Expr, Iden, InsertStatement, Order, Query, SqliteQueryBuilder, Table};
+use serde::{Deserialize, Serialize};
+
+#[derive(Iden)]
+pub enum StockEvent {
+    Table,
+    Id,
+    // "item" or "riven"
+    StockType,
+    StockId,
+    // The column that changed, like "status" or "listed_price"
+    Field,
+    OldValue,
+    NewValue,
+    Source,
+    Reason,
+    Created,
+}
+
+/// What made a change to the stock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StockEventSource {
+    User,
+    LiveScraper,
+    TradeDetection,
+    Import,
+}
+
+impl StockEventSource {
+    pub fn as_str(&self) -> &str {
+        match self {
+            StockEventSource::User => "user",
+            StockEventSource::LiveScraper => "live_scraper",
+            StockEventSource::TradeDetection => "trade_detection",
+            StockEventSource::Import => "import",
+        }
+    }
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
+pub struct StockEventStruct {
+    pub id: i64,
+    pub stock_type: String,
+    pub stock_id: i64,
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub source: String,
+    pub reason: Option<String>,
+    pub created: String,
+}
+
+/// The statement that creates the table, run by the migration that introduced the events.
+pub fn create_table_sql() -> String {
+    Table::create()
+        .table(StockEvent::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(StockEvent::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(StockEvent::StockType).string().not_null())
+        .col(ColumnDef::new(StockEvent::StockId).integer().not_null())
+        .col(ColumnDef::new(StockEvent::Field).string().not_null())
+        .col(ColumnDef::new(StockEvent::OldValue).string())
+        .col(ColumnDef::new(StockEvent::NewValue).string())
+        .col(ColumnDef::new(StockEvent::Source).string().not_null())
+        .col(ColumnDef::new(StockEvent::Reason).string())
+        .col(ColumnDef::new(StockEvent::Created).date_time().not_null())
+        .build(SqliteQueryBuilder)
+}
+
+/// Triggers that stop the events from being changed or deleted once they are recorded.
+pub fn append_only_triggers_sql() -> Vec<String> {
+    ["UPDATE", "DELETE"]
+        .iter()
+        .map(|action| {
+            format!(
+                "CREATE TRIGGER IF NOT EXISTS stock_event_no_{} BEFORE {} ON stock_event
+                BEGIN SELECT RAISE(ABORT, 'Stock events can not be changed'); END",
+                action.to_lowercase(),
+                action
+            )
+        })
+        .collect()
+}
+
+/// The stock types that have a timeline.
+const STOCK_TYPES: [&str; 2] = ["item", "riven"];
+
+/// The events are only ever added, a stock row keeps its timeline after it is deleted.
+pub struct StockEventModule<'a> {
+    pub client: &'a DBClient,
+}
+
+impl<'a> StockEventModule<'a> {
+    /// Adds an event for every `(field, old, new)` where the value changed.
+    pub async fn record(
+        &self,
+        stock_type: &str,
+        stock_id: i64,
+        changes: Vec<(&str, Option<String>, Option<String>)>,
+        source: StockEventSource,
+        reason: Option<String>,
+    ) -> Result<(), AppError> {
+        let changes: Vec<_> = changes
+            .into_iter()
+            .filter(|(_, old, new)| old != new)
+            .collect();
+        if changes.is_empty() {
+            return Ok(());
+        }
+        let connection = self.client.connection.lock().unwrap().clone();
+        let created = chrono::Local::now().naive_local().to_string();
+        let mut sql = InsertStatement::default();
+        sql.into_table(StockEvent::Table).columns([
+            StockEvent::StockType,
+            StockEvent::StockId,
+            StockEvent::Field,
+            StockEvent::OldValue,
+            StockEvent::NewValue,
+            StockEvent::Source,
+            StockEvent::Reason,
+            StockEvent::Created,
+        ]);
+        for (field, old, new) in changes {
+            sql.values_panic([
+                stock_type.into(),
+                stock_id.into(),
+                field.into(),
+                old.into(),
+                new.into(),
+                source.as_str().into(),
+                reason.clone().into(),
+                created.clone().into(),
+            ]);
+        }
+        sqlx::query(&sql.to_string(SqliteQueryBuilder))
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(())
+    }
+
+    /// Every change of a stock row, oldest first.
+    pub async fn get_timeline(
+        &self,
+        stock_type: &str,
+        stock_id: i64,
+    ) -> Result<Vec<StockEventStruct>, AppError> {
+        if !STOCK_TYPES.contains(&stock_type) {
+            return Err(AppError::new(
+                "Database",
+                eyre!("Unknown stock type {}", stock_type),
+            ));
+        }
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .columns([
+                StockEvent::Id,
+                StockEvent::StockType,
+                StockEvent::StockId,
+                StockEvent::Field,
+                StockEvent::OldValue,
+                StockEvent::NewValue,
+                StockEvent::Source,
+                StockEvent::Reason,
+                StockEvent::Created,
+            ])
+            .from(StockEvent::Table)
+            .and_where(Expr::col(StockEvent::StockType).eq(stock_type))
+            .and_where(Expr::col(StockEvent::StockId).eq(stock_id))
+            .order_by(StockEvent::Id, Order::Asc)
+            .to_string(SqliteQueryBuilder);
+        let rows = sqlx::query_as::<_, StockEventStruct>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StockEventSource;
+    use crate::wfm_client::mock_server::MockWfmServer;
+
+    #[tokio::test]
+    async fn records_only_the_values_that_changed() {
+        let server = MockWfmServer::start().await;
+        let db = server.database().await;
+        let events = db.stock_event();
+        events
+            .record(
+                "item",
+                1,
+                vec![
+                    ("status", Some("pending".to_string()), Some("live".to_string())),
+                    ("owned", Some("2".to_string()), Some("2".to_string())),
+                ],
+                StockEventSource::LiveScraper,
+                None,
+            )
+            .await
+            .unwrap();
+        events
+            .record(
+                "item",
+                1,
+                vec![("owned", Some("2".to_string()), Some("1".to_string()))],
+                StockEventSource::TradeDetection,
+                Some("Sold to Partner01".to_string()),
+            )
+            .await
+            .unwrap();
+        events
+            .record(
+                "riven",
+                1,
+                vec![("status", None, Some("pending".to_string()))],
+                StockEventSource::User,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let timeline = events.get_timeline("item", 1).await.unwrap();
+        assert_eq!(
+            timeline
+                .iter()
+                .map(|event| (event.field.as_str(), event.source.as_str(), event.reason.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("status", "live_scraper", None),
+                ("owned", "trade_detection", Some("Sold to Partner01".to_string())),
+            ]
+        );
+        assert_eq!(events.get_timeline("riven", 1).await.unwrap().len(), 1);
+        assert!(events.get_timeline("auction", 1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn recorded_events_can_not_be_changed() {
+        let server = MockWfmServer::start().await;
+        let db = server.database().await;
+        db.stock_event()
+            .record(
+                "item",
+                1,
+                vec![("status", None, Some("pending".to_string()))],
+                StockEventSource::User,
+                None,
+            )
+            .await
+            .unwrap();
+        let connection = db.connection.lock().unwrap().clone();
+
+        assert!(sqlx::query("UPDATE stock_event SET new_value = 'live'")
+            .execute(&connection)
+            .await
+            .is_err());
+        assert!(sqlx::query("DELETE FROM stock_event")
+            .execute(&connection)
+            .await
+            .is_err());
+        assert_eq!(db.stock_event().get_timeline("item", 1).await.unwrap().len(), 1);
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     auth::AuthState,
     database::client::DBClient,
-    enums::LogLevel,
+    enums::{LogLevel, LotOrder},
     error::AppError,
     helper,
     logger::{self},
@@ -13,6 +13,7 @@ use polars::{
     series::Series,
 };
 use reqwest::header::HeaderMap;
+use super::stock_event::StockEventSource;
 use sea_query::{ColumnDef, Expr, Iden, InsertStatement, Query, SqliteQueryBuilder, Table, Value};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -58,9 +59,53 @@ pub struct StockItemStruct {
 
 pub struct StockItemModule<'a> {
     pub client: &'a DBClient,
+    // Recorded with the changes made through this module
+    pub source: StockEventSource,
+    pub reason: Option<String>,
 }
 
 impl<'a> StockItemModule<'a> {
+    /// Records the changes made through the module as coming from `source`.
+    pub fn with_source(mut self, source: StockEventSource, reason: Option<&str>) -> Self {
+        self.source = source;
+        self.reason = reason.map(|reason| reason.to_string());
+        self
+    }
+
+    async fn record_changes(
+        &self,
+        previous: &StockItemStruct,
+        current: &StockItemStruct,
+    ) -> Result<(), AppError> {
+        self.client
+            .stock_event()
+            .record(
+                "item",
+                current.id,
+                vec![
+                    ("status", Some(previous.status.clone()), Some(current.status.clone())),
+                    (
+                        "listed_price",
+                        previous.listed_price.map(|p| p.to_string()),
+                        current.listed_price.map(|p| p.to_string()),
+                    ),
+                    (
+                        "price",
+                        Some(format!("{:.2}", previous.price)),
+                        Some(format!("{:.2}", current.price)),
+                    ),
+                    (
+                        "owned",
+                        Some(previous.owned.to_string()),
+                        Some(current.owned.to_string()),
+                    ),
+                ],
+                self.source,
+                self.reason.clone(),
+            )
+            .await
+    }
+
     // Methods sea-query
 
     // Initialize the database
@@ -123,39 +168,6 @@ impl<'a> StockItemModule<'a> {
             .await
             .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
 
-        let mut table = Table::alter()
-            .table(StockItem::Table)
-            .add_column(
-                ColumnDef::new(StockItem::MiniumPrice)
-                    .integer()
-                    .default(Value::Int(None)),
-            )
-            .to_string(SqliteQueryBuilder);
-        helper::alter_table(connection.clone(), &table).await?;
-
-        table = Table::alter()
-            .table(StockItem::Table)
-            .add_column(
-                ColumnDef::new(StockItem::Hidden)
-                    .boolean()
-                    .not_null()
-                    .default(Value::Bool(Some(false))),
-            )
-            .to_string(SqliteQueryBuilder);
-
-        helper::alter_table(connection.clone(), &table).await?;
-
-        table = Table::alter()
-            .table(StockItem::Table)
-            .add_column(
-                ColumnDef::new(StockItem::Status)
-                    .string()
-                    .not_null()
-                    .default("pending"),
-            )
-            .to_string(SqliteQueryBuilder);
-        helper::alter_table(connection.clone(), &table).await?;
-
         Ok(true)
     }
 
@@ -233,25 +245,13 @@ impl<'a> StockItemModule<'a> {
 
         let inventory = match inventorys {
             Some(t) => {
-                let total_owned = t.owned + quantity;
-                // Get price per unit
-                let total_price = (t.price * t.owned as f64) + price as f64;
-                let weighted_price = total_price / total_owned as f64;
-
-                self.update_by_id(
-                    t.id,
-                    Some(total_owned),
-                    Some(weighted_price),
-                    None,
-                    None,
-                    None,
-                    None,
-                )
-                .await?;
-                let mut t = t.clone();
-                t.owned = total_owned;
-                t.price = weighted_price;
-                t
+                self.client
+                    .stock_lot()
+                    .create(t.id, quantity, price / quantity as f64, None)
+                    .await?;
+                let inventory = self.refresh_from_lots(t.id).await?;
+                self.record_changes(&t, &inventory).await?;
+                inventory
             }
             None => {
                 let price = price / (quantity as f64);
@@ -310,6 +310,24 @@ impl<'a> StockItemModule<'a> {
                     .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
                 let id = row.last_insert_rowid();
                 inventory.id = id;
+                self.client
+                    .stock_lot()
+                    .create(id, quantity, inventory.price, None)
+                    .await?;
+                self.client
+                    .stock_event()
+                    .record(
+                        "item",
+                        id,
+                        vec![
+                            ("status", None, Some(inventory.status.clone())),
+                            ("price", None, Some(format!("{:.2}", inventory.price))),
+                            ("owned", None, Some(inventory.owned.to_string())),
+                        ],
+                        self.source,
+                        self.reason.clone(),
+                    )
+                    .await?;
                 inventory
             }
         };
@@ -342,14 +360,27 @@ impl<'a> StockItemModule<'a> {
             ));
         }
         let mut inventory = inventory.unwrap().clone();
+        let previous = inventory.clone();
         let mut values = vec![];
 
         if owned.is_some() {
-            inventory.owned = owned.unwrap();
+            // The lots follow a quantity changed by hand, added units are bought at `price`
+            // and the price is the new average of the lots
+            self.client
+                .stock_lot()
+                .reconcile(id, owned.unwrap(), price.unwrap_or(inventory.price))
+                .await?;
+            let (owned, average) = self.client.stock_lot().get_aggregate(id).await?;
+            inventory.owned = owned;
             values.push((StockItem::Owned, owned.into()));
-        }
-
-        if price.is_some() {
+            if owned > 0 {
+                inventory.price = average;
+                values.push((StockItem::Price, average.into()));
+            } else if price.is_some() {
+                inventory.price = price.unwrap();
+                values.push((StockItem::Price, price.into()));
+            }
+        } else if price.is_some() {
             inventory.price = price.unwrap();
             values.push((StockItem::Price, price.into()));
         }
@@ -394,6 +425,7 @@ impl<'a> StockItemModule<'a> {
             .execute(&connection)
             .await
             .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        self.record_changes(&previous, &inventory).await?;
 
         self.emit(
             "CREATE_OR_UPDATE",
@@ -402,6 +434,7 @@ impl<'a> StockItemModule<'a> {
         Ok(inventory.clone())
     }
     pub async fn reset_listed_price(&self) -> Result<(), AppError> {
+        let previous = self.get_items().await?;
         let connection = self.client.connection.lock().unwrap().clone();
         let sql = Query::update()
             .table(StockItem::Table)
@@ -415,7 +448,13 @@ impl<'a> StockItemModule<'a> {
             .await
             .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
 
-        self.emit("SET", json!(self.get_items().await?));
+        let items = self.get_items().await?;
+        for current in items.iter() {
+            if let Some(previous) = previous.iter().find(|item| item.id == current.id) {
+                self.record_changes(previous, current).await?;
+            }
+        }
+        self.emit("SET", json!(items));
         Ok(())
     }
     pub async fn update_by_url(
@@ -472,6 +511,20 @@ impl<'a> StockItemModule<'a> {
             .execute(&connection)
             .await
             .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        self.client
+            .stock_event()
+            .record(
+                "item",
+                id,
+                vec![(
+                    "status",
+                    stock_item.map(|item| item.status.clone()),
+                    Some("deleted".to_string()),
+                )],
+                self.source,
+                self.reason.clone(),
+            )
+            .await?;
         self.emit(
             "DELETE",
             serde_json::to_value(stock_item.unwrap().clone()).unwrap(),
@@ -479,7 +532,13 @@ impl<'a> StockItemModule<'a> {
         Ok(stock_item.unwrap().clone())
     }
 
-    pub async fn sell_item(&self, id: i64, mut quantity: i32) -> Result<StockItemStruct, AppError> {
+    /// Takes `quantity` out of the purchase lots in `order`, the stock item is deleted once none are left.
+    pub async fn sell_item(
+        &self,
+        id: i64,
+        mut quantity: i32,
+        order: &LotOrder,
+    ) -> Result<StockItemStruct, AppError> {
         let items = self.get_items().await?;
         let stock_item = items.iter().find(|t| t.id == id);
 
@@ -495,23 +554,36 @@ impl<'a> StockItemModule<'a> {
         if quantity <= 0 {
             quantity = 1;
         }
-        inventory.owned -= quantity;
+        self.client.stock_lot().consume(id, quantity, order).await?;
+        let (owned, price) = self.client.stock_lot().get_aggregate(id).await?;
 
-        if inventory.owned <= 0 {
+        if owned <= 0 {
+            inventory.owned = owned;
             self.delete(id).await?;
-        } else {
-            self.update_by_id(
-                id,
-                Some(inventory.owned.clone()),
-                None,
-                None,
-                Some(-1),
-                None,
-                None,
-            )
-            .await?;
+            return Ok(inventory);
         }
-        Ok(inventory.clone())
+        // The lots already hold the new quantity, so this only writes it to the row
+        self.update_by_id(id, Some(owned), Some(price), None, Some(-1), None, None)
+            .await
+    }
+
+    /// Writes the quantity and average price of the open lots to the stock item.
+    async fn refresh_from_lots(&self, id: i64) -> Result<StockItemStruct, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let (owned, price) = self.client.stock_lot().get_aggregate(id).await?;
+        let sql = Query::update()
+            .table(StockItem::Table)
+            .values([(StockItem::Owned, owned.into()), (StockItem::Price, price.into())])
+            .and_where(Expr::col(StockItem::Id).eq(id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        self.get_by_id(id).await?.ok_or(AppError::new(
+            "Database",
+            eyre!("Item not found in database"),
+        ))
     }
 
     pub async fn get_items_names(&self) -> Result<Vec<String>, AppError> {
@@ -562,3 +634,172 @@ impl<'a> StockItemModule<'a> {
         Ok(df.unwrap())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{enums::LotOrder, wfm_client::mock_server::MockWfmServer};
+
+    #[tokio::test]
+    async fn saves_the_quantity_left_after_a_partial_sale() {
+        let server = MockWfmServer::start().await;
+        server.add_item("primed_flow_id", "primed_flow", "Primed Flow");
+        let db = server.database().await;
+        let item = db
+            .stock_item()
+            .create("primed_flow", 3, 30.0, None, 0, None)
+            .await
+            .unwrap();
+
+        let sold = db
+            .stock_item()
+            .sell_item(item.id, 1, &LotOrder::Fifo)
+            .await
+            .unwrap();
+
+        assert_eq!(sold.owned, 2);
+        let stored = db.stock_item().get_by_id(item.id).await.unwrap().unwrap();
+        assert_eq!(stored.owned, 2);
+        assert_eq!(stored.price, 10.0);
+        assert_eq!(stored.listed_price, None);
+    }
+
+    #[tokio::test]
+    async fn deletes_the_item_once_the_last_unit_is_sold() {
+        let server = MockWfmServer::start().await;
+        server.add_item("primed_flow_id", "primed_flow", "Primed Flow");
+        let db = server.database().await;
+        let item = db
+            .stock_item()
+            .create("primed_flow", 2, 20.0, None, 0, None)
+            .await
+            .unwrap();
+
+        db.stock_item()
+            .sell_item(item.id, 2, &LotOrder::Fifo)
+            .await
+            .unwrap();
+
+        assert!(db.stock_item().get_by_id(item.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn sells_from_the_lots_in_the_chosen_order() {
+        let server = MockWfmServer::start().await;
+        server.add_item("primed_flow_id", "primed_flow", "Primed Flow");
+        let db = server.database().await;
+        db.stock_item()
+            .create("primed_flow", 1, 30.0, None, 0, None)
+            .await
+            .unwrap();
+        let item = db
+            .stock_item()
+            .create("primed_flow", 1, 10.0, None, 0, None)
+            .await
+            .unwrap();
+        assert_eq!(item.owned, 2);
+        assert_eq!(item.price, 20.0);
+
+        let sold = db
+            .stock_item()
+            .sell_item(item.id, 1, &LotOrder::Cheapest)
+            .await
+            .unwrap();
+
+        // The 30 platinum unit is left
+        assert_eq!(sold.owned, 1);
+        assert_eq!(sold.price, 30.0);
+    }
+
+    #[tokio::test]
+    async fn a_quantity_changed_by_hand_moves_the_lots() {
+        let server = MockWfmServer::start().await;
+        server.add_item("primed_flow_id", "primed_flow", "Primed Flow");
+        let db = server.database().await;
+        let item = db
+            .stock_item()
+            .create("primed_flow", 2, 20.0, None, 0, None)
+            .await
+            .unwrap();
+
+        let raised = db
+            .stock_item()
+            .update_by_id(item.id, Some(4), Some(14.0), None, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(raised.owned, 4);
+        assert_eq!(raised.price, 12.0);
+        assert_eq!(
+            db.stock_lot().get_aggregate(item.id).await.unwrap(),
+            (4, 12.0)
+        );
+
+        let lowered = db
+            .stock_item()
+            .update_by_id(item.id, Some(1), None, None, None, None, None)
+            .await
+            .unwrap();
+        // The oldest units go first
+        assert_eq!(lowered.owned, 1);
+        assert_eq!(lowered.price, 14.0);
+    }
+
+    #[tokio::test]
+    async fn the_stored_price_matches_the_lots() {
+        let server = MockWfmServer::start().await;
+        server.add_item("primed_flow_id", "primed_flow", "Primed Flow");
+        let db = server.database().await;
+        let item = db
+            .stock_item()
+            .create("primed_flow", 3, 30.0, None, 0, None)
+            .await
+            .unwrap();
+
+        for (owned, price) in [(5, Some(16.0)), (2, Some(50.0)), (6, None)] {
+            db.stock_item()
+                .update_by_id(item.id, Some(owned), price, None, None, None, None)
+                .await
+                .unwrap();
+            let stored = db.stock_item().get_by_id(item.id).await.unwrap().unwrap();
+            assert_eq!(
+                (stored.owned, stored.price),
+                db.stock_lot().get_aggregate(item.id).await.unwrap()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn records_the_creation_and_the_merged_quantity() {
+        let server = MockWfmServer::start().await;
+        server.add_item("primed_flow_id", "primed_flow", "Primed Flow");
+        let db = server.database().await;
+        let item = db
+            .stock_item()
+            .create("primed_flow", 2, 20.0, None, 0, None)
+            .await
+            .unwrap();
+        db.stock_item()
+            .create("primed_flow", 2, 40.0, None, 0, None)
+            .await
+            .unwrap();
+
+        let timeline = db
+            .stock_event()
+            .get_timeline("item", item.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|event| (event.field, event.old_value, event.new_value))
+            .collect::<Vec<_>>();
+        let value = |value: &str| Some(value.to_string());
+        assert_eq!(
+            timeline,
+            vec![
+                ("status".to_string(), None, value("pending")),
+                ("price".to_string(), None, value("10.00")),
+                ("owned".to_string(), None, value("2")),
+                ("price".to_string(), value("10.00"), value("15.00")),
+                ("owned".to_string(), value("2"), value("4")),
+            ]
+        );
+    }
+}
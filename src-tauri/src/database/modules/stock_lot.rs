@@ -0,0 +1,362 @@
+use crate::{database::client::DBClient, enums::LotOrder, error::AppError};
+use eyre::eyre;
+use sea_query::{
+    ColumnDef, Expr, ForeignKey, ForeignKeyAction, Iden, InsertStatement, Order, Query,
+    SqliteQueryBuilder, Table,
+};
+use serde::{Deserialize, Serialize};
+
+use super::stock_item::StockItem;
+
+#[derive(Iden)]
+pub enum StockLot {
+    Table,
+    Id,
+    StockItemId,
+    // The quantity that was bought
+    Quantity,
+    // What is left of it
+    Remaining,
+    UnitPrice,
+    Acquired,
+    TransactionId,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
+pub struct StockLotStruct {
+    pub id: i64,
+    pub stock_item_id: i64,
+    pub quantity: i32,
+    pub remaining: i32,
+    pub unit_price: f64,
+    pub acquired: String,
+    pub transaction_id: Option<i64>,
+}
+
+/// What a sale took from one lot.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ConsumedLot {
+    pub lot_id: i64,
+    pub quantity: i32,
+    pub unit_price: f64,
+}
+
+/// The statement that creates the table, run by the migration that introduced lots.
+pub fn create_table_sql() -> String {
+    Table::create()
+        .table(StockLot::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(StockLot::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(StockLot::StockItemId).integer().not_null())
+        .col(ColumnDef::new(StockLot::Quantity).integer().not_null())
+        .col(ColumnDef::new(StockLot::Remaining).integer().not_null())
+        .col(ColumnDef::new(StockLot::UnitPrice).double().not_null())
+        .col(ColumnDef::new(StockLot::Acquired).date_time().not_null())
+        .col(ColumnDef::new(StockLot::TransactionId).integer())
+        .foreign_key(
+            ForeignKey::create()
+                .from(StockLot::Table, StockLot::StockItemId)
+                .to(StockItem::Table, StockItem::Id)
+                .on_delete(ForeignKeyAction::Cascade),
+        )
+        .build(SqliteQueryBuilder)
+}
+
+pub struct StockLotModule<'a> {
+    pub client: &'a DBClient,
+}
+
+impl<'a> StockLotModule<'a> {
+    /// The lots of a stock item, oldest first.
+    pub async fn get_by_stock_item(
+        &self,
+        stock_item_id: i64,
+    ) -> Result<Vec<StockLotStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .columns([
+                StockLot::Id,
+                StockLot::StockItemId,
+                StockLot::Quantity,
+                StockLot::Remaining,
+                StockLot::UnitPrice,
+                StockLot::Acquired,
+                StockLot::TransactionId,
+            ])
+            .from(StockLot::Table)
+            .and_where(Expr::col(StockLot::StockItemId).eq(stock_item_id))
+            .order_by(StockLot::Acquired, Order::Asc)
+            .order_by(StockLot::Id, Order::Asc)
+            .to_string(SqliteQueryBuilder);
+        let rows = sqlx::query_as::<_, StockLotStruct>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(rows)
+    }
+
+    /// The lots that still have units left.
+    pub async fn get_open(&self, stock_item_id: i64) -> Result<Vec<StockLotStruct>, AppError> {
+        Ok(self
+            .get_by_stock_item(stock_item_id)
+            .await?
+            .into_iter()
+            .filter(|lot| lot.remaining > 0)
+            .collect())
+    }
+
+    pub async fn create(
+        &self,
+        stock_item_id: i64,
+        quantity: i32,
+        unit_price: f64,
+        transaction_id: Option<i64>,
+    ) -> Result<StockLotStruct, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let mut lot = StockLotStruct {
+            id: 0,
+            stock_item_id,
+            quantity,
+            remaining: quantity,
+            unit_price,
+            acquired: chrono::Local::now().naive_local().to_string(),
+            transaction_id,
+        };
+        let sql = InsertStatement::default()
+            .into_table(StockLot::Table)
+            .columns([
+                StockLot::StockItemId,
+                StockLot::Quantity,
+                StockLot::Remaining,
+                StockLot::UnitPrice,
+                StockLot::Acquired,
+                StockLot::TransactionId,
+            ])
+            .values_panic([
+                lot.stock_item_id.into(),
+                lot.quantity.into(),
+                lot.remaining.into(),
+                lot.unit_price.into(),
+                lot.acquired.clone().into(),
+                lot.transaction_id.into(),
+            ])
+            .to_string(SqliteQueryBuilder);
+        let row = sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        lot.id = row.last_insert_rowid();
+        Ok(lot)
+    }
+
+    /// Links the newest lot of a stock item that has no transaction yet to `transaction_id`.
+    pub async fn link_transaction(
+        &self,
+        stock_item_id: i64,
+        transaction_id: i64,
+    ) -> Result<(), AppError> {
+        let lot = self
+            .get_by_stock_item(stock_item_id)
+            .await?
+            .into_iter()
+            .rev()
+            .find(|lot| lot.transaction_id.is_none());
+        let Some(lot) = lot else {
+            return Ok(());
+        };
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::update()
+            .table(StockLot::Table)
+            .values([(StockLot::TransactionId, transaction_id.into())])
+            .and_where(Expr::col(StockLot::Id).eq(lot.id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(())
+    }
+
+    async fn set_remaining(&self, id: i64, remaining: i32) -> Result<(), AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::update()
+            .table(StockLot::Table)
+            .values([(StockLot::Remaining, remaining.into())])
+            .and_where(Expr::col(StockLot::Id).eq(id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(())
+    }
+
+    /// Takes `quantity` units out of the open lots in `order`.
+    /// Returns less than `quantity` when the lots run out.
+    pub async fn consume(
+        &self,
+        stock_item_id: i64,
+        quantity: i32,
+        order: &LotOrder,
+    ) -> Result<Vec<ConsumedLot>, AppError> {
+        let mut lots = self.get_open(stock_item_id).await?;
+        match order {
+            LotOrder::Lifo => lots.reverse(),
+            LotOrder::Cheapest => lots.sort_by(|a, b| a.unit_price.total_cmp(&b.unit_price)),
+            LotOrder::Expensive => lots.sort_by(|a, b| b.unit_price.total_cmp(&a.unit_price)),
+            LotOrder::Fifo | LotOrder::Unknown(_) => {}
+        }
+
+        let mut left = quantity;
+        let mut consumed = vec![];
+        for lot in lots {
+            if left <= 0 {
+                break;
+            }
+            let taken = left.min(lot.remaining);
+            self.set_remaining(lot.id, lot.remaining - taken).await?;
+            consumed.push(ConsumedLot {
+                lot_id: lot.id,
+                quantity: taken,
+                unit_price: lot.unit_price,
+            });
+            left -= taken;
+        }
+        Ok(consumed)
+    }
+
+    /// Makes the open lots add up to `owned` after the quantity was changed by hand.
+    /// Missing units become a new lot at `unit_price`, extra units are taken oldest first.
+    pub async fn reconcile(
+        &self,
+        stock_item_id: i64,
+        owned: i32,
+        unit_price: f64,
+    ) -> Result<(), AppError> {
+        let held: i32 = self
+            .get_open(stock_item_id)
+            .await?
+            .iter()
+            .map(|lot| lot.remaining)
+            .sum();
+        if owned > held {
+            self.create(stock_item_id, owned - held, unit_price, None)
+                .await?;
+        } else if owned < held {
+            self.consume(stock_item_id, held - owned, &LotOrder::Fifo)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// The quantity left and its average unit price.
+    pub async fn get_aggregate(&self, stock_item_id: i64) -> Result<(i32, f64), AppError> {
+        let lots = self.get_open(stock_item_id).await?;
+        let owned: i32 = lots.iter().map(|lot| lot.remaining).sum();
+        if owned == 0 {
+            return Ok((0, 0.0));
+        }
+        let total: f64 = lots
+            .iter()
+            .map(|lot| lot.remaining as f64 * lot.unit_price)
+            .sum();
+        Ok((owned, total / owned as f64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{enums::LotOrder, wfm_client::mock_server::MockWfmServer};
+
+    #[tokio::test]
+    async fn consumes_lots_in_order() {
+        let server = MockWfmServer::start().await;
+        server.add_item("primed_flow_id", "primed_flow", "Primed Flow");
+        let db = server.database().await;
+        let item = db
+            .stock_item()
+            .create("primed_flow", 2, 20.0, None, 0, None)
+            .await
+            .unwrap();
+        db.stock_lot().create(item.id, 2, 16.0, None).await.unwrap();
+
+        let fifo = db
+            .stock_lot()
+            .consume(item.id, 3, &LotOrder::Fifo)
+            .await
+            .unwrap();
+        assert_eq!(
+            fifo.iter()
+                .map(|lot| (lot.quantity, lot.unit_price))
+                .collect::<Vec<_>>(),
+            vec![(2, 10.0), (1, 16.0)]
+        );
+        assert_eq!(
+            db.stock_lot().get_aggregate(item.id).await.unwrap(),
+            (1, 16.0)
+        );
+    }
+
+    #[tokio::test]
+    async fn returns_less_when_the_lots_run_out() {
+        let server = MockWfmServer::start().await;
+        server.add_item("primed_flow_id", "primed_flow", "Primed Flow");
+        let db = server.database().await;
+        let item = db
+            .stock_item()
+            .create("primed_flow", 1, 10.0, None, 0, None)
+            .await
+            .unwrap();
+
+        let consumed = db
+            .stock_lot()
+            .consume(item.id, 3, &LotOrder::Lifo)
+            .await
+            .unwrap();
+
+        assert_eq!(consumed.iter().map(|lot| lot.quantity).sum::<i32>(), 1);
+        assert_eq!(
+            db.stock_lot().get_aggregate(item.id).await.unwrap(),
+            (0, 0.0)
+        );
+        assert!(db.stock_lot().get_open(item.id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn reconciles_to_the_quantity_given() {
+        let server = MockWfmServer::start().await;
+        server.add_item("primed_flow_id", "primed_flow", "Primed Flow");
+        let db = server.database().await;
+        let item = db
+            .stock_item()
+            .create("primed_flow", 2, 20.0, None, 0, None)
+            .await
+            .unwrap();
+
+        db.stock_lot().reconcile(item.id, 3, 13.0).await.unwrap();
+        assert_eq!(
+            db.stock_lot()
+                .get_by_stock_item(item.id)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(
+            db.stock_lot().get_aggregate(item.id).await.unwrap(),
+            (3, 11.0)
+        );
+
+        db.stock_lot().reconcile(item.id, 3, 50.0).await.unwrap();
+        assert_eq!(
+            db.stock_lot().get_aggregate(item.id).await.unwrap(),
+            (3, 11.0)
+        );
+    }
+}
@@ -20,6 +20,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::{Pool, Row, Sqlite};
 
+use super::stock_event::StockEventSource;
 use super::stock_item::StockItemStruct;
 
 #[derive(Iden)]
@@ -87,9 +88,48 @@ pub struct MinMaxStruct {
 
 pub struct StockRivenModule<'a> {
     pub client: &'a DBClient,
+    // Recorded with the changes made through this module
+    pub source: StockEventSource,
+    pub reason: Option<String>,
 }
 
 impl<'a> StockRivenModule<'a> {
+    /// Records the changes made through the module as coming from `source`.
+    pub fn with_source(mut self, source: StockEventSource, reason: Option<&str>) -> Self {
+        self.source = source;
+        self.reason = reason.map(|reason| reason.to_string());
+        self
+    }
+
+    async fn record_changes(
+        &self,
+        previous: &StockRivenStruct,
+        current: &StockRivenStruct,
+    ) -> Result<(), AppError> {
+        self.client
+            .stock_event()
+            .record(
+                "riven",
+                current.id,
+                vec![
+                    ("status", Some(previous.status.clone()), Some(current.status.clone())),
+                    (
+                        "listed_price",
+                        previous.listed_price.map(|p| p.to_string()),
+                        current.listed_price.map(|p| p.to_string()),
+                    ),
+                    (
+                        "price",
+                        Some(format!("{:.2}", previous.price)),
+                        Some(format!("{:.2}", current.price)),
+                    ),
+                ],
+                self.source,
+                self.reason.clone(),
+            )
+            .await
+    }
+
     // Methods sea-query
 
     // Initialize the database
@@ -177,39 +217,6 @@ impl<'a> StockRivenModule<'a> {
             .await
             .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
 
-        // Alter Table
-        let mut table = Table::alter()
-            .table(StockRiven::Table)
-            .add_column(
-                ColumnDef::new(StockRiven::MatchRiven)
-                    .json()
-                    .not_null()
-                    .default(json!({})),
-            )
-            .to_string(SqliteQueryBuilder);
-        helper::alter_table(connection.clone(), &table).await?;
-
-        table = Table::alter()
-            .table(StockRiven::Table)
-            .add_column(
-                ColumnDef::new(StockRiven::MiniumPrice)
-                    .integer()
-                    .default(Value::Int(None)),
-            )
-            .to_string(SqliteQueryBuilder);
-        helper::alter_table(connection.clone(), &table).await?;
-
-        table = Table::alter()
-            .table(StockRiven::Table)
-            .add_column(
-                ColumnDef::new(StockRiven::Status)
-                    .string()
-                    .not_null()
-                    .default("pending"),
-            )
-            .to_string(SqliteQueryBuilder);
-        helper::alter_table(connection.clone(), &table).await?;
-
         Ok(true)
     }
 
@@ -264,6 +271,7 @@ impl<'a> StockRivenModule<'a> {
         re_rolls: i32,
         polarity: &str,
         minium_price: Option<i32>,
+        status: &str,
     ) -> Result<StockRivenStruct, AppError> {
         let connection = self.client.connection.lock().unwrap().clone();
         let cache = self.client.cache.lock().unwrap().clone();
@@ -307,7 +315,7 @@ impl<'a> StockRivenModule<'a> {
             price: price as f64,
             listed_price: None,
             private: false,
-            status: "pending".to_string(),
+            status: status.to_string(),
             created: chrono::Local::now().naive_local().to_string(),
         };
 
@@ -356,6 +364,19 @@ impl<'a> StockRivenModule<'a> {
             .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
         let id = row.last_insert_rowid();
         inventory.id = id;
+        self.client
+            .stock_event()
+            .record(
+                "riven",
+                id,
+                vec![
+                    ("status", None, Some(inventory.status.clone())),
+                    ("price", None, Some(format!("{:.2}", inventory.price))),
+                ],
+                self.source,
+                self.reason.clone(),
+            )
+            .await?;
 
         // Update UI
         self.emit(
@@ -387,6 +408,7 @@ impl<'a> StockRivenModule<'a> {
                 auction.item.re_rolls.expect("No re-rolls found") as i32,
                 &auction.item.polarity.clone().expect("No polarity found"),
                 None,
+                "pending",
             )
             .await?;
 
@@ -394,6 +416,7 @@ impl<'a> StockRivenModule<'a> {
     }
 
     pub async fn reset_listed_price(&self) -> Result<(), AppError> {
+        let previous = self.get_rivens().await?;
         let connection = self.client.connection.lock().unwrap().clone();
         let sql = Query::update()
             .table(StockRiven::Table)
@@ -401,13 +424,21 @@ impl<'a> StockRivenModule<'a> {
                 (StockRiven::ListedPrice, Value::Int(None)),
                 (StockRiven::Status, "pending".into()),
             ])
+            // Drafts stay drafts until they are completed
+            .and_where(Expr::col(StockRiven::Status).ne("draft"))
             .to_string(SqliteQueryBuilder);
         sqlx::query(&sql.replace("\\", ""))
             .execute(&connection)
             .await
             .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
 
-        self.emit("SET", json!(self.get_rivens().await?));
+        let rivens = self.get_rivens().await?;
+        for current in rivens.iter() {
+            if let Some(previous) = previous.iter().find(|riven| riven.id == current.id) {
+                self.record_changes(previous, current).await?;
+            }
+        }
+        self.emit("SET", json!(rivens));
         Ok(())
     }
     pub async fn update_by_id(
@@ -434,6 +465,10 @@ impl<'a> StockRivenModule<'a> {
             ));
         }
         let mut stock_riven = stock_riven.unwrap().clone();
+        let previous = stock_riven.clone();
+        // The listed price is cleared unless a new one is given, -1 clears it too
+        let listed_price = listed_price.filter(|price| *price > -1);
+        stock_riven.listed_price = listed_price;
         let mut values = vec![(StockRiven::ListedPrice, listed_price.into())];
 
         if order_id.is_some() {
@@ -463,11 +498,6 @@ impl<'a> StockRivenModule<'a> {
             values.push((StockRiven::MiniumPrice, minium_price.into()));
         }
 
-        if listed_price.is_some() && listed_price.unwrap() > -1 {
-            stock_riven.listed_price = listed_price;
-            values.push((StockRiven::ListedPrice, listed_price.into()));
-        }
-
         if visibility.is_some() {
             stock_riven.private = !visibility.unwrap();
             values.push((StockRiven::Private, stock_riven.private.into()));
@@ -512,6 +542,8 @@ impl<'a> StockRivenModule<'a> {
             .await
             .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
 
+        self.record_changes(&previous, &stock_riven).await?;
+
         self.emit(
             "CREATE_OR_UPDATE",
             serde_json::to_value(stock_riven.clone()).unwrap(),
@@ -538,6 +570,20 @@ impl<'a> StockRivenModule<'a> {
             .execute(&connection)
             .await
             .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        self.client
+            .stock_event()
+            .record(
+                "riven",
+                id,
+                vec![(
+                    "status",
+                    stock_item.map(|riven| riven.status.clone()),
+                    Some("deleted".to_string()),
+                )],
+                self.source,
+                self.reason.clone(),
+            )
+            .await?;
         self.emit(
             "DELETE",
             serde_json::to_value(stock_item.unwrap().clone()).unwrap(),
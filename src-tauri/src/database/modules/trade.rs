@@ -0,0 +1,548 @@
+use std::collections::HashMap;
+
+use crate::{
+    database::client::DBClient,
+    error::AppError,
+    helper, logger,
+    structs::TradeClassification,
+    wf_ee_log_parser::events::on_new_trading::{PlayerTradeStruct, TradeItemStruct},
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use eyre::eyre;
+use sea_query::{
+    ColumnDef, Expr, ForeignKey, ForeignKeyAction, Iden, InsertStatement, Order, Query,
+    SelectStatement, SqliteQueryBuilder, Table,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::SqliteConnection;
+
+pub struct TradeModule<'a> {
+    pub client: &'a DBClient,
+}
+#[derive(Iden)]
+pub enum Trade {
+    Table,
+    Id,
+    UserName,
+    TradeType,
+    TotalPlatinum,
+    WhisperId,
+    // The raw EE.log lines of the trade
+    Logs,
+    Created,
+}
+
+#[derive(Iden)]
+pub enum TradeItem {
+    Table,
+    Id,
+    TradeId,
+    // "offered" or "received"
+    Direction,
+    Name,
+    WfmId,
+    WfmUrlName,
+    DisplayName,
+    Quantity,
+    Rank,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
+pub struct TradeStruct {
+    pub id: i64,
+    pub user_name: String,
+    pub trade_type: String,
+    pub total_platinum: i32,
+    pub whisper_id: Option<i64>,
+    pub logs: sqlx::types::Json<Vec<String>>,
+    pub created: String,
+    #[sqlx(skip)]
+    pub offerings: Vec<TradeItemStruct>,
+    #[sqlx(skip)]
+    pub receiving: Vec<TradeItemStruct>,
+}
+
+#[derive(sqlx::FromRow, Clone, Debug)]
+struct TradeItemRow {
+    trade_id: i64,
+    direction: String,
+    name: String,
+    wfm_id: Option<String>,
+    wfm_url_name: Option<String>,
+    display_name: String,
+    quantity: i32,
+    rank: i32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TradeFilter {
+    // Part of the name of the player we traded with
+    pub partner: Option<String>,
+    // Part of the name or url of an item in the trade
+    pub item: Option<String>,
+    pub trade_type: Option<String>,
+    pub from_date: Option<DateTime<Utc>>,
+    pub to_date: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TradePage {
+    pub total: i64,
+    pub page: i64,
+    pub limit: i64,
+    pub results: Vec<TradeStruct>,
+}
+
+impl TradeStruct {
+    pub fn to_player_trade(&self) -> PlayerTradeStruct {
+        PlayerTradeStruct {
+            crated_at: self.created.clone(),
+            user_name: self.user_name.clone(),
+            trade_type: serde_json::from_value(Value::String(self.trade_type.clone()))
+                .unwrap_or(TradeClassification::Unknown),
+            total_platinum: self.total_platinum,
+            offerings: self.offerings.clone(),
+            receiving: self.receiving.clone(),
+            whisper_id: self.whisper_id,
+        }
+    }
+}
+
+impl<'a> TradeModule<'a> {
+    pub async fn initialize(&self) -> Result<bool, AppError> {
+        self.create_tables().await?;
+        self.migrate_json_files().await?;
+        Ok(true)
+    }
+
+    /// Creates the trade tables, without importing the old `tradings.json` files.
+    pub async fn create_tables(&self) -> Result<(), AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Table::create()
+            .table(Trade::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(Trade::Id)
+                    .integer()
+                    .not_null()
+                    .auto_increment()
+                    .primary_key(),
+            )
+            .col(ColumnDef::new(Trade::UserName).string().not_null())
+            .col(ColumnDef::new(Trade::TradeType).string().not_null())
+            .col(ColumnDef::new(Trade::TotalPlatinum).integer().not_null())
+            .col(ColumnDef::new(Trade::WhisperId).integer())
+            .col(ColumnDef::new(Trade::Logs).json().not_null())
+            .col(ColumnDef::new(Trade::Created).date_time().not_null())
+            .build(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        let sql = Table::create()
+            .table(TradeItem::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(TradeItem::Id)
+                    .integer()
+                    .not_null()
+                    .auto_increment()
+                    .primary_key(),
+            )
+            .col(ColumnDef::new(TradeItem::TradeId).integer().not_null())
+            .col(ColumnDef::new(TradeItem::Direction).string().not_null())
+            .col(ColumnDef::new(TradeItem::Name).string().not_null())
+            .col(ColumnDef::new(TradeItem::WfmId).string())
+            .col(ColumnDef::new(TradeItem::WfmUrlName).string())
+            .col(ColumnDef::new(TradeItem::DisplayName).string().not_null())
+            .col(ColumnDef::new(TradeItem::Quantity).integer().not_null())
+            .col(ColumnDef::new(TradeItem::Rank).integer().not_null())
+            .foreign_key(
+                ForeignKey::create()
+                    .from(TradeItem::Table, TradeItem::TradeId)
+                    .to(Trade::Table, Trade::Id)
+                    .on_delete(ForeignKeyAction::Cascade),
+            )
+            .build(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(())
+    }
+
+    /// Moves the trades of the old `tradings.json` files into the database.
+    /// Migrated files are renamed so they are only imported once.
+    async fn migrate_json_files(&self) -> Result<(), AppError> {
+        let logs_path = helper::get_app_roaming_path().join("logs");
+        let folders = match std::fs::read_dir(logs_path) {
+            Ok(folders) => folders,
+            Err(_) => return Ok(()),
+        };
+        for folder in folders.flatten() {
+            let path = folder.path().join("tradings.json");
+            let file = match std::fs::File::open(&path) {
+                Ok(file) => file,
+                Err(_) => continue,
+            };
+            let data: Vec<Value> = match serde_json::from_reader(std::io::BufReader::new(file)) {
+                Ok(data) => data,
+                Err(e) => {
+                    logger::warning_con(
+                        "Database",
+                        format!("Could not read {}: {}", path.display(), e).as_str(),
+                    );
+                    continue;
+                }
+            };
+            let connection = self.client.connection.lock().unwrap().clone();
+            let mut tx = connection
+                .begin()
+                .await
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+            let mut count = 0;
+            for entry in data {
+                let logs: Vec<String> = entry
+                    .get("current_trade_logs")
+                    .and_then(|logs| serde_json::from_value(logs.clone()).ok())
+                    .unwrap_or_default();
+                match serde_json::from_value::<PlayerTradeStruct>(entry) {
+                    Ok(trade) if Self::normalize_date(&trade.crated_at).is_none() => {
+                        logger::warning_con(
+                            "Database",
+                            format!(
+                                "Skipping trade in {} with an invalid date: {}",
+                                path.display(),
+                                trade.crated_at
+                            )
+                            .as_str(),
+                        )
+                    }
+                    Ok(trade) => {
+                        Self::insert(&mut *tx, &trade, &logs).await?;
+                        count += 1;
+                    }
+                    Err(e) => logger::warning_con(
+                        "Database",
+                        format!("Skipping trade in {}: {}", path.display(), e).as_str(),
+                    ),
+                }
+            }
+            // A file is only renamed once all of its trades are stored
+            tx.commit()
+                .await
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+            std::fs::rename(&path, path.with_extension("json.migrated"))
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+            logger::info_con(
+                "Database",
+                format!("Migrated {} trades from {}", count, path.display()).as_str(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Stores the trade dates as UTC so they can be compared as text.
+    fn normalize_date(date: &str) -> Option<String> {
+        DateTime::parse_from_rfc3339(date)
+            .or_else(|_| DateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S%.f %:z"))
+            .map(|date| {
+                date.with_timezone(&Utc)
+                    .to_rfc3339_opts(SecondsFormat::Secs, true)
+            })
+            .ok()
+    }
+
+    fn apply_filter(query: &mut SelectStatement, filter: &TradeFilter) {
+        if let Some(partner) = filter.partner.clone().filter(|p| !p.is_empty()) {
+            query.and_where(Expr::col(Trade::UserName).like(format!("%{}%", partner)));
+        }
+        if let Some(item) = filter.item.clone().filter(|i| !i.is_empty()) {
+            let item = format!("%{}%", item);
+            query.and_where(Expr::cust_with_values(
+                "id IN (SELECT trade_id FROM trade_item WHERE name LIKE ? OR display_name LIKE ? OR wfm_url_name LIKE ?)",
+                [item.clone(), item.clone(), item],
+            ));
+        }
+        if let Some(trade_type) = filter.trade_type.clone().filter(|t| !t.is_empty()) {
+            query.and_where(Expr::col(Trade::TradeType).eq(trade_type));
+        }
+        if let Some(from_date) = filter.from_date {
+            query.and_where(
+                Expr::col(Trade::Created)
+                    .gte(from_date.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            );
+        }
+        if let Some(to_date) = filter.to_date {
+            query.and_where(
+                Expr::col(Trade::Created).lte(to_date.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            );
+        }
+    }
+
+    /// Loads the items of the trades.
+    async fn fill_items(&self, trades: &mut Vec<TradeStruct>) -> Result<(), AppError> {
+        if trades.is_empty() {
+            return Ok(());
+        }
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .columns([
+                TradeItem::TradeId,
+                TradeItem::Direction,
+                TradeItem::Name,
+                TradeItem::WfmId,
+                TradeItem::WfmUrlName,
+                TradeItem::DisplayName,
+                TradeItem::Quantity,
+                TradeItem::Rank,
+            ])
+            .from(TradeItem::Table)
+            .and_where(Expr::col(TradeItem::TradeId).is_in(trades.iter().map(|t| t.id)))
+            .order_by(TradeItem::Id, Order::Asc)
+            .to_string(SqliteQueryBuilder);
+        let rows = sqlx::query_as::<_, TradeItemRow>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        let mut items: HashMap<i64, Vec<TradeItemRow>> = HashMap::new();
+        for row in rows {
+            items.entry(row.trade_id).or_default().push(row);
+        }
+        for trade in trades.iter_mut() {
+            for row in items.remove(&trade.id).unwrap_or_default() {
+                let item = TradeItemStruct {
+                    name: row.name,
+                    wfm_id: row.wfm_id,
+                    wfm_url_name: row.wfm_url_name,
+                    display_name: row.display_name,
+                    quantity: row.quantity,
+                    rank: row.rank,
+                };
+                if row.direction == "offered" {
+                    trade.offerings.push(item);
+                } else {
+                    trade.receiving.push(item);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a page of trades matching the filter, newest first. Pages start at 1.
+    pub async fn get_trades(
+        &self,
+        filter: TradeFilter,
+        page: i64,
+        limit: i64,
+    ) -> Result<TradePage, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let page = page.max(1);
+        let limit = limit.max(1);
+
+        let mut count_query = Query::select();
+        count_query.expr(Expr::col(Trade::Id).count()).from(Trade::Table);
+        Self::apply_filter(&mut count_query, &filter);
+        let total: i64 = sqlx::query_scalar(&count_query.to_string(SqliteQueryBuilder))
+            .fetch_one(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+
+        let mut query = Query::select();
+        query
+            .columns([
+                Trade::Id,
+                Trade::UserName,
+                Trade::TradeType,
+                Trade::TotalPlatinum,
+                Trade::WhisperId,
+                Trade::Logs,
+                Trade::Created,
+            ])
+            .from(Trade::Table)
+            .order_by(Trade::Created, Order::Desc)
+            .order_by(Trade::Id, Order::Desc)
+            .limit(limit as u64)
+            .offset(((page - 1) * limit) as u64);
+        Self::apply_filter(&mut query, &filter);
+        let mut results = sqlx::query_as::<_, TradeStruct>(&query.to_string(SqliteQueryBuilder))
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        self.fill_items(&mut results).await?;
+        Ok(TradePage {
+            total,
+            page,
+            limit,
+            results,
+        })
+    }
+
+    /// Returns every stored trade, used to find duplicates when importing old logs.
+    pub async fn get_all(&self) -> Result<Vec<TradeStruct>, AppError> {
+        let page = self.get_trades(TradeFilter::default(), 1, i64::MAX).await?;
+        Ok(page.results)
+    }
+
+    pub async fn get_by_id(&self, id: i64) -> Result<Option<TradeStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .columns([
+                Trade::Id,
+                Trade::UserName,
+                Trade::TradeType,
+                Trade::TotalPlatinum,
+                Trade::WhisperId,
+                Trade::Logs,
+                Trade::Created,
+            ])
+            .from(Trade::Table)
+            .and_where(Expr::col(Trade::Id).eq(id))
+            .to_string(SqliteQueryBuilder);
+        let trade = sqlx::query_as::<_, TradeStruct>(&sql)
+            .fetch_optional(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        let mut trades: Vec<TradeStruct> = trade.into_iter().collect();
+        self.fill_items(&mut trades).await?;
+        Ok(trades.pop())
+    }
+
+    /// Inserts the trade and its items on `connection`, the caller owns the transaction.
+    async fn insert(
+        connection: &mut SqliteConnection,
+        trade: &PlayerTradeStruct,
+        logs: &Vec<String>,
+    ) -> Result<TradeStruct, AppError> {
+        let created = Self::normalize_date(&trade.crated_at).ok_or(AppError::new(
+            "Database",
+            eyre!("Invalid trade date: {}", trade.crated_at),
+        ))?;
+        let mut entry = TradeStruct {
+            id: 0,
+            user_name: trade.user_name.clone(),
+            trade_type: serde_json::to_value(&trade.trade_type)
+                .ok()
+                .and_then(|v| v.as_str().map(|s| s.to_string()))
+                .unwrap_or("Unknown".to_string()),
+            total_platinum: trade.total_platinum,
+            whisper_id: trade.whisper_id,
+            logs: sqlx::types::Json(logs.clone()),
+            created,
+            offerings: trade.offerings.clone(),
+            receiving: trade.receiving.clone(),
+        };
+        let sql = InsertStatement::default()
+            .into_table(Trade::Table)
+            .columns([
+                Trade::UserName,
+                Trade::TradeType,
+                Trade::TotalPlatinum,
+                Trade::WhisperId,
+                Trade::Logs,
+                Trade::Created,
+            ])
+            .values_panic([
+                entry.user_name.clone().into(),
+                entry.trade_type.clone().into(),
+                entry.total_platinum.into(),
+                entry.whisper_id.into(),
+                serde_json::to_value(&entry.logs).unwrap().into(),
+                entry.created.clone().into(),
+            ])
+            .to_string(SqliteQueryBuilder);
+        let row = sqlx::query(&sql.replace("\\", ""))
+            .execute(&mut *connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        entry.id = row.last_insert_rowid();
+
+        let items = trade
+            .offerings
+            .iter()
+            .map(|item| ("offered", item))
+            .chain(trade.receiving.iter().map(|item| ("received", item)));
+        for (direction, item) in items {
+            let sql = InsertStatement::default()
+                .into_table(TradeItem::Table)
+                .columns([
+                    TradeItem::TradeId,
+                    TradeItem::Direction,
+                    TradeItem::Name,
+                    TradeItem::WfmId,
+                    TradeItem::WfmUrlName,
+                    TradeItem::DisplayName,
+                    TradeItem::Quantity,
+                    TradeItem::Rank,
+                ])
+                .values_panic([
+                    entry.id.into(),
+                    direction.into(),
+                    item.name.clone().into(),
+                    item.wfm_id.clone().into(),
+                    item.wfm_url_name.clone().into(),
+                    item.display_name.clone().into(),
+                    item.quantity.into(),
+                    item.rank.into(),
+                ])
+                .to_string(SqliteQueryBuilder);
+            sqlx::query(&sql)
+                .execute(&mut *connection)
+                .await
+                .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        }
+        Ok(entry)
+    }
+
+    /// Stores a trade with its items and the raw log lines.
+    pub async fn create(
+        &self,
+        trade: &PlayerTradeStruct,
+        logs: &Vec<String>,
+    ) -> Result<TradeStruct, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let mut tx = connection
+            .begin()
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        let entry = Self::insert(&mut *tx, trade, logs).await?;
+        tx.commit()
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        self.emit("CREATE_OR_UPDATE", serde_json::to_value(&entry).unwrap());
+        Ok(entry)
+    }
+
+    pub async fn delete(&self, id: i64) -> Result<TradeStruct, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let trade = match self.get_by_id(id).await? {
+            Some(trade) => trade,
+            None => return Err(AppError::new("Database", eyre!("Trade not found in database"))),
+        };
+        let sql = Query::delete()
+            .from_table(TradeItem::Table)
+            .and_where(Expr::col(TradeItem::TradeId).eq(id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        let sql = Query::delete()
+            .from_table(Trade::Table)
+            .and_where(Expr::col(Trade::Id).eq(id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        self.emit("DELETE", serde_json::to_value(&trade).unwrap());
+        Ok(trade)
+    }
+
+    pub fn emit(&self, operation: &str, data: serde_json::Value) {
+        helper::emit_update("trades", operation, Some(data));
+    }
+}
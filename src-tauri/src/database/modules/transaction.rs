@@ -287,6 +287,21 @@ impl<'a> TransactionModule<'a> {
         Ok(transaction.clone())
     }
     
+    /// Moves a transaction to the date it happened, used by imports.
+    pub async fn set_created(&self, id: i64, created: &str) -> Result<(), AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::update()
+            .table(Transaction::Table)
+            .values([(Transaction::Created, created.into())])
+            .and_where(Expr::col(Transaction::Id).eq(id))
+            .to_string(SqliteQueryBuilder);
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(())
+    }
+
     pub async fn delete(&self, id: i64) -> Result<(), AppError> {
         let connection = self.client.connection.lock().unwrap().clone();
 
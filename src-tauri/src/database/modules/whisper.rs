@@ -0,0 +1,204 @@
+use crate::{database::client::DBClient, error::AppError, helper, logger};
+use eyre::eyre;
+use sea_query::{ColumnDef, Expr, Iden, InsertStatement, Order, Query, SqliteQueryBuilder, Table};
+use serde::{Deserialize, Serialize};
+
+pub struct WhisperModule<'a> {
+    pub client: &'a DBClient,
+}
+#[derive(Iden)]
+pub enum Whisper {
+    Table,
+    Id,
+    UserName,
+    // What the player wants to do, "buy" or "sell"
+    OfferType,
+    Url,
+    Name,
+    Rank,
+    Price,
+    OrderId,
+    MatchStatus,
+    Message,
+    Created,
+}
+
+#[derive(sqlx::FromRow, Serialize, Deserialize, Clone, Debug)]
+pub struct WhisperStruct {
+    pub id: i64,
+    pub user_name: String,
+    pub offer_type: String,
+    pub url: Option<String>,
+    pub name: String,
+    pub rank: Option<i64>,
+    pub price: i64,
+    pub order_id: Option<String>,
+    pub match_status: String,
+    pub message: String,
+    pub created: String,
+}
+
+impl<'a> WhisperModule<'a> {
+    pub async fn initialize(&self) -> Result<bool, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Table::create()
+            .table(Whisper::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(Whisper::Id)
+                    .integer()
+                    .not_null()
+                    .auto_increment()
+                    .primary_key(),
+            )
+            .col(ColumnDef::new(Whisper::UserName).string().not_null())
+            .col(ColumnDef::new(Whisper::OfferType).string().not_null())
+            .col(ColumnDef::new(Whisper::Url).string())
+            .col(ColumnDef::new(Whisper::Name).string().not_null())
+            .col(ColumnDef::new(Whisper::Rank).integer())
+            .col(ColumnDef::new(Whisper::Price).integer().not_null())
+            .col(ColumnDef::new(Whisper::OrderId).string())
+            .col(ColumnDef::new(Whisper::MatchStatus).string().not_null())
+            .col(ColumnDef::new(Whisper::Message).string().not_null())
+            .col(ColumnDef::new(Whisper::Created).date_time().not_null())
+            .build(SqliteQueryBuilder);
+
+        sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(true)
+    }
+
+    fn get_columns() -> [Whisper; 11] {
+        [
+            Whisper::Id,
+            Whisper::UserName,
+            Whisper::OfferType,
+            Whisper::Url,
+            Whisper::Name,
+            Whisper::Rank,
+            Whisper::Price,
+            Whisper::OrderId,
+            Whisper::MatchStatus,
+            Whisper::Message,
+            Whisper::Created,
+        ]
+    }
+
+    pub async fn get_whispers(&self) -> Result<Vec<WhisperStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .columns(Self::get_columns())
+            .from(Whisper::Table)
+            .order_by(Whisper::Id, Order::Desc)
+            .to_string(SqliteQueryBuilder);
+        let rows = sqlx::query_as::<_, WhisperStruct>(&sql)
+            .fetch_all(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(rows)
+    }
+
+    /// Returns the last whisper we got from the player since `since`, used to link it to a trade.
+    pub async fn get_last_by_user(
+        &self,
+        user_name: &str,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Option<WhisperStruct>, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let sql = Query::select()
+            .columns(Self::get_columns())
+            .from(Whisper::Table)
+            .and_where(Expr::cust_with_values(
+                "lower(user_name) = ?",
+                [user_name.to_lowercase()],
+            ))
+            .and_where(Expr::col(Whisper::Created).gte(since.to_rfc3339()))
+            .order_by(Whisper::Id, Order::Desc)
+            .limit(1)
+            .to_string(SqliteQueryBuilder);
+        let row = sqlx::query_as::<_, WhisperStruct>(&sql)
+            .fetch_optional(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        Ok(row)
+    }
+
+    pub async fn create(
+        &self,
+        user_name: &str,
+        offer_type: &str,
+        url: Option<String>,
+        name: &str,
+        rank: Option<i64>,
+        price: i64,
+        order_id: Option<String>,
+        match_status: &str,
+        message: &str,
+    ) -> Result<WhisperStruct, AppError> {
+        let connection = self.client.connection.lock().unwrap().clone();
+        let mut whisper = WhisperStruct {
+            id: 0,
+            user_name: user_name.to_string(),
+            offer_type: offer_type.to_string(),
+            url,
+            name: name.to_string(),
+            rank,
+            price,
+            order_id,
+            match_status: match_status.to_string(),
+            message: message.to_string(),
+            created: chrono::Utc::now().to_rfc3339(),
+        };
+        logger::info_con(
+            "Database",
+            format!(
+                "Creating Whisper: {} {} {} {}",
+                whisper.user_name, whisper.offer_type, whisper.name, whisper.price
+            )
+            .as_str(),
+        );
+        let sql = InsertStatement::default()
+            .into_table(Whisper::Table)
+            .columns([
+                Whisper::UserName,
+                Whisper::OfferType,
+                Whisper::Url,
+                Whisper::Name,
+                Whisper::Rank,
+                Whisper::Price,
+                Whisper::OrderId,
+                Whisper::MatchStatus,
+                Whisper::Message,
+                Whisper::Created,
+            ])
+            .values_panic([
+                whisper.user_name.clone().into(),
+                whisper.offer_type.clone().into(),
+                whisper.url.clone().into(),
+                whisper.name.clone().into(),
+                whisper.rank.into(),
+                whisper.price.into(),
+                whisper.order_id.clone().into(),
+                whisper.match_status.clone().into(),
+                whisper.message.clone().into(),
+                whisper.created.clone().into(),
+            ])
+            .to_string(SqliteQueryBuilder);
+        let row = sqlx::query(&sql)
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
+        whisper.id = row.last_insert_rowid();
+        self.emit(
+            "CREATE_OR_UPDATE",
+            serde_json::to_value(whisper.clone()).unwrap(),
+        );
+        Ok(whisper)
+    }
+
+    pub fn emit(&self, operation: &str, data: serde_json::Value) {
+        helper::emit_update("whispers", operation, Some(data));
+    }
+}
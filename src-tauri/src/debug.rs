@@ -2,7 +2,10 @@ use crate::{
     auth::AuthState,
     database::{
         client::DBClient,
-        modules::transaction::{Transaction, TransactionStruct},
+        modules::{
+            import_export::parse_created,
+            transaction::{Transaction, TransactionStruct},
+        },
     },
     error::AppError,
     logger,
@@ -59,6 +62,8 @@ impl DebugClient {
         let watdb = SqlitePool::connect(db_path.as_str()).await.unwrap();
 
         if import_type == "inventory" {
+            // Keep a copy of what is about to be deleted
+            db.backups().create("reset").await?;
             // Delete all data in the database to prevent duplicates and errors
             sqlx::query("DELETE FROM 'stock_item'")
                 .execute(&dbcon)
@@ -90,6 +95,8 @@ impl DebugClient {
                     .await?;
             }
         } else if import_type == "transactions" {
+            // Keep a copy of what is about to be deleted
+            db.backups().create("reset").await?;
             // Delete all data in the database to prevent duplicates and errors
             sqlx::query("DELETE FROM 'transaction'")
                 .execute(&dbcon)
@@ -105,6 +112,15 @@ impl DebugClient {
                 let datetime = row.try_get::<String, _>(2).unwrap();
                 let transaction_type = row.try_get::<String, _>(3).unwrap();
                 let price = row.try_get::<i64, _>(4).unwrap();
+                let Some(datetime) = parse_created(&datetime) else {
+                    logger::error(
+                        "Database",
+                        format!("Could not read the date {} of {}", datetime, name).as_str(),
+                        true,
+                        Some(self.log_file.as_str()),
+                    );
+                    continue;
+                };
 
                 let item = self.cache.lock()?.items().find_type(&name)?;
                 if item.is_none() {
@@ -162,25 +178,26 @@ impl DebugClient {
 
     pub async fn reset_data(&self, reset_type: String) -> Result<bool, AppError> {
         let db = self.db.lock()?.clone();
-        let db: Pool<Sqlite> = db.get_connection().clone().lock()?.clone();
-        if reset_type == "inventory" {
-            // Delete all data in the database to prevent duplicates and errors
-            sqlx::query("DELETE FROM inventory;")
-                .execute(&db)
-                .await
-                .map_err(|e| AppError::new("Debug", eyre!(e.to_string())))?;
-        } else if reset_type == "transactions" {
-            // Delete all data in the database to prevent duplicates and errors
-            sqlx::query("DELETE FROM transaction;")
-                .execute(&db)
-                .await
-                .map_err(|e| AppError::new("Debug", eyre!(e.to_string())))?;
-        } else {
-            logger::error_con(
-                "Debug",
-                format!("Could not find import type {}", reset_type).as_str(),
-            );
-        }
+        let table = match reset_type.as_str() {
+            "inventory" | "stock_item" => "stock_item",
+            "stock_riven" => "stock_riven",
+            "transactions" => "\"transaction\"",
+            _ => {
+                logger::error_con(
+                    "Debug",
+                    format!("Could not find import type {}", reset_type).as_str(),
+                );
+                return Ok(false);
+            }
+        };
+        // Keep a copy of what is about to be deleted
+        db.backups().create("reset").await?;
+        let connection: Pool<Sqlite> = db.get_connection().clone().lock()?.clone();
+        // Delete all data in the database to prevent duplicates and errors
+        sqlx::query(format!("DELETE FROM {};", table).as_str())
+            .execute(&connection)
+            .await
+            .map_err(|e| AppError::new("Debug", eyre!(e.to_string())))?;
         Ok(true)
     }
 }
@@ -178,6 +178,63 @@ impl<'de> Deserialize<'de> for StockMode {
         })
     }
 }
+/// The order purchase lots are sold in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LotOrder {
+    // Oldest lot first
+    Fifo,
+    // Newest lot first
+    Lifo,
+    Cheapest,
+    Expensive,
+    Unknown(String),
+}
+impl LotOrder {
+    pub fn as_str(&self) -> &str {
+        match *self {
+            LotOrder::Fifo => "fifo",
+            LotOrder::Lifo => "lifo",
+            LotOrder::Cheapest => "cheapest",
+            LotOrder::Expensive => "expensive",
+            LotOrder::Unknown(ref i) => i,
+        }
+    }
+}
+impl Serialize for LotOrder {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let value = match self {
+            LotOrder::Unknown(i) => {
+                logger::critical_file(
+                    "LotOrder",
+                    format!("Unknown LotOrder: {}", i).as_str(),
+                    Some("enums.log"),
+                );
+                "unknown"
+            }
+            order => order.as_str(),
+        };
+        serializer.serialize_str(value)
+    }
+}
+
+impl<'de> Deserialize<'de> for LotOrder {
+    fn deserialize<D>(deserializer: D) -> Result<LotOrder, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s: String = String::deserialize(deserializer)?;
+        Ok(match s.as_str() {
+            "fifo" => LotOrder::Fifo,
+            "lifo" => LotOrder::Lifo,
+            "cheapest" => LotOrder::Cheapest,
+            "expensive" => LotOrder::Expensive,
+            s => LotOrder::Unknown(s.to_string()),
+        })
+    }
+}
 #[derive(PartialEq, Debug, Clone)]
 pub enum LogLevel {
     Info,
@@ -27,6 +27,78 @@ pub struct ErrorApiResponse {
 
     #[serde(skip_serializing_if = "Option::is_none", rename = "method")]
     pub method: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty", rename = "errors")]
+    pub errors: Vec<WfmError>,
+}
+
+impl ErrorApiResponse {
+    /// The first typed error of the response, if any.
+    pub fn wfm_error(&self) -> Option<&WfmError> {
+        self.errors.first()
+    }
+}
+
+/// An error returned by warframe.market, parsed from the `error` object of the response.
+/// The object maps a field (`_form` and `request` for the whole request) to message keys like `app.form.not_exist`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WfmError {
+    NotFound { field: String, message: String },
+    AlreadyExists { field: String, message: String },
+    Validation { field: String, message: String },
+    Unauthorized { message: String },
+    Banned { message: String },
+    RateLimited { retry_after: Option<u64> },
+    ServerError { status_code: i64 },
+    // The request timed out or could not connect
+    Unreachable { message: String },
+    Unknown { field: String, message: String },
+}
+
+impl WfmError {
+    /// Parses one message of the `error` object.
+    pub fn parse(status_code: i64, field: &str, message: &str) -> Self {
+        let field = field.to_string();
+        let message = message.to_string();
+        if message.contains("banned") {
+            WfmError::Banned { message }
+        } else if status_code == 401 || message.contains("unauthorized") {
+            WfmError::Unauthorized { message }
+        } else if status_code == 429 {
+            WfmError::RateLimited { retry_after: None }
+        } else if status_code >= 500 {
+            WfmError::ServerError { status_code }
+        } else if message.ends_with("not_exist") || message.ends_with("not_found") {
+            WfmError::NotFound { field, message }
+        } else if message.contains("already_created") || message.contains("already_exist") {
+            WfmError::AlreadyExists { field, message }
+        } else if field != "_form" && field != "request" || message.ends_with(".invalid") {
+            WfmError::Validation { field, message }
+        } else {
+            WfmError::Unknown { field, message }
+        }
+    }
+
+    /// The error for a response without an `error` object, like a 429 or 5xx page.
+    pub fn from_status(status_code: i64, headers: &HeaderMap) -> Self {
+        match status_code {
+            429 => WfmError::RateLimited {
+                retry_after: headers
+                    .get("retry-after")
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.trim().parse().ok()),
+            },
+            401 => WfmError::Unauthorized {
+                message: "unauthorized".to_string(),
+            },
+            status_code if status_code >= 500 => WfmError::ServerError { status_code },
+            status_code => WfmError::Unknown {
+                field: "request".to_string(),
+                message: format!("status {}", status_code),
+            },
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -40,6 +112,7 @@ pub struct AppError {
     component: String,
     eyre_report: String,
     log_level: LogLevel,
+    wfm_error: Option<WfmError>,
 }
 impl AppError {
     // Custom constructor
@@ -48,6 +121,7 @@ impl AppError {
             component: component.to_string(),
             eyre_report: format!("{:?}", eyre_report),
             log_level: LogLevel::Critical,
+            wfm_error: None,
         }
     }
     // Custom constructor
@@ -58,6 +132,7 @@ impl AppError {
         log_level: LogLevel,
     ) -> Self {
         let mut new_err: AppError = AppError::new_with_level(component, eyre_report, log_level);
+        new_err.wfm_error = err.wfm_error().cloned();
         let mut cause = new_err.cause();
         let backtrace = new_err.backtrace();
         let mut extra = new_err.extra_data();
@@ -104,6 +179,7 @@ impl AppError {
             component: component.to_string(),
             eyre_report: format!("{:?}", eyre_report),
             log_level,
+            wfm_error: None,
         }
     }
     pub fn get_info(&self) -> (String, String, Value) {
@@ -176,6 +252,10 @@ impl AppError {
     pub fn log_level(&self) -> LogLevel {
         self.log_level.clone()
     }
+    // Getter for the warframe.market error
+    pub fn wfm_error(&self) -> Option<&WfmError> {
+        self.wfm_error.as_ref()
+    }
     // Getter for extra_data
     pub fn extra_data(&self) -> Value {
         let (_before_location, _after_location, json) = self.get_info();
@@ -190,6 +270,7 @@ impl AppError {
             "backtrace": self.backtrace(),
             "log_level": self.log_level(),
             "extra_data": self.extra_data(),
+            "wfm_error": self.wfm_error,
         })
     }
 }
@@ -229,3 +310,30 @@ pub fn create_log_file(file: String, e: &AppError) {
         Some(file.as_str()),
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_invalid_forms_as_validation_errors() {
+        assert_eq!(
+            WfmError::parse(400, "_form", "app.form.invalid"),
+            WfmError::Validation {
+                field: "_form".to_string(),
+                message: "app.form.invalid".to_string(),
+            }
+        );
+        assert_eq!(
+            WfmError::parse(400, "_form", "app.form.something_else"),
+            WfmError::Unknown {
+                field: "_form".to_string(),
+                message: "app.form.something_else".to_string(),
+            }
+        );
+        assert!(matches!(
+            WfmError::parse(400, "_form", "app.auction.not_exist"),
+            WfmError::NotFound { .. }
+        ));
+    }
+}
@@ -24,6 +24,9 @@ use crate::{
     PACKAGEINFO,
 };
 pub static WINDOW: Lazy<Mutex<Option<Window>>> = Lazy::new(|| Mutex::new(None));
+pub static WARFRAME_LOG_FOLDER: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+static STEAM_LIBRARY_PATH: Lazy<regex::Regex> =
+    Lazy::new(|| regex::Regex::new(r#""path"\s+"(.+?)""#).unwrap());
 
 #[derive(Debug)]
 pub enum ColumnType {
@@ -162,6 +165,127 @@ pub fn get_app_roaming_path() -> PathBuf {
     }
 }
 
+/// Returns the folders where Warframe may write EE.log and Launcher.log,
+/// the native folder first, then the Proton and Wine prefixes on Linux.
+pub fn get_warframe_log_folder_candidates() -> Vec<PathBuf> {
+    let mut folders = vec![get_app_local_path().join("Warframe")];
+    if !cfg!(target_os = "linux") {
+        return folders;
+    }
+    let base_dirs = match BaseDirs::new() {
+        Some(base_dirs) => base_dirs,
+        None => return folders,
+    };
+    let user = std::env::var("USER").unwrap_or_default();
+    let wine_prefix = std::env::var("WINEPREFIX").ok().map(PathBuf::from);
+    folders.extend(get_linux_log_folder_candidates(
+        base_dirs.home_dir(),
+        &user,
+        wine_prefix,
+    ));
+
+    let mut unique: Vec<PathBuf> = vec![];
+    for folder in folders {
+        if !unique.contains(&folder) {
+            unique.push(folder);
+        }
+    }
+    unique
+}
+
+/// Returns the Warframe folders inside the Proton and Wine prefixes found from `home`.
+fn get_linux_log_folder_candidates(
+    home: &Path,
+    user: &str,
+    wine_prefix: Option<PathBuf>,
+) -> Vec<PathBuf> {
+    let mut folders: Vec<PathBuf> = vec![];
+    let folder_in_prefix = |prefix: &Path, user: &str| {
+        prefix
+            .join("drive_c")
+            .join("users")
+            .join(user)
+            .join("AppData")
+            .join("Local")
+            .join("Warframe")
+    };
+
+    // Steam Proton, Warframe has the app id 230410
+    let mut steam_libraries = vec![
+        home.join(".steam").join("steam"),
+        home.join(".steam").join("root"),
+        home.join(".local").join("share").join("Steam"),
+        home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"),
+        home.join("snap/steam/common/.local/share/Steam"),
+    ];
+    for steam in steam_libraries.clone() {
+        steam_libraries.extend(get_steam_library_folders(&steam));
+    }
+    for library in steam_libraries {
+        let prefix = library.join("steamapps/compatdata/230410/pfx");
+        folders.push(folder_in_prefix(&prefix, "steamuser"));
+    }
+
+    // Wine and Lutris prefixes
+    let mut prefixes = vec![home.join(".wine"), home.join("Games").join("warframe")];
+    if let Some(prefix) = wine_prefix {
+        prefixes.insert(0, prefix);
+    }
+    for prefix in prefixes {
+        if !user.is_empty() {
+            folders.push(folder_in_prefix(&prefix, user));
+        }
+        folders.push(folder_in_prefix(&prefix, "steamuser"));
+    }
+    folders
+}
+
+/// Reads the extra Steam library folders from `libraryfolders.vdf`.
+fn get_steam_library_folders(steam_path: &Path) -> Vec<PathBuf> {
+    match fs::read_to_string(steam_path.join("steamapps").join("libraryfolders.vdf")) {
+        Ok(content) => parse_steam_library_folders(&content),
+        Err(_) => vec![],
+    }
+}
+
+/// Returns the `path` values of a `libraryfolders.vdf`.
+fn parse_steam_library_folders(content: &str) -> Vec<PathBuf> {
+    STEAM_LIBRARY_PATH
+        .captures_iter(content)
+        .map(|captures| PathBuf::from(captures[1].replace("\\\\", "\\")))
+        .collect()
+}
+
+/// Finds the folder that holds EE.log, the custom folder from the settings wins when it is set.
+/// Returns the folder if found and every folder that was searched.
+pub fn find_warframe_log_folder(custom_folder: &str) -> (Option<PathBuf>, Vec<PathBuf>) {
+    let candidates = if custom_folder.is_empty() {
+        get_warframe_log_folder_candidates()
+    } else {
+        let custom_folder = PathBuf::from(custom_folder);
+        // Allow pointing at the log file itself
+        match custom_folder.file_name() {
+            Some(name) if name == "EE.log" => {
+                vec![custom_folder.parent().unwrap_or(Path::new("")).to_path_buf()]
+            }
+            _ => vec![custom_folder],
+        }
+    };
+    let found = candidates
+        .iter()
+        .find(|folder| folder.join("EE.log").exists())
+        .cloned();
+    (found, candidates)
+}
+
+/// Returns the Warframe log folder found by the EE log parser, or the default one.
+pub fn get_warframe_log_folder() -> PathBuf {
+    if let Some(folder) = WARFRAME_LOG_FOLDER.lock().unwrap().clone() {
+        return folder;
+    }
+    get_app_local_path().join("Warframe")
+}
+
 pub fn get_desktop_path() -> PathBuf {
     if let Some(base_dirs) = UserDirs::new() {
         let local_path = get_app_roaming_path(); // Ensure local_path lives long enough
@@ -586,56 +710,6 @@ pub fn send_message_to_discord(
     });
 }
 
-pub async fn alter_table(
-    connection: sqlx::Pool<sqlx::Sqlite>,
-    alter_sql: &str,
-) -> Result<bool, AppError> {
-    let re = regex::Regex::new(r#"ALTER TABLE "(?P<table>[^"]+)" ADD COLUMN "(?P<column>[^"]+)"#)
-        .unwrap();
-    if let Some(captures) = re.captures(alter_sql) {
-        let table_name = captures.name("table").map_or("", |m| m.as_str());
-        let column_name = captures.name("column").map_or("", |m| m.as_str());
-        if table_name != "" && column_name != "" {
-            let rep = sqlx::query(format!("PRAGMA table_info(\"{}\")", table_name).as_str())
-                .fetch_all(&connection)
-                .await
-                .map_err(|e| AppError::new("Database", eyre!(e.to_string())));
-            match rep {
-                Ok(r) => {
-                    for row in r {
-                        let name: String = sqlx::Row::get(&row, "name");
-                        if name == column_name {
-                            return Ok(true);
-                        }
-                    }
-                    sqlx::query(&alter_sql)
-                        .execute(&connection)
-                        .await
-                        .map_err(|e| AppError::new("Database", eyre!(e.to_string())))?;
-                    return Ok(false);
-                }
-                Err(_) => {
-                    return Err(AppError::new(
-                        "Database",
-                        eyre!("Could not find table in database."),
-                    ));
-                }
-            }
-        } else {
-            logger::warning_con(
-                "Helper",
-                "Could not find table name or column name in the SQL.",
-            );
-        }
-    } else {
-        logger::warning_con(
-            "Helper",
-            "Could not find table name or column name in the SQL.",
-        );
-    }
-    Ok(false)
-}
-
 pub fn calculate_trade_tax(item_tags: Vec<String>, rank: Option<i64>) -> i64 {
     // If tags contains "arcane_upgrade" then it is an arcane
     if item_tags.contains(&"arcane_enhancement".to_string()) {
@@ -672,7 +746,7 @@ pub fn calculate_trade_tax(item_tags: Vec<String>, rank: Option<i64>) -> i64 {
 }
 
 pub fn get_warframe_language() -> WarframeLanguage {
-    let path = get_app_local_path().join("Warframe").join("Launcher.log");
+    let path = get_warframe_log_folder().join("Launcher.log");
 
     let log_file = "get_warframe_language.log";
 
@@ -764,3 +838,96 @@ pub fn validate_json(json: &Value, required: &Value, path: &str) -> (Value, Vec<
 
     (modified_json, missing_properties)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::{find_warframe_log_folder, get_linux_log_folder_candidates, parse_steam_library_folders};
+
+    #[test]
+    fn reads_the_steam_library_paths() {
+        let content = r#"
+"libraryfolders"
+{
+	"0"
+	{
+		"path"		"/home/tenno/.local/share/Steam"
+		"label"		""
+	}
+	"1"
+	{
+		"path"		"D:\\SteamLibrary"
+		"apps"
+		{
+			"230410"		"52428800"
+		}
+	}
+}
+"#;
+        assert_eq!(
+            parse_steam_library_folders(content),
+            vec![
+                PathBuf::from("/home/tenno/.local/share/Steam"),
+                PathBuf::from("D:\\SteamLibrary"),
+            ]
+        );
+        assert!(parse_steam_library_folders("").is_empty());
+    }
+
+    #[test]
+    fn finds_the_prefixes_of_every_steam_library() {
+        let home = std::env::temp_dir().join("qf_helper_home");
+        let steamapps = home.join(".steam/steam/steamapps");
+        fs::create_dir_all(&steamapps).unwrap();
+        fs::write(
+            steamapps.join("libraryfolders.vdf"),
+            "\"libraryfolders\" { \"1\" { \"path\" \"/mnt/games/SteamLibrary\" } }",
+        )
+        .unwrap();
+
+        let folders =
+            get_linux_log_folder_candidates(&home, "tenno", Some(PathBuf::from("/opt/wf")));
+        let in_prefix = |prefix: PathBuf, user: &str| {
+            prefix.join(format!("drive_c/users/{}/AppData/Local/Warframe", user))
+        };
+        assert!(folders.contains(&in_prefix(
+            home.join(".steam/steam/steamapps/compatdata/230410/pfx"),
+            "steamuser"
+        )));
+        assert!(folders.contains(&in_prefix(
+            PathBuf::from("/mnt/games/SteamLibrary/steamapps/compatdata/230410/pfx"),
+            "steamuser"
+        )));
+        // The prefix from WINEPREFIX is searched before the default ones
+        let wine_prefix = folders
+            .iter()
+            .position(|folder| *folder == in_prefix(PathBuf::from("/opt/wf"), "tenno"))
+            .unwrap();
+        let default_prefix = folders
+            .iter()
+            .position(|folder| *folder == in_prefix(home.join(".wine"), "tenno"))
+            .unwrap();
+        assert!(wine_prefix < default_prefix);
+        fs::remove_dir_all(home).unwrap();
+    }
+
+    #[test]
+    fn uses_the_custom_folder_when_it_is_set() {
+        let folder = std::env::temp_dir().join("qf_helper_custom_folder");
+        fs::create_dir_all(&folder).unwrap();
+        let folder_str = folder.to_string_lossy().to_string();
+
+        assert_eq!(find_warframe_log_folder(&folder_str), (None, vec![folder.clone()]));
+
+        fs::write(folder.join("EE.log"), "").unwrap();
+        assert_eq!(
+            find_warframe_log_folder(&folder_str),
+            (Some(folder.clone()), vec![folder.clone()])
+        );
+        // The log file itself can be picked
+        let log_file = folder.join("EE.log").to_string_lossy().to_string();
+        assert_eq!(find_warframe_log_folder(&log_file).0, Some(folder.clone()));
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
@@ -0,0 +1,385 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io::{Read, Write},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use eyre::eyre;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{error::AppError, helper};
+
+// The name of the recording inside the session archive
+const SESSION_ENTRY: &str = "session.json";
+// Response headers that carry the session and are never written to a session
+const SECRET_HEADERS: [&str; 2] = ["set-cookie", "authorization"];
+// Body fields that are masked before they are written to a session
+const SECRET_FIELDS: [&str; 3] = ["password", "access_token", "email"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecorderMode {
+    Off,
+    Recording,
+    Replaying,
+}
+
+/// A request and the response it got.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedExchange {
+    // "wfm" or "relics_run"
+    pub source: String,
+    pub method: String,
+    // The url without the configured endpoint, so a session replays against any endpoint
+    pub url: String,
+    pub body: Option<Value>,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub response: String,
+    pub recorded: String,
+}
+
+impl RecordedExchange {
+    fn key(source: &str, method: &str, url: &str, body: Option<&Value>) -> String {
+        let body = body
+            .map(|body| Self::redact(body.clone()).to_string())
+            .unwrap_or_default();
+        format!("{} {} {} {}", source, method, url.trim_start_matches('/'), body)
+    }
+
+    /// Masks the secret fields of a request body, also in nested objects.
+    fn redact(mut body: Value) -> Value {
+        match &mut body {
+            Value::Object(fields) => {
+                for (name, value) in fields.iter_mut() {
+                    if SECRET_FIELDS.contains(&name.as_str()) && value.is_string() {
+                        *value = json!("********");
+                    } else {
+                        *value = Self::redact(value.take());
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for item in items.iter_mut() {
+                    *item = Self::redact(item.take());
+                }
+            }
+            _ => {}
+        }
+        body
+    }
+
+    pub fn status(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    pub fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in self.headers.iter() {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                headers.append(name, value);
+            }
+        }
+        headers
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RecorderStatus {
+    pub mode: RecorderMode,
+    pub path: Option<String>,
+    pub exchanges: usize,
+}
+
+#[derive(Debug)]
+struct RecorderState {
+    mode: RecorderMode,
+    path: Option<PathBuf>,
+    recorded: Vec<RecordedExchange>,
+    // Recorded responses by request, served in the order they were recorded
+    replay: HashMap<String, VecDeque<RecordedExchange>>,
+}
+
+/// Records the responses of warframe.market and relics.run to a zip compressed session file,
+/// or serves them back from one so a scraper cycle can be reproduced offline.
+#[derive(Debug)]
+pub struct HttpRecorder {
+    state: Mutex<RecorderState>,
+}
+
+impl Default for HttpRecorder {
+    fn default() -> Self {
+        HttpRecorder {
+            state: Mutex::new(RecorderState {
+                mode: RecorderMode::Off,
+                path: None,
+                recorded: vec![],
+                replay: HashMap::new(),
+            }),
+        }
+    }
+}
+
+impl HttpRecorder {
+    fn error(e: impl std::fmt::Display) -> AppError {
+        AppError::new("HttpRecorder", eyre!(e.to_string()))
+    }
+
+    /// Where new sessions are written, like "sessions/session_20240101_120000.zip".
+    pub fn new_session_path() -> PathBuf {
+        let folder = helper::get_app_roaming_path().join("sessions");
+        if !folder.exists() {
+            std::fs::create_dir_all(&folder).unwrap();
+        }
+        folder.join(format!(
+            "session_{}.zip",
+            chrono::Local::now().format("%Y%m%d_%H%M%S")
+        ))
+    }
+
+    pub fn mode(&self) -> RecorderMode {
+        self.state.lock().unwrap().mode
+    }
+
+    /// Responses are not cached while recording or replaying,
+    /// every request has to show up in the session.
+    pub fn is_active(&self) -> bool {
+        self.mode() != RecorderMode::Off
+    }
+
+    pub fn get_status(&self) -> RecorderStatus {
+        let state = self.state.lock().unwrap();
+        RecorderStatus {
+            mode: state.mode,
+            path: state.path.as_ref().map(|path| path.to_string_lossy().to_string()),
+            exchanges: match state.mode {
+                RecorderMode::Replaying => state.replay.values().map(|queue| queue.len()).sum(),
+                _ => state.recorded.len(),
+            },
+        }
+    }
+
+    pub fn start_recording(&self, path: PathBuf) -> Result<(), AppError> {
+        let mut state = self.state.lock()?;
+        if state.mode != RecorderMode::Off {
+            return Err(Self::error("The recorder is already running"));
+        }
+        state.mode = RecorderMode::Recording;
+        state.path = Some(path);
+        state.recorded.clear();
+        Ok(())
+    }
+
+    /// Loads a session and serves its responses until `stop` is called.
+    pub fn start_replay(&self, path: PathBuf) -> Result<usize, AppError> {
+        let file = File::open(&path).map_err(Self::error)?;
+        let mut archive = ZipArchive::new(file).map_err(Self::error)?;
+        let mut content = String::new();
+        archive
+            .by_name(SESSION_ENTRY)
+            .map_err(Self::error)?
+            .read_to_string(&mut content)
+            .map_err(Self::error)?;
+        let exchanges: Vec<RecordedExchange> =
+            serde_json::from_str(&content).map_err(Self::error)?;
+
+        let mut state = self.state.lock()?;
+        if state.mode != RecorderMode::Off {
+            return Err(Self::error("The recorder is already running"));
+        }
+        state.replay.clear();
+        for exchange in exchanges.iter() {
+            let key = RecordedExchange::key(
+                &exchange.source,
+                &exchange.method,
+                &exchange.url,
+                exchange.body.as_ref(),
+            );
+            state
+                .replay
+                .entry(key)
+                .or_default()
+                .push_back(exchange.clone());
+        }
+        state.mode = RecorderMode::Replaying;
+        state.path = Some(path);
+        Ok(exchanges.len())
+    }
+
+    /// Stops recording or replaying, a recording is written to its session file.
+    pub fn stop(&self) -> Result<Option<PathBuf>, AppError> {
+        let mut state = self.state.lock()?;
+        let mode = state.mode;
+        let path = state.path.take();
+        let recorded = std::mem::take(&mut state.recorded);
+        state.mode = RecorderMode::Off;
+        state.replay.clear();
+        drop(state);
+
+        let path = match (mode, path) {
+            (RecorderMode::Recording, Some(path)) => path,
+            _ => return Ok(None),
+        };
+        let json = serde_json::to_string(&recorded).map_err(Self::error)?;
+        let file = File::create(&path).map_err(Self::error)?;
+        let mut zip = ZipWriter::new(file);
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        zip.start_file(SESSION_ENTRY, options).map_err(Self::error)?;
+        zip.write_all(json.as_bytes()).map_err(Self::error)?;
+        zip.finish().map_err(Self::error)?;
+        Ok(Some(path))
+    }
+
+    pub fn record(
+        &self,
+        source: &str,
+        method: &str,
+        url: &str,
+        body: Option<&Value>,
+        status: StatusCode,
+        headers: &HeaderMap,
+        response: &str,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        let url = url.trim_start_matches('/');
+        // Logging in is never recorded, the session may be shared in a bug report
+        if state.mode != RecorderMode::Recording || url.starts_with("auth/") {
+            return;
+        }
+        state.recorded.push(RecordedExchange {
+            source: source.to_string(),
+            method: method.to_string(),
+            url: url.to_string(),
+            body: body.cloned().map(RecordedExchange::redact),
+            status: status.as_u16(),
+            headers: headers
+                .iter()
+                .filter(|(name, _)| !SECRET_HEADERS.contains(&name.as_str()))
+                .filter_map(|(name, value)| {
+                    value
+                        .to_str()
+                        .ok()
+                        .map(|value| (name.to_string(), value.to_string()))
+                })
+                .collect(),
+            response: response.to_string(),
+            recorded: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+
+    /// Returns the recorded response while replaying, None when not replaying.
+    /// A request that is not in the session is an error, it is never sent.
+    pub fn replay(
+        &self,
+        source: &str,
+        method: &str,
+        url: &str,
+        body: Option<&Value>,
+    ) -> Result<Option<RecordedExchange>, AppError> {
+        let mut state = self.state.lock()?;
+        if state.mode != RecorderMode::Replaying {
+            return Ok(None);
+        }
+        let key = RecordedExchange::key(source, method, url, body);
+        match state.replay.get_mut(&key) {
+            // The last response keeps being served once the recorded ones are used up
+            Some(queue) if queue.len() > 1 => Ok(queue.pop_front()),
+            Some(queue) if !queue.is_empty() => Ok(queue.front().cloned()),
+            _ => Err(Self::error(format!(
+                "{} {} was not recorded in the session",
+                method, url
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::RecordedExchange;
+    use crate::wfm_client::mock_server::{order_json, MockResponse, MockWfmServer};
+
+    #[tokio::test]
+    async fn replays_a_recorded_session() {
+        let server = MockWfmServer::start().await;
+        server.set_order_book(
+            "primed_flow",
+            vec![order_json("o1", "primed_flow", "sell", 40, 1, Some(10), "Seller", "ingame")],
+        );
+        let wfm = server.client();
+        let port = server.address.rsplit(':').next().unwrap_or_default().to_string();
+        let path = std::env::temp_dir().join(format!("quantframe_session_{}.zip", port));
+
+        let recorder = wfm.recorder();
+        recorder.start_recording(path.clone()).unwrap();
+        let recorded = wfm.orders().get_ordres_by_item("primed_flow").await.unwrap();
+        assert_eq!(recorder.stop().unwrap(), Some(path.clone()));
+
+        // The market changes, the replay still serves what was recorded without sending anything
+        server.set_order_book("primed_flow", vec![]);
+        let sent = server.requests().len();
+        assert_eq!(recorder.start_replay(path.clone()).unwrap(), 1);
+        let replayed = wfm.orders().get_ordres_by_item("primed_flow").await.unwrap();
+        assert_eq!(replayed.height(), recorded.height());
+        assert!(wfm.orders().get_ordres_by_item("ash_prime_set").await.is_err());
+        assert_eq!(server.requests().len(), sent);
+        recorder.stop().unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn does_not_record_secrets() {
+        let server = MockWfmServer::start().await;
+        server.script(
+            "GET",
+            "v1/items/primed_flow/orders",
+            MockResponse::payload(json!({ "orders": [] }))
+                .with_header("set-cookie", "JWT=mock-token; Path=/"),
+        );
+        let wfm = server.client();
+        let port = server.address.rsplit(':').next().unwrap_or_default().to_string();
+        let path = std::env::temp_dir().join(format!("quantframe_secrets_{}.zip", port));
+
+        let recorder = wfm.recorder();
+        recorder.start_recording(path.clone()).unwrap();
+        wfm.auth()
+            .login("mock@warframe.market".to_string(), "password".to_string())
+            .await
+            .unwrap();
+        wfm.orders().get_ordres_by_item("primed_flow").await.unwrap();
+        let recorded = recorder.state.lock().unwrap().recorded.clone();
+        recorder.stop().unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].url, "items/primed_flow/orders");
+        assert!(recorded[0].headers.iter().all(|(name, _)| name != "set-cookie"));
+    }
+
+    #[test]
+    fn masks_secret_fields() {
+        let body = json!({
+            "password": "hunter2",
+            "user": { "email": "mock@warframe.market", "ingame_name": "MockUser" },
+        });
+        assert_eq!(
+            RecordedExchange::redact(body),
+            json!({
+                "password": "********",
+                "user": { "email": "********", "ingame_name": "MockUser" },
+            })
+        );
+    }
+}
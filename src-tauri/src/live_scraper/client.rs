@@ -1,4 +1,5 @@
 use std::{
+    collections::HashSet,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc, Mutex,
@@ -10,13 +11,13 @@ use serde_json::json;
 
 use crate::{
     auth::AuthState,
-    database::client::DBClient,
+    database::{client::DBClient, modules::stock_event::StockEventSource},
     enums::{LogLevel, OrderMode, StockMode},
     error::AppError,
-    handler::MonitorHandler,
     helper,
     logger::{self},
     price_scraper::PriceScraper,
+    request_scheduler::RequestPriority,
     settings::SettingsState,
     wfm_client::client::WFMClient,
 };
@@ -32,7 +33,8 @@ pub struct LiveScraperClient {
     pub wfm: Arc<Mutex<WFMClient>>,
     pub auth: Arc<Mutex<AuthState>>,
     pub db: Arc<Mutex<DBClient>>,
-    pub mh: Arc<Mutex<MonitorHandler>>,
+    // Items that got new orders since they were last checked
+    changed_items: Arc<Mutex<HashSet<String>>>,
 }
 
 impl LiveScraperClient {
@@ -42,19 +44,36 @@ impl LiveScraperClient {
         wfm: Arc<Mutex<WFMClient>>,
         auth: Arc<Mutex<AuthState>>,
         db: Arc<Mutex<DBClient>>,
-        mh: Arc<Mutex<MonitorHandler>>,
     ) -> Self {
         LiveScraperClient {
             log_file: "live_scraper.log".to_string(),
             price_scraper,
             settings,
             is_running: Arc::new(AtomicBool::new(false)),
-            wfm,
+            // Scraping reads wait behind the user's own requests
+            wfm: Arc::new(Mutex::new(
+                wfm.lock().unwrap().with_priority(RequestPriority::Scraping),
+            )),
             auth,
             db,
-            mh,
+            changed_items: Arc::new(Mutex::new(HashSet::new())),
         }
     }
+    /// Called by the websocket when someone posts an order for `url_name`.
+    pub fn on_order_changed(&self, url_name: &str) {
+        if !self.is_running() {
+            return;
+        }
+        self.changed_items
+            .lock()
+            .unwrap()
+            .insert(url_name.to_string());
+    }
+
+    /// Returns the items that got new orders and forgets them.
+    pub fn take_changed_items(&self) -> HashSet<String> {
+        std::mem::take(&mut *self.changed_items.lock().unwrap())
+    }
     fn report_error(&self, error: AppError) {
         let component = error.component();
         let cause = error.cause();
@@ -100,9 +119,17 @@ impl LiveScraperClient {
             logger::info_con("LiveScraper", "Loop live scraper is started");
 
             scraper.send_message("riven.reset", None);
-            db.stock_riven().reset_listed_price().await.unwrap();
+            db.stock_riven()
+                .with_source(StockEventSource::LiveScraper, Some("Live scraper started"))
+                .reset_listed_price()
+                .await
+                .unwrap();
             scraper.send_message("item.reset", None);
-            db.stock_item().reset_listed_price().await.unwrap();
+            db.stock_item()
+                .with_source(StockEventSource::LiveScraper, Some("Live scraper started"))
+                .reset_listed_price()
+                .await
+                .unwrap();
             scraper
                 .item()
                 .delete_all_orders(OrderMode::Both)
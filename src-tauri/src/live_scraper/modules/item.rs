@@ -1,3 +1,4 @@
+use crate::database::modules::stock_event::StockEventSource;
 use crate::enums::OrderMode;
 use crate::error;
 use crate::live_scraper::client::LiveScraperClient;
@@ -148,9 +149,16 @@ impl<'a> ItemModule<'a> {
             Some(self.client.log_file.as_str()),
         );
 
+        let cache = db.cache.lock()?.clone();
+
+        // Items that got new orders on the websocket are checked first
+        let changed_items = self.client.take_changed_items();
+        let mut items_to_check: Vec<String> = all_interesting_items.iter().cloned().collect();
+        items_to_check.sort_by_key(|item| !changed_items.contains(item));
+
         let mut current_index = all_interesting_items.len();
         // Loop through all interesting items
-        for item in all_interesting_items.clone() {
+        for item in items_to_check {
             if self.client.is_running() == false || item == "" {
                 continue;
             }
@@ -186,10 +194,10 @@ impl<'a> ItemModule<'a> {
                     format!("Item: {item} is not in all_interesting_items").as_str(),
                     Some(self.client.log_file.as_str()),
                 );
-                let item_info = wfm.items().get_item(item.to_string()).await?;
+                let item_info = cache.items().get_details(&item).await?;
 
                 let item_id = item_info.id;
-                let item_rank = item_info.items_in_set.get(0).unwrap().mod_max_rank;
+                let item_rank = cache.items().get_mod_max_rank(&item).await?;
                 self.compare_live_orders_when_selling(
                     &item,
                     &item_id,
@@ -257,6 +265,8 @@ impl<'a> ItemModule<'a> {
                 .await?;
             }
         }
+        // Keep the item details fetched during this cycle
+        cache.save_to_file()?;
         Ok(())
     }
     fn get_week_increase(&self, df: &DataFrame, row_name: &str) -> Result<f64, AppError> {
@@ -1064,6 +1074,7 @@ impl<'a> ItemModule<'a> {
             self.client
                 .send_message("item.sell.deleting", Some(json!({ "name": item_name})));
             db.stock_item()
+                .with_source(StockEventSource::LiveScraper, None)
                 .update_by_url(
                     item_name,
                     None,
@@ -1117,6 +1128,7 @@ impl<'a> ItemModule<'a> {
             }
 
             db.stock_item()
+                .with_source(StockEventSource::LiveScraper, None)
                 .update_by_url(
                     item_name,
                     None,
@@ -1169,6 +1181,7 @@ impl<'a> ItemModule<'a> {
             // Only update the database if the item is not already marked as to_low_profit
             if stock_item.status != "to_low_profit" {
                 db.stock_item()
+                    .with_source(StockEventSource::LiveScraper, None)
                     .update_by_url(
                         item_name,
                         None,
@@ -1218,6 +1231,7 @@ impl<'a> ItemModule<'a> {
                     )
                     .await?;
                 db.stock_item()
+                    .with_source(StockEventSource::LiveScraper, None)
                     .update_by_url(
                         item_name,
                         None,
@@ -1254,6 +1268,7 @@ impl<'a> ItemModule<'a> {
                 )
                 .await?;
             db.stock_item()
+                .with_source(StockEventSource::LiveScraper, None)
                 .update_by_url(
                     item_name,
                     None,
@@ -1293,3 +1308,102 @@ impl<'a> ItemModule<'a> {
         Ok(helper::merge_dataframes(vec![current_orders, order_df])?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::Ordering, Arc, Mutex};
+
+    use crate::{
+        enums::OrderMode,
+        live_scraper::client::LiveScraperClient,
+        price_scraper::PriceScraper,
+        wfm_client::mock_server::{order_json, MockWfmServer},
+    };
+
+    // A scraper in sell mode that owns one Primed Flow bought for `price`
+    async fn sell_scraper(server: &MockWfmServer, price: f64) -> LiveScraperClient {
+        server.add_item("primed_flow_id", "primed_flow", "Primed Flow");
+        let db = server.database().await;
+        db.stock_item()
+            .create("primed_flow", 1, price, None, 0, None)
+            .await
+            .unwrap();
+
+        // The price history has no popular items, only the stock is checked
+        let port = server.address.rsplit(':').next().unwrap_or_default();
+        let csv_path = std::env::temp_dir().join(format!("qf_mock_prices_{}.csv", port));
+        std::fs::write(
+            &csv_path,
+            "name,datetime,order_type,volume,min_price,max_price,range,median,avg_price,mod_rank,item_id\n\
+             ash_prime_set,2024-01-01T00:00:00.000+00:00,closed,0,50,50,0,50,50,0,ash_prime_set_id\n",
+        )
+        .unwrap();
+
+        let wfm = Arc::new(Mutex::new(server.client()));
+        let auth = wfm.lock().unwrap().auth.clone();
+        let price_scraper = PriceScraper::new(Arc::clone(&wfm), Arc::clone(&auth))
+            .with_csv_path(csv_path.to_str().unwrap());
+        let mut settings = server.settings();
+        settings.live_scraper.stock_item.order_mode = OrderMode::Sell;
+        let scraper = LiveScraperClient::new(
+            Arc::new(Mutex::new(settings)),
+            Arc::new(Mutex::new(price_scraper)),
+            wfm,
+            auth,
+            Arc::new(Mutex::new(db)),
+        );
+        scraper.is_running.store(true, Ordering::SeqCst);
+        scraper
+    }
+
+    #[tokio::test]
+    async fn check_stock_lists_owned_items_at_the_lowest_price() {
+        let server = MockWfmServer::start().await;
+        server.set_order_book(
+            "primed_flow",
+            vec![
+                order_json("a", "primed_flow", "sell", 45, 1, Some(0), "SellerA", "ingame"),
+                order_json("b", "primed_flow", "sell", 40, 1, Some(0), "SellerB", "ingame"),
+            ],
+        );
+        let scraper = sell_scraper(&server, 20.0).await;
+
+        scraper.item().check_stock().await.unwrap();
+
+        let my_orders = server.state.lock().unwrap().my_orders.clone();
+        assert_eq!(my_orders.len(), 1);
+        assert_eq!(my_orders[0]["order_type"], "sell");
+        assert_eq!(my_orders[0]["platinum"], 40);
+        let db = scraper.db.lock().unwrap().clone();
+        let stock = db
+            .stock_item()
+            .get_item_by_url_name("primed_flow")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stock.listed_price, Some(40));
+        assert_eq!(stock.status, "live");
+    }
+
+    #[tokio::test]
+    async fn check_stock_does_not_sell_below_the_bought_price() {
+        let server = MockWfmServer::start().await;
+        server.set_order_book(
+            "primed_flow",
+            vec![order_json("a", "primed_flow", "sell", 40, 1, Some(0), "SellerA", "ingame")],
+        );
+        let scraper = sell_scraper(&server, 100.0).await;
+
+        scraper.item().check_stock().await.unwrap();
+
+        assert!(server.state.lock().unwrap().my_orders.is_empty());
+        let db = scraper.db.lock().unwrap().clone();
+        let stock = db
+            .stock_item()
+            .get_item_by_url_name("primed_flow")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stock.status, "to_low_profit");
+    }
+}
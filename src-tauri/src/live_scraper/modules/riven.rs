@@ -2,8 +2,8 @@ use reqwest::header::HeaderMap;
 use serde_json::json;
 
 use crate::{
-    auth::AuthState, error::AppError, live_scraper::client::LiveScraperClient, logger,
-    structs::AuctionItem,
+    auth::AuthState, database::modules::stock_event::StockEventSource, error::AppError,
+    live_scraper::client::LiveScraperClient, logger, structs::AuctionItem,
 };
 pub struct RivenModule<'a> {
     pub client: &'a LiveScraperClient,
@@ -32,6 +32,11 @@ impl<'a> RivenModule<'a> {
                 break;
             }
 
+            // Skip rivens bought in game that are not completed yet
+            if riven.status == "draft" {
+                continue;
+            }
+
             // Find my auction for this riven if exists
             let auction = my_rivens
                 .iter()
@@ -50,6 +55,7 @@ impl<'a> RivenModule<'a> {
                 // Update database status to inactive
                 if riven.status != "inactive" {
                     db.stock_riven()
+                        .with_source(StockEventSource::LiveScraper, None)
                         .update_by_id(
                             riven.id,
                             Some("".to_string()),
@@ -132,6 +138,7 @@ impl<'a> RivenModule<'a> {
                 self.client
                     .send_message("riven.no_offers", Some(json!({ "name": riven.weapon_url})));
                 db.stock_riven()
+                    .with_source(StockEventSource::LiveScraper, None)
                     .update_by_id(
                         riven.id,
                         None,
@@ -240,6 +247,7 @@ impl<'a> RivenModule<'a> {
                     || order_id.is_some()
                 {
                     db.stock_riven()
+                        .with_source(StockEventSource::LiveScraper, None)
                         .update_by_id(
                             riven.id,
                             order_id,
@@ -276,6 +284,7 @@ impl<'a> RivenModule<'a> {
                 }
                 if riven.listed_price.is_some() || riven.status != "to_low_profit" {
                     db.stock_riven()
+                        .with_source(StockEventSource::LiveScraper, None)
                         .update_by_id(
                             riven.id,
                             None,
@@ -26,14 +26,17 @@ use tauri::SystemTray;
 mod auth;
 mod cache;
 mod commands;
+mod credentials;
 mod database;
 mod debug;
 mod error;
 mod helper;
+mod http_recorder;
 mod live_scraper;
 mod logger;
 mod price_scraper;
-mod rate_limiter;
+mod request_scheduler;
+mod retry_policy;
 mod settings;
 mod system_tray;
 mod wf_ee_log_parser;
@@ -67,6 +70,11 @@ async fn setup_async(app: &mut App) -> Result<(), AppError> {
     )));
     app.manage(wfm_client.clone());
 
+    // create and manage chat outbox state
+    let chat_outbox = wfm_client::chat_outbox::ChatOutbox::new(Arc::clone(&wfm_client));
+    chat_outbox.start();
+    app.manage(Arc::new(Mutex::new(chat_outbox)));
+
     // create and manage Cache state
     let cache_arc = Arc::new(Mutex::new(CacheClient::new(Arc::clone(&wfm_client))));
     app.manage(cache_arc.clone());
@@ -78,6 +86,10 @@ async fn setup_async(app: &mut App) -> Result<(), AppError> {
             .unwrap(),
     ));
     app.manage(database_client.clone());
+    database::modules::backup::start_schedule(
+        Arc::clone(&database_client),
+        Arc::clone(&settings_arc),
+    );
 
     // create and manage PriceScraper state
     let price_scraper: Arc<Mutex<PriceScraper>> = Arc::new(Mutex::new(PriceScraper::new(
@@ -87,22 +99,36 @@ async fn setup_async(app: &mut App) -> Result<(), AppError> {
     app.manage(price_scraper.clone());
 
     // create and manage LiveScraper state
-    let live_scraper = LiveScraperClient::new(
+    let live_scraper = Arc::new(Mutex::new(LiveScraperClient::new(
         Arc::clone(&settings_arc),
         Arc::clone(&price_scraper),
         Arc::clone(&wfm_client),
         Arc::clone(&auth_arc),
         Arc::clone(&database_client),
+    )));
+    app.manage(live_scraper.clone());
+
+    // create and manage Warframe Market websocket state
+    let wfm_socket = wfm_client::websocket::WFMSocket::new(
+        Arc::clone(&auth_arc),
+        Arc::clone(&settings_arc),
         Arc::clone(&monitor_handler_arc),
+        Arc::clone(&live_scraper),
+        Arc::clone(&wfm_client),
     );
-    app.manage(Arc::new(Mutex::new(live_scraper)));
+    wfm_socket.start();
+    app.manage(Arc::new(Mutex::new(wfm_socket)));
 
     // create and manage WhisperScraper state
     let ee_log = EELogParser::new(
         Arc::clone(&settings_arc),
         Arc::clone(&monitor_handler_arc),
         Arc::clone(&cache_arc),
-    );
+        Arc::clone(&auth_arc),
+        Arc::clone(&wfm_client),
+        Arc::clone(&live_scraper),
+        Arc::clone(&database_client),
+    )?;
     app.manage(Arc::new(Mutex::new(ee_log)));
     // create and manage WhisperScraper state
     let debug_client = DebugClient::new(
@@ -127,7 +153,6 @@ fn main() {
     }));
 
     tauri::Builder::default()
-        .plugin(tauri_plugin_websocket::init())
         .system_tray(SystemTray::new().with_menu(system_tray::client::get_tray_menu()))
         .on_system_tray_event(|_app, event| match event {
             SystemTrayEvent::MenuItemClick { id, .. } => {
@@ -170,7 +195,8 @@ fn main() {
             commands::base::open_logs_folder,
             commands::base::export_logs,
             commands::base::show_notification,
-            commands::base::on_new_wfm_message,
+            commands::base::get_request_status,
+            commands::base::send_wfm_socket_message,
             commands::auth::login,
             commands::auth::logout,
             commands::base::log,
@@ -178,10 +204,20 @@ fn main() {
             commands::transaction::create_transaction_entry,
             commands::transaction::delete_transaction_entry,
             commands::transaction::update_transaction_entry,
+            commands::transaction::get_profit_report,
+            commands::trade::get_trades,
+            commands::trade::get_trade,
+            commands::trade::delete_trade,
             commands::live_scraper::toggle_live_scraper,
             commands::price_scraper::generate_price_history,
             commands::debug::import_warframe_algo_trader_data,
             commands::debug::reset_data,
+            commands::debug::start_http_recording,
+            commands::debug::start_http_replay,
+            commands::debug::stop_http_recorder,
+            commands::debug::get_http_recorder_status,
+            commands::ee_log::preview_ee_log_backfill,
+            commands::ee_log::commit_ee_log_backfill,
             commands::auctions::refresh_auctions,
             commands::orders::refresh_orders,
             commands::orders::get_orders,
@@ -191,12 +227,27 @@ fn main() {
             commands::orders::delete_all_orders,
             commands::chat::get_chat,
             commands::chat::delete_chat,
+            commands::chat::send_chat_message,
+            commands::chat::mark_chat_read,
+            commands::chat::start_chat,
+            commands::chat::get_chat_outbox,
+            commands::chat::delete_chat_outbox_message,
             commands::chat::refresh_chats,
+            commands::import_export::export_data,
+            commands::import_export::import_data,
+            commands::backup::create_backup,
+            commands::backup::list_backups,
+            commands::backup::restore_backup,
+            commands::backup::check_database_integrity,
+            commands::profile::get_user_summary,
+            commands::profile::get_user_reviews,
             // Stock commands
             commands::stock::create_item_stock,
             commands::stock::delete_item_stock,
             commands::stock::update_item_stock,
             commands::stock::sell_item_stock,
+            commands::stock::get_item_stock_lots,
+            commands::stock::get_stock_timeline,
             commands::stock::sell_item_stock_by_url,
             commands::stock::create_riven_stock,
             commands::stock::import_auction,
@@ -1,5 +1,6 @@
 use crate::enums::LogLevel;
 use crate::error::{ApiResult, AppError, ErrorApiResponse};
+use crate::retry_policy::RetryPolicy;
 use crate::wfm_client::client::WFMClient;
 use crate::{helper, logger};
 use eyre::eyre;
@@ -8,7 +9,7 @@ use reqwest::{Client, Method, Url};
 use serde_json::{json, Value};
 use std::path::Path;
 use std::sync::Mutex;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::{
     collections::HashMap,
     fs::{self, File},
@@ -45,6 +46,13 @@ impl PriceScraper {
             auth,
         }
     }
+    /// Reads the price history from `csv_path` instead, for tests.
+    #[cfg(test)]
+    pub fn with_csv_path(mut self, csv_path: &str) -> Self {
+        self.csv_path = csv_path.to_string();
+        self.csv_backop_path = csv_path.to_string();
+        self
+    }
     /// Reads the price history data from a CSV file and returns it as a DataFrame.
     /// If the backup file is available, it is used instead of the main file.
     pub fn get_price_historys(&self) -> Result<DataFrame, AppError> {
@@ -61,6 +69,40 @@ impl PriceScraper {
             .map_err(|e| AppError::new("PriceScraper", eyre!(e.to_string())))
     }
 
+    /// The average price items were sold for over the price history, by url name and mod rank.
+    pub fn get_closed_averages(&self) -> Result<HashMap<(String, i64), f64>, AppError> {
+        let df = self
+            .get_price_historys()?
+            .lazy()
+            .filter(col("order_type").eq(lit("closed")))
+            // Items without ranks have no mod rank, like rank 0 in the stock
+            .with_column(col("mod_rank").cast(DataType::Int64).fill_null(lit(0)))
+            .groupby(&["name", "mod_rank"])
+            .agg(&[col("avg_price").mean().alias("avg_price")])
+            .collect()
+            .map_err(|e| AppError::new("PriceScraper", eyre!(e.to_string())))?;
+        let names = df
+            .column("name")
+            .and_then(|names| names.utf8().cloned())
+            .map_err(|e| AppError::new("PriceScraper", eyre!(e.to_string())))?;
+        let ranks = df
+            .column("mod_rank")
+            .and_then(|ranks| ranks.i64().cloned())
+            .map_err(|e| AppError::new("PriceScraper", eyre!(e.to_string())))?;
+        let prices = df
+            .column("avg_price")
+            .and_then(|prices| prices.f64().cloned())
+            .map_err(|e| AppError::new("PriceScraper", eyre!(e.to_string())))?;
+        Ok(names
+            .into_iter()
+            .zip(ranks.into_iter())
+            .zip(prices.into_iter())
+            .filter_map(|((name, rank), price)| {
+                Some(((name?.to_string(), rank.unwrap_or(0)), price?))
+            })
+            .collect())
+    }
+
     pub fn get_status(&self) -> Option<u128> {
         // Try to read from "allItemDataBackup.csv", and if it fails, read from "allItemData.csv".
         let file = File::open(&self.csv_path).or_else(|_| File::open(&self.csv_backop_path));
@@ -86,17 +128,12 @@ impl PriceScraper {
         platform: &str,
         day: &str,
     ) -> Result<ApiResult<Value>, AppError> {
-        let mut url = format!("http://relics.run/history/price_history_{}.json", day);
+        let wfm = self.wfm.lock()?.clone();
+        let endpoint = wfm.settings.lock()?.endpoints.get_relics_run();
+        let mut url = format!("{}price_history_{}.json", endpoint, day);
         if platform != "pc" {
-            url = format!(
-                "http://relics.run/history/{}/price_history_{}.json",
-                platform, day
-            );
+            url = format!("{}{}/price_history_{}.json", endpoint, platform, day);
         }
-        let client = Client::new();
-        let request = client.request(Method::GET, Url::parse(&url).unwrap());
-        let response = request.send().await;
-
         // Define the error response
         let mut error_def = ErrorApiResponse {
             status_code: 500,
@@ -106,23 +143,62 @@ impl PriceScraper {
             body: None,
             url: Some(url.clone()),
             method: Some("GET".to_string()),
+            errors: vec![],
         };
 
-        if let Err(e) = response {
-            error_def.messages.push(e.to_string());
-            return Err(AppError::new_api(
-                "PriceScraper",
-                error_def,
-                eyre!(format!("There was an error sending the request: {}", e)),
-                LogLevel::Critical,
-            ));
-        }
+        // Sessions key the price history by the path below the relics.run endpoint
+        let path = url.trim_start_matches(&endpoint).to_string();
+        let recorder = wfm.recorder();
+        let (status, headers, content) = match recorder.replay("relics_run", "GET", &path, None)? {
+            Some(exchange) => (exchange.status(), exchange.headers(), exchange.response),
+            None => {
+                let request_url = Url::parse(&url).map_err(|e| {
+                    AppError::new("PriceScraper", eyre!("Invalid price history url {}: {}", url, e))
+                })?;
+                let client = Client::new();
+                let retry = RetryPolicy::default();
+                let send = || {
+                    client
+                        .request(Method::GET, request_url.clone())
+                        .timeout(retry.timeout)
+                        .send()
+                };
+                let on_retry = |cause: String, delay: Duration, attempt: u32| {
+                    logger::warning_con(
+                        "PriceScraper",
+                        format!(
+                            "Getting {} failed with {}, retrying in {:.1}s ({}/{})",
+                            url,
+                            cause,
+                            delay.as_secs_f64(),
+                            attempt,
+                            retry.max_retries
+                        )
+                        .as_str(),
+                    );
+                };
+                let (response, _) = retry.send(&Method::GET, &url, send, on_retry).await;
+
+                if let Err(e) = response {
+                    error_def.messages.push(e.to_string());
+                    return Err(AppError::new_api(
+                        "PriceScraper",
+                        error_def,
+                        eyre!(format!("There was an error sending the request: {}", e)),
+                        LogLevel::Critical,
+                    ));
+                }
 
-        // Get the response data from the response
-        let response_data = response.unwrap();
-        error_def.status_code = response_data.status().as_u16() as i64;
-        let headers = response_data.headers().clone();
-        let content = response_data.text().await.unwrap_or_default();
+                // Get the response data from the response
+                let response_data = response.unwrap();
+                let status = response_data.status();
+                let headers = response_data.headers().clone();
+                let content = response_data.text().await.unwrap_or_default();
+                recorder.record("relics_run", "GET", &path, None, status, &headers, &content);
+                (status, headers, content)
+            }
+        };
+        error_def.status_code = status.as_u16() as i64;
         error_def.raw_response = Some(content.clone());
 
         if error_def.status_code != 200 {
@@ -446,3 +522,32 @@ impl PriceScraper {
         Ok(full_df.height() as i64)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wfm_client::mock_server::MockWfmServer;
+
+    #[tokio::test]
+    async fn get_price_by_day_uses_the_configured_endpoint() {
+        let server = MockWfmServer::start().await;
+        server.state.lock().unwrap().price_history.insert(
+            "2024-01-01".to_string(),
+            json!({ "Ash Prime Set": [{ "datetime": "2024-01-01T00:00:00.000+00:00" }] }),
+        );
+        let wfm = server.client();
+        let auth = wfm.auth.clone();
+        let scraper = PriceScraper::new(Arc::new(Mutex::new(wfm)), auth);
+
+        match scraper.get_price_by_day("pc", "2024-01-01").await.unwrap() {
+            ApiResult::Success(items, _) => assert!(items.get("Ash Prime Set").is_some()),
+            ApiResult::Error(e, _) => panic!("Unexpected error {:?}", e.status_code),
+        }
+        match scraper.get_price_by_day("xbox", "2024-01-02").await.unwrap() {
+            ApiResult::Success(..) => panic!("Missing days should not be found"),
+            ApiResult::Error(e, _) => assert_eq!(e.status_code, 404),
+        }
+        let requests = server.requests();
+        assert_eq!(requests[1].path, "history/xbox/price_history_2024-01-02.json");
+    }
+}
@@ -0,0 +1,347 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use tokio::sync::Notify;
+
+/// Requests with a lower value are sent first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestPriority {
+    // Commands the user is waiting on
+    Interactive = 0,
+    // Creating, updating and deleting orders and auctions from background work
+    Mutation = 1,
+    // Background reads of the EE.log events, the websocket and the chat outbox
+    Background = 2,
+    // The live scraper's order book fetches
+    Scraping = 3,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct EndpointStats {
+    pub endpoint: String,
+    pub requests: i64,
+    pub errors: i64,
+    pub rate_limited: i64,
+    pub last_status: Option<u16>,
+    pub last_request: Option<String>,
+    // Time spent waiting in the queue, in milliseconds
+    pub total_wait_ms: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SchedulerStatus {
+    pub rate: f64,
+    pub base_rate: f64,
+    pub burst: f64,
+    pub waiting: HashMap<String, usize>,
+    pub endpoints: Vec<EndpointStats>,
+}
+
+#[derive(Debug)]
+struct SchedulerState {
+    tokens: f64,
+    last_refill: Instant,
+    // Requests per second, lowered when the api returns 429
+    rate: f64,
+    // The rate from the settings, the rate recovers up to it
+    base_rate: f64,
+    burst: f64,
+    waiting: [usize; 4],
+    endpoints: HashMap<String, EndpointStats>,
+}
+
+impl SchedulerState {
+    // The rate never drops below this, however many 429 are returned
+    fn min_rate(&self) -> f64 {
+        self.base_rate / 8.0
+    }
+}
+
+/// A token bucket shared by all requests to warframe.market.
+/// Requests wait by priority, the bucket holds up to `burst` tokens and
+/// the rate is halved on every 429 and slowly recovers on success.
+#[derive(Debug)]
+pub struct RequestScheduler {
+    state: Mutex<SchedulerState>,
+    notify: Notify,
+}
+
+// A request that waited this long is served as if it had one priority higher,
+// so the scraper still gets a share while the user keeps the queue busy
+const AGING_STEP: Duration = Duration::from_secs(5);
+
+// Path segments that are part of a route, anything else is a parameter
+const ROUTE_SEGMENTS: [&str; 17] = [
+    "auth",
+    "signin",
+    "items",
+    "orders",
+    "profile",
+    "auctions",
+    "create",
+    "entry",
+    "close",
+    "search",
+    "riven",
+    "attributes",
+    "im",
+    "chats",
+    "statistics",
+    "reviews",
+    "v1",
+];
+
+// Counts a request as waiting until it is dropped, also when the request is cancelled
+struct QueueSlot<'a> {
+    scheduler: &'a RequestScheduler,
+    index: usize,
+}
+
+impl<'a> QueueSlot<'a> {
+    fn new(scheduler: &'a RequestScheduler, index: usize) -> Self {
+        scheduler.state.lock().unwrap().waiting[index] += 1;
+        QueueSlot { scheduler, index }
+    }
+}
+
+impl<'a> Drop for QueueSlot<'a> {
+    fn drop(&mut self) {
+        self.scheduler.state.lock().unwrap().waiting[self.index] -= 1;
+        self.scheduler.notify.notify_waiters();
+    }
+}
+
+impl RequestScheduler {
+    pub fn new(rate: f64, burst: f64) -> Self {
+        let rate = rate.max(0.1);
+        RequestScheduler {
+            state: Mutex::new(SchedulerState {
+                tokens: burst.max(1.0),
+                last_refill: Instant::now(),
+                rate,
+                base_rate: rate,
+                burst: burst.max(1.0),
+                waiting: [0; 4],
+                endpoints: HashMap::new(),
+            }),
+            notify: Notify::new(),
+        }
+    }
+
+    /// Changes the rate and burst, like when the settings are saved.
+    pub fn set_limits(&self, rate: f64, burst: f64) {
+        let mut state = self.state.lock().unwrap();
+        self.refill(&mut state);
+        let rate = rate.max(0.1);
+        // A rate lowered by 429 stays lowered by the same share
+        state.rate = (state.rate * rate / state.base_rate).clamp(rate / 8.0, rate);
+        state.base_rate = rate;
+        state.burst = burst.max(1.0);
+        state.tokens = state.tokens.min(state.burst);
+        drop(state);
+        self.notify.notify_waiters();
+    }
+
+    /// The priority a request is served with after waiting for `waited`.
+    fn aged_index(index: usize, waited: Duration) -> usize {
+        let steps = (waited.as_secs_f64() / AGING_STEP.as_secs_f64()) as usize;
+        index.saturating_sub(steps)
+    }
+
+    /// Turns "items/ash_prime_set/orders" into "items/{}/orders" so counters are kept per route.
+    pub fn endpoint_key(method: &str, url: &str) -> String {
+        let path = url.split('?').next().unwrap_or_default();
+        let route = path
+            .trim_matches('/')
+            .split('/')
+            .map(|segment| {
+                if ROUTE_SEGMENTS.contains(&segment) {
+                    segment
+                } else {
+                    "{}"
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        format!("{} {}", method, route)
+    }
+
+    fn refill(&self, state: &mut SchedulerState) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * state.rate).min(state.burst);
+        state.last_refill = now;
+    }
+
+    /// Waits until a request with the given priority may be sent.
+    pub async fn acquire(&self, priority: RequestPriority, endpoint: &str) {
+        let started = Instant::now();
+        let index = priority as usize;
+        let _slot = QueueSlot::new(self, index);
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                self.refill(&mut state);
+                let aged_index = Self::aged_index(index, started.elapsed());
+                let higher_waiting = state.waiting[..aged_index].iter().any(|count| *count > 0);
+                if !higher_waiting && state.tokens >= 1.0 {
+                    state.tokens -= 1.0;
+                    let stats = state
+                        .endpoints
+                        .entry(endpoint.to_string())
+                        .or_insert_with(|| EndpointStats {
+                            endpoint: endpoint.to_string(),
+                            ..Default::default()
+                        });
+                    stats.requests += 1;
+                    stats.total_wait_ms += started.elapsed().as_millis() as i64;
+                    stats.last_request = Some(chrono::Utc::now().to_rfc3339());
+                    break;
+                }
+                // The wait can never be negative, the bucket may already be full
+                // while a request with a higher priority is served first
+                let missing = (1.0 - state.tokens).max(0.0);
+                Duration::from_secs_f64((missing / state.rate).max(0.001))
+            };
+            // Wake up early when another request got its token, the queue may have changed
+            let _ = tokio::time::timeout(wait, self.notify.notified()).await;
+        }
+    }
+
+    /// Records the response of a request and adapts the rate to it.
+    pub fn report(&self, endpoint: &str, status: Option<u16>) {
+        let mut state = self.state.lock().unwrap();
+        match status {
+            Some(429) => {
+                state.rate = (state.rate / 2.0).max(state.min_rate());
+                state.tokens = 0.0;
+            }
+            Some(status) if status < 500 => {
+                state.rate = (state.rate + state.base_rate / 20.0).min(state.base_rate);
+            }
+            _ => {}
+        }
+        if let Some(stats) = state.endpoints.get_mut(endpoint) {
+            stats.last_status = status;
+            if status == Some(429) {
+                stats.rate_limited += 1;
+            }
+            if status.map(|status| status >= 400).unwrap_or(true) {
+                stats.errors += 1;
+            }
+        }
+    }
+
+    pub fn get_status(&self) -> SchedulerStatus {
+        let state = self.state.lock().unwrap();
+        let mut endpoints: Vec<EndpointStats> = state.endpoints.values().cloned().collect();
+        endpoints.sort_by(|a, b| b.requests.cmp(&a.requests));
+        SchedulerStatus {
+            rate: state.rate,
+            base_rate: state.base_rate,
+            burst: state.burst,
+            waiting: HashMap::from([
+                ("interactive".to_string(), state.waiting[0]),
+                ("mutation".to_string(), state.waiting[1]),
+                ("background".to_string(), state.waiting[2]),
+                ("scraping".to_string(), state.waiting[3]),
+            ]),
+            endpoints,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    use super::{RequestPriority, RequestScheduler, AGING_STEP};
+
+    #[tokio::test]
+    async fn serves_the_higher_priority_first() {
+        let scheduler = Arc::new(RequestScheduler::new(20.0, 1.0));
+        // Empty the bucket so both requests have to wait
+        scheduler.acquire(RequestPriority::Interactive, "GET items").await;
+
+        let order = Arc::new(Mutex::new(vec![]));
+        let mut handles = vec![];
+        for priority in [RequestPriority::Scraping, RequestPriority::Interactive] {
+            let scheduler = Arc::clone(&scheduler);
+            let order = Arc::clone(&order);
+            handles.push(tokio::spawn(async move {
+                scheduler.acquire(priority, "GET items").await;
+                order.lock().unwrap().push(priority);
+            }));
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec![RequestPriority::Interactive, RequestPriority::Scraping]
+        );
+    }
+
+    #[tokio::test]
+    async fn sends_a_burst_at_once() {
+        let scheduler = RequestScheduler::new(1.0, 3.0);
+        let started = Instant::now();
+        for _ in 0..3 {
+            scheduler.acquire(RequestPriority::Scraping, "GET items").await;
+        }
+        assert!(started.elapsed() < Duration::from_millis(500));
+        assert_eq!(scheduler.get_status().endpoints[0].requests, 3);
+    }
+
+    #[test]
+    fn halves_the_rate_on_429_down_to_the_floor() {
+        let scheduler = RequestScheduler::new(8.0, 3.0);
+        scheduler.report("GET items", Some(429));
+        assert_eq!(scheduler.get_status().rate, 4.0);
+        for _ in 0..10 {
+            scheduler.report("GET items", Some(429));
+        }
+        assert_eq!(scheduler.get_status().rate, 1.0);
+    }
+
+    #[test]
+    fn recovers_the_rate_on_success() {
+        let scheduler = RequestScheduler::new(8.0, 3.0);
+        scheduler.report("GET items", Some(429));
+        scheduler.report("GET items", Some(200));
+        assert!((scheduler.get_status().rate - 4.4).abs() < 1e-9);
+        // Server errors say nothing about the rate
+        scheduler.report("GET items", Some(503));
+        assert!((scheduler.get_status().rate - 4.4).abs() < 1e-9);
+        for _ in 0..20 {
+            scheduler.report("GET items", Some(200));
+        }
+        assert_eq!(scheduler.get_status().rate, 8.0);
+    }
+
+    #[test]
+    fn a_waiting_request_moves_up() {
+        let scraping = RequestPriority::Scraping as usize;
+        assert_eq!(RequestScheduler::aged_index(scraping, Duration::ZERO), scraping);
+        assert_eq!(RequestScheduler::aged_index(scraping, AGING_STEP), scraping - 1);
+        assert_eq!(RequestScheduler::aged_index(scraping, AGING_STEP * 10), 0);
+    }
+
+    #[test]
+    fn new_limits_keep_the_lowered_share() {
+        let scheduler = RequestScheduler::new(4.0, 1.0);
+        scheduler.report("GET items", Some(429));
+        scheduler.set_limits(8.0, 5.0);
+        let status = scheduler.get_status();
+        assert_eq!((status.rate, status.base_rate, status.burst), (4.0, 8.0, 5.0));
+    }
+}
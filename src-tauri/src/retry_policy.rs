@@ -0,0 +1,203 @@
+use std::{future::Future, time::Duration};
+
+use rand::Rng;
+use reqwest::{header::HeaderMap, Method, Response, StatusCode};
+
+use crate::request_scheduler::RequestScheduler;
+
+// Routes that change state relative to the current one, sending them twice closes twice
+const NOT_IDEMPOTENT: [&str; 2] = ["PUT profile/orders/close/{}", "PUT auctions/entry/{}/close"];
+
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    // Upper bound for the delay asked by a Retry-After header
+    pub max_retry_after: Duration,
+    pub timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            max_retry_after: Duration::from_secs(60),
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns true if sending the request twice has the same effect as sending it once.
+    /// Closing an order lowers its quantity, so it is never retried.
+    pub fn is_idempotent(method: &Method, url: &str) -> bool {
+        match *method {
+            Method::GET | Method::HEAD | Method::DELETE => true,
+            Method::PUT => {
+                let endpoint = RequestScheduler::endpoint_key(method.as_str(), url);
+                !NOT_IDEMPOTENT.contains(&endpoint.as_str())
+            }
+            _ => false,
+        }
+    }
+
+    /// Too many requests and server errors are expected to go away on their own.
+    pub fn is_transient_status(status: StatusCode) -> bool {
+        status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+    }
+
+    pub fn is_transient_error(error: &reqwest::Error) -> bool {
+        error.is_timeout() || error.is_connect()
+    }
+
+    /// Reads a Retry-After header given either in seconds or as an http date.
+    pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+        let value = headers.get("retry-after")?.to_str().ok()?.trim();
+        if let Ok(seconds) = value.parse::<u64>() {
+            return Some(Duration::from_secs(seconds));
+        }
+        let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+        let seconds = (date.timestamp_millis() - chrono::Utc::now().timestamp_millis()).max(0);
+        Some(Duration::from_millis(seconds as u64))
+    }
+
+    /// Exponential backoff with jitter, the delay is between half and all of `base_delay * 2^attempt`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        let half = delay.as_secs_f64() / 2.0;
+        Duration::from_secs_f64(half + half * rand::thread_rng().gen::<f64>())
+    }
+
+    /// Returns how long to wait before retrying, or None if the result should be used as is.
+    pub fn should_retry(
+        &self,
+        method: &Method,
+        url: &str,
+        attempt: u32,
+        result: &Result<Response, reqwest::Error>,
+    ) -> Option<Duration> {
+        if attempt >= self.max_retries || !Self::is_idempotent(method, url) {
+            return None;
+        }
+        match result {
+            Ok(response) if Self::is_transient_status(response.status()) => {
+                match Self::retry_after(response.headers()) {
+                    Some(delay) => Some(delay.min(self.max_retry_after)),
+                    None => Some(self.backoff(attempt)),
+                }
+            }
+            Err(e) if Self::is_transient_error(e) => Some(self.backoff(attempt)),
+            _ => None,
+        }
+    }
+
+    /// Sends the request until it succeeds or may not be retried any more.
+    /// `on_retry` is told why an attempt failed, how long is waited and the attempt number.
+    /// Returns the last result and how many retries were made.
+    pub async fn send<F, Fut>(
+        &self,
+        method: &Method,
+        url: &str,
+        mut send: F,
+        mut on_retry: impl FnMut(String, Duration, u32),
+    ) -> (Result<Response, reqwest::Error>, u32)
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<Response, reqwest::Error>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let response = send().await;
+            match self.should_retry(method, url, attempt, &response) {
+                Some(delay) => {
+                    attempt += 1;
+                    on_retry(Self::describe(&response), delay, attempt);
+                    tokio::time::sleep(delay).await;
+                }
+                None => return (response, attempt),
+            }
+        }
+    }
+
+    /// Describes a failed attempt for the retry warning.
+    pub fn describe(result: &Result<Response, reqwest::Error>) -> String {
+        match result {
+            Ok(response) => format!("status {}", response.status()),
+            Err(e) => e.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::{
+        header::{HeaderMap, HeaderValue},
+        Method,
+    };
+
+    use super::RetryPolicy;
+
+    fn headers(retry_after: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", HeaderValue::from_str(retry_after).unwrap());
+        headers
+    }
+
+    #[test]
+    fn reads_retry_after_in_seconds_and_as_a_date() {
+        assert_eq!(
+            RetryPolicy::retry_after(&headers(" 7 ")),
+            Some(Duration::from_secs(7))
+        );
+
+        let date = (chrono::Utc::now() + chrono::Duration::seconds(30)).to_rfc2822();
+        let delay = RetryPolicy::retry_after(&headers(&date)).unwrap();
+        assert!(delay > Duration::from_secs(28) && delay <= Duration::from_secs(30));
+
+        // A date in the past means the request may be sent right away
+        let date = (chrono::Utc::now() - chrono::Duration::seconds(30)).to_rfc2822();
+        assert_eq!(RetryPolicy::retry_after(&headers(&date)), Some(Duration::ZERO));
+
+        assert_eq!(RetryPolicy::retry_after(&headers("soon")), None);
+        assert_eq!(RetryPolicy::retry_after(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn keeps_the_backoff_within_its_bounds() {
+        let policy = RetryPolicy::default();
+        for attempt in 0..8 {
+            let full = policy
+                .base_delay
+                .saturating_mul(2u32.pow(attempt))
+                .min(policy.max_delay);
+            for _ in 0..50 {
+                let delay = policy.backoff(attempt);
+                assert!(delay >= full / 2 && delay <= full, "{:?} for {}", delay, attempt);
+            }
+        }
+        // Large attempts neither overflow nor pass the maximum
+        assert!(policy.backoff(u32::MAX) <= policy.max_delay);
+    }
+
+    #[test]
+    fn only_retries_requests_that_are_safe_to_repeat() {
+        assert!(RetryPolicy::is_idempotent(&Method::GET, "items/ash_prime_set/orders"));
+        assert!(RetryPolicy::is_idempotent(&Method::DELETE, "profile/orders/o1"));
+        assert!(RetryPolicy::is_idempotent(&Method::PUT, "profile/orders/o1"));
+        assert!(RetryPolicy::is_idempotent(&Method::PUT, "im/chats/c1/read"));
+        assert!(RetryPolicy::is_idempotent(&Method::PUT, "auctions/entry/a1"));
+        assert!(!RetryPolicy::is_idempotent(&Method::PUT, "profile/orders/close/o1"));
+        assert!(!RetryPolicy::is_idempotent(&Method::PUT, "auctions/entry/a1/close"));
+        assert!(!RetryPolicy::is_idempotent(&Method::POST, "profile/orders"));
+        // An item whose name contains close is still updated safely
+        assert!(RetryPolicy::is_idempotent(&Method::PUT, "profile/orders/closed_item"));
+    }
+}
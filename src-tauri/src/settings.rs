@@ -1,10 +1,11 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::PathBuf;
 
-use crate::enums::{OrderMode, StockMode};
+use crate::enums::{LotOrder, OrderMode, StockMode};
 use crate::error::AppError;
 use crate::{helper, logger};
 use eyre::eyre;
@@ -16,6 +17,34 @@ pub struct SettingsState {
     pub dev_mode: bool,
     pub live_scraper: LiveScraperSettings,
     pub notifications: Notifications,
+    pub ee_log: EELogSettings,
+    pub endpoints: EndpointSettings,
+    pub backup: BackupSettings,
+    pub requests: RequestSettings,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RequestSettings {
+    // Requests per second sent to warframe.market
+    pub rate: f64,
+    // Requests that may be sent at once after a quiet moment
+    pub burst: f64,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupSettings {
+    // Back the database up on a schedule
+    pub enabled: bool,
+    pub interval_hours: i64,
+    // How many scheduled backups are kept
+    pub keep: i64,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EndpointSettings {
+    // Base url of the warframe.market api
+    pub wfm_api: String,
+    // Base url of the relics.run price history
+    pub relics_run: String,
+    // Url of the warframe.market websocket
+    pub wfm_socket: String,
 }
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct LiveScraperSettings {
@@ -44,6 +73,8 @@ pub struct StockItemSettings {
     pub strict_whitelist: bool,
     // What to post sell, buy, or both
     pub order_mode: OrderMode,
+    // Which purchase lots a sale takes from first
+    pub lot_order: LotOrder,
 }
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct StockRivenSettings {
@@ -65,6 +96,24 @@ pub struct Notification {
 pub struct Notifications {
     pub on_new_conversation: Notification,
     pub on_wfm_chat_message: Notification,
+    pub on_whisper_offer: Notification,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EELogSettings {
+    // Enable flag per EE.log event id
+    pub events: HashMap<String, bool>,
+    // Folder with EE.log, empty to search the known locations
+    pub custom_folder: String,
+    pub game_session: GameSessionSettings,
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameSessionSettings {
+    // Set the warframe.market status from the game state
+    pub auto_status: bool,
+    // Status to use when the game is closed
+    pub closed_status: String,
+    pub pause_live_scraper: bool,
+    pub hide_orders: bool,
 }
 // Allow us to run AuthState::default()
 impl Default for SettingsState {
@@ -87,6 +136,7 @@ impl Default for SettingsState {
                     report_to_wfm: true,
                     auto_trade: true,
                     order_mode: OrderMode::Both,
+                    lot_order: LotOrder::Fifo,
                 },
                 stock_riven: StockRivenSettings {
                     range_threshold: 25,
@@ -109,10 +159,91 @@ impl Default for SettingsState {
                     webhook: Some("".to_string()),
                     user_ids: Some(vec![]),
                 },
+                on_whisper_offer: Notification {
+                    discord_notify: false,
+                    system_notify: true,
+                    content: "<PLAYER_NAME> wants to <ORDER_TYPE> <ITEM_NAME> for <PRICE>p. <MATCH>"
+                        .to_string(),
+                    title: "New Whisper Offer".to_string(),
+                    webhook: Some("".to_string()),
+                    user_ids: Some(vec![]),
+                },
+            },
+            ee_log: EELogSettings {
+                events: HashMap::from([
+                    ("on_new_conversation".to_string(), true),
+                    ("on_new_whisper".to_string(), true),
+                    ("on_trading".to_string(), true),
+                    ("on_game_session".to_string(), true),
+                ]),
+                custom_folder: "".to_string(),
+                game_session: GameSessionSettings {
+                    auto_status: true,
+                    closed_status: "invisible".to_string(),
+                    pause_live_scraper: false,
+                    hide_orders: false,
+                },
+            },
+            endpoints: EndpointSettings {
+                wfm_api: "https://api.warframe.market/v1/".to_string(),
+                relics_run: "http://relics.run/history/".to_string(),
+                wfm_socket: "wss://warframe.market/socket".to_string(),
+            },
+            backup: BackupSettings {
+                enabled: true,
+                interval_hours: 24,
+                keep: 7,
+            },
+            requests: RequestSettings {
+                rate: 1.0,
+                burst: 3.0,
             },
         }
     }
 }
+impl EndpointSettings {
+    fn with_slash(url: &str, default: &str) -> String {
+        let url = if url.is_empty() { default } else { url };
+        format!("{}/", url.trim_end_matches('/'))
+    }
+
+    /// Base url of the warframe.market api, ending with a slash.
+    pub fn get_wfm_api(&self) -> String {
+        Self::with_slash(&self.wfm_api, "https://api.warframe.market/v1/")
+    }
+
+    /// Base url of the relics.run price history, ending with a slash.
+    pub fn get_relics_run(&self) -> String {
+        Self::with_slash(&self.relics_run, "http://relics.run/history/")
+    }
+
+    /// Checks that every endpoint that is set is a valid url.
+    pub fn validate(&self) -> Result<(), AppError> {
+        for (name, url) in [
+            ("wfm_api", &self.wfm_api),
+            ("relics_run", &self.relics_run),
+            ("wfm_socket", &self.wfm_socket),
+        ] {
+            if url.is_empty() {
+                continue;
+            }
+            reqwest::Url::parse(url).map_err(|e| {
+                AppError::new("Settings", eyre!("Invalid {} endpoint {}: {}", name, url, e))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Url of the warframe.market websocket for the given platform.
+    pub fn get_wfm_socket(&self, platform: &str) -> String {
+        let url = if self.wfm_socket.is_empty() {
+            "wss://warframe.market/socket"
+        } else {
+            self.wfm_socket.as_str()
+        };
+        format!("{}?platform={}", url.trim_end_matches('/'), platform)
+    }
+}
 impl SettingsState {
     fn get_file_path() -> PathBuf {
         let app_path = helper::get_app_roaming_path();
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
 
 use crate::enums::OrderType;
@@ -116,18 +118,150 @@ pub struct Item {
     pub mod_max_rank: Option<i64>,
     pub subtypes: Option<Vec<String>>,
 }
+// Languages warframe.market translates item names to
+pub const ITEM_LANGUAGES: [&str; 13] = [
+    "en", "ru", "ko", "de", "fr", "pt", "zh-hans", "zh-hant", "es", "it", "pl", "uk", "sv",
+];
+
+/// An item with every item of its set, a set is listed as its root followed by the parts.
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct ItemDetails {
     pub id: String,
     pub items_in_set: Vec<ItemInfo>,
 }
+
+impl ItemDetails {
+    pub fn find(&self, url_name: &str) -> Option<&ItemInfo> {
+        self.items_in_set
+            .iter()
+            .find(|item| item.url_name == url_name)
+    }
+
+    /// The item that was asked for, the set root for a set.
+    pub fn item(&self) -> Option<&ItemInfo> {
+        self.items_in_set
+            .iter()
+            .find(|item| item.id == self.id)
+            .or(self.items_in_set.get(0))
+    }
+
+    pub fn set_root(&self) -> Option<&ItemInfo> {
+        self.items_in_set.iter().find(|item| item.set_root)
+    }
+
+    /// The parts needed for the set, each with the quantity the set needs.
+    pub fn parts(&self) -> Vec<&ItemInfo> {
+        self.items_in_set
+            .iter()
+            .filter(|item| !item.set_root)
+            .collect()
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.set_root().is_some() && self.items_in_set.len() > 1
+    }
+
+    /// The ducats of all parts of the set, counted as often as the set needs them.
+    pub fn set_ducats(&self) -> i64 {
+        self.parts()
+            .iter()
+            .map(|part| part.ducats.unwrap_or(0) * part.quantity_for_set.unwrap_or(1))
+            .sum()
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(from = "RawItemInfo")]
 pub struct ItemInfo {
-    #[serde(rename = "id")]
     pub id: String,
-
-    #[serde(rename = "mod_max_rank")]
+    pub url_name: String,
+    pub set_root: bool,
     pub mod_max_rank: Option<f64>,
+    pub ducats: Option<i64>,
+    pub trading_tax: Option<i64>,
+    pub quantity_for_set: Option<i64>,
+    pub rarity: Option<String>,
+    pub vaulted: Option<bool>,
+    pub mastery_level: Option<i64>,
+    pub tags: Vec<String>,
+    pub subtypes: Option<Vec<String>>,
+    pub thumb: Option<String>,
+    // Names and descriptions by language, like "en" or "zh-hans"
+    pub translations: HashMap<String, ItemTranslation>,
+}
+
+impl ItemInfo {
+    /// The name in `language`, or the english one if it is not translated.
+    pub fn name(&self, language: &str) -> Option<&str> {
+        self.translations
+            .get(language)
+            .or(self.translations.get("en"))
+            .map(|translation| translation.item_name.as_str())
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ItemTranslation {
+    pub item_name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub wiki_link: Option<String>,
+}
+
+// warframe.market sends each translation as its own key next to the item fields,
+// the cache keeps them together in `translations`
+#[derive(Deserialize)]
+struct RawItemInfo {
+    id: String,
+    #[serde(default)]
+    url_name: String,
+    #[serde(default)]
+    set_root: bool,
+    mod_max_rank: Option<f64>,
+    ducats: Option<i64>,
+    trading_tax: Option<i64>,
+    quantity_for_set: Option<i64>,
+    rarity: Option<String>,
+    vaulted: Option<bool>,
+    mastery_level: Option<i64>,
+    #[serde(default)]
+    tags: Vec<String>,
+    subtypes: Option<Vec<String>>,
+    thumb: Option<String>,
+    #[serde(default)]
+    translations: HashMap<String, ItemTranslation>,
+    #[serde(flatten)]
+    other: HashMap<String, serde_json::Value>,
+}
+
+impl From<RawItemInfo> for ItemInfo {
+    fn from(raw: RawItemInfo) -> Self {
+        let mut translations = raw.translations;
+        for language in ITEM_LANGUAGES.iter() {
+            if let Some(value) = raw.other.get(*language) {
+                if let Ok(translation) = serde_json::from_value(value.clone()) {
+                    translations.insert(language.to_string(), translation);
+                }
+            }
+        }
+        ItemInfo {
+            id: raw.id,
+            url_name: raw.url_name,
+            set_root: raw.set_root,
+            mod_max_rank: raw.mod_max_rank,
+            ducats: raw.ducats,
+            trading_tax: raw.trading_tax,
+            quantity_for_set: raw.quantity_for_set,
+            rarity: raw.rarity,
+            vaulted: raw.vaulted,
+            mastery_level: raw.mastery_level,
+            tags: raw.tags,
+            subtypes: raw.subtypes,
+            thumb: raw.thumb,
+            translations,
+        }
+    }
 }
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Order {
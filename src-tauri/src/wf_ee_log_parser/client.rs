@@ -1,33 +1,57 @@
+use crate::auth::AuthState;
 use crate::cache::client::CacheClient;
+use crate::database::client::DBClient;
 use crate::error::AppError;
 use crate::handler::MonitorHandler;
+use crate::live_scraper::client::LiveScraperClient;
+use crate::request_scheduler::RequestPriority;
 use crate::settings::SettingsState;
+use crate::wfm_client::client::WFMClient;
 use crate::{helper, logger};
-use serde_json::json;
+use serde_json::{json, Value};
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Seek, SeekFrom}; // Add Seek here
-use std::path::PathBuf;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom}; // Add Seek here
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use super::events::on_game_session::OnGameSessionEvent;
 use super::events::on_new_conversation::OnNewConversationEvent;
 use super::events::on_new_trading::OnTradingEvent;
+use super::events::on_new_whisper::OnNewWhisperEvent;
+use super::modules::backfill::{BackfillModule, BackfillPreview};
+use super::registry::EELogEventRegistry;
+
+// How long a failed search for EE.log is kept before searching again
+const SEARCH_INTERVAL: Duration = Duration::from_secs(30);
+// How much of the end of EE.log is read on start to rebuild the state of the events
+const RESTORE_TAIL_SIZE: u64 = 512 * 1024;
 
 #[derive(Clone, Debug)]
 pub struct EELogParser {
     is_running: Arc<AtomicBool>,
-    wf_ee_path: PathBuf,
+    wf_ee_path: Arc<Mutex<PathBuf>>,
+    // Folders searched for EE.log, the custom folder they were searched for and when
+    searched_folders: Arc<Mutex<Vec<PathBuf>>>,
+    custom_folder: Arc<Mutex<Option<String>>>,
+    last_search: Arc<Mutex<Instant>>,
     component: String,
     last_file_size: Arc<Mutex<u64>>,
     last_line_index: Arc<Mutex<usize>>,
     handle: Arc<Mutex<Option<JoinHandle<()>>>>,
     cold_start: Arc<AtomicBool>,
+    pub log_file: String,
     pub settings: Arc<Mutex<crate::settings::SettingsState>>,
+    pub backfill_preview: Arc<Mutex<Option<BackfillPreview>>>,
+    pub db: Arc<Mutex<DBClient>>,
+    // Kept to create the trading event that replays a log
+    mh: Arc<Mutex<MonitorHandler>>,
+    cache: Arc<Mutex<CacheClient>>,
+    wfm: Arc<Mutex<WFMClient>>,
     // Events
-    event_conversation: Arc<Mutex<OnNewConversationEvent>>,
-    event_trading: Arc<Mutex<OnTradingEvent>>,
+    events: Arc<Mutex<EELogEventRegistry>>,
 }
 
 impl EELogParser {
@@ -35,29 +59,71 @@ impl EELogParser {
         settings: Arc<Mutex<SettingsState>>,
         mh: Arc<Mutex<MonitorHandler>>,
         cache: Arc<Mutex<CacheClient>>,
-    ) -> Self {
-        let wf_ee_path = helper::get_app_local_path().join("Warframe").join("EE.log");
-        Self {
+        auth: Arc<Mutex<AuthState>>,
+        wfm: Arc<Mutex<WFMClient>>,
+        live_scraper: Arc<Mutex<LiveScraperClient>>,
+        db: Arc<Mutex<DBClient>>,
+    ) -> Result<Self, AppError> {
+        let custom_folder = settings.lock().unwrap().ee_log.custom_folder.clone();
+        let (folder, searched_folders) = helper::find_warframe_log_folder(&custom_folder);
+        let wf_ee_path = folder
+            .clone()
+            .unwrap_or(helper::get_app_local_path().join("Warframe"))
+            .join("EE.log");
+        *helper::WARFRAME_LOG_FOLDER.lock().unwrap() = folder;
+        let log_file = "ee_log_parser.log";
+        // Events wait while the session is expired instead of failing
+        let wfm = Arc::new(Mutex::new(
+            wfm.lock().unwrap().with_priority(RequestPriority::Background),
+        ));
+
+        // Register the events, the first event that handles a line stops the others from seeing it
+        let mut events = EELogEventRegistry::new(Arc::clone(&settings), log_file);
+        events.register(Arc::new(Mutex::new(OnNewConversationEvent::new(
+            Arc::clone(&settings),
+            Arc::clone(&mh),
+        ))));
+        events.register(Arc::new(Mutex::new(OnNewWhisperEvent::new(
+            Arc::clone(&settings),
+            Arc::clone(&mh),
+            Arc::clone(&cache),
+            Arc::clone(&wfm),
+            Arc::clone(&db),
+        ))));
+        events.register(Arc::new(Mutex::new(OnTradingEvent::new(
+            Arc::clone(&settings),
+            Arc::clone(&mh),
+            Arc::clone(&cache),
+            Arc::clone(&db),
+            Arc::clone(&wfm),
+        )?)));
+        events.register(Arc::new(Mutex::new(OnGameSessionEvent::new(
+            Arc::clone(&settings),
+            Arc::clone(&auth),
+            Arc::clone(&wfm),
+            Arc::clone(&live_scraper),
+        ))));
+
+        Ok(Self {
             is_running: Arc::new(AtomicBool::new(false)),
             component: "EELogParser".to_string(),
-            wf_ee_path: wf_ee_path.clone(),
+            wf_ee_path: Arc::new(Mutex::new(wf_ee_path)),
+            searched_folders: Arc::new(Mutex::new(searched_folders)),
+            custom_folder: Arc::new(Mutex::new(Some(custom_folder))),
+            last_search: Arc::new(Mutex::new(Instant::now())),
             last_file_size: Arc::new(Mutex::new(0)),
             last_line_index: Arc::new(Mutex::new(0)),
             handle: Arc::new(Mutex::new(None)),
             cold_start: Arc::new(AtomicBool::new(true)),
+            log_file: log_file.to_string(),
             settings: Arc::clone(&settings),
-            event_conversation: Arc::new(Mutex::new(OnNewConversationEvent::new(
-                Arc::clone(&settings),
-                Arc::clone(&mh),
-                wf_ee_path.clone(),
-            ))),
-            event_trading: Arc::new(Mutex::new(OnTradingEvent::new(
-                Arc::clone(&settings),
-                Arc::clone(&mh),
-                Arc::clone(&cache),
-                wf_ee_path.clone(),
-            ))),
-        }
+            backfill_preview: Arc::new(Mutex::new(None)),
+            db,
+            mh,
+            cache,
+            wfm,
+            events: Arc::new(Mutex::new(events)),
+        })
     }
 
     pub fn start_loop(&mut self) {
@@ -92,22 +158,121 @@ impl EELogParser {
         self.is_running.load(Ordering::SeqCst)
     }
 
+    pub fn get_ee_log_path(&self) -> Result<PathBuf, AppError> {
+        Ok(self.wf_ee_path.lock()?.clone())
+    }
+
+    pub fn get_status(&self) -> Result<Value, AppError> {
+        let path = self.get_ee_log_path()?;
+        let searched_folders = self.searched_folders.lock()?.clone();
+        Ok(json!({
+            "found": path.exists(),
+            "path": path,
+            "searched_folders": searched_folders,
+        }))
+    }
+
+    /// Searches for EE.log again when the custom folder has changed, or when it is missing
+    /// and the last search is older than `SEARCH_INTERVAL`.
+    /// Returns true when there is a log file to read.
+    fn update_path(&self) -> Result<bool, AppError> {
+        let custom_folder = self.settings.lock()?.ee_log.custom_folder.clone();
+        let mut last_custom_folder = self.custom_folder.lock()?;
+        let mut last_search = self.last_search.lock()?;
+        let current_path = self.get_ee_log_path()?;
+        let same_folder = last_custom_folder.as_ref() == Some(&custom_folder);
+        if current_path.exists() && same_folder {
+            return Ok(true);
+        }
+        // Keep the last result until it is time to search again
+        if same_folder && current_path == PathBuf::new() && last_search.elapsed() < SEARCH_INTERVAL
+        {
+            return Ok(false);
+        }
+        let was_found = current_path.exists();
+
+        let (folder, searched_folders) = helper::find_warframe_log_folder(&custom_folder);
+        *last_custom_folder = Some(custom_folder);
+        *last_search = Instant::now();
+        *self.searched_folders.lock()? = searched_folders;
+        *helper::WARFRAME_LOG_FOLDER.lock()? = folder.clone();
+
+        let path = match folder {
+            Some(folder) => folder.join("EE.log"),
+            None => {
+                if was_found || current_path != PathBuf::new() {
+                    logger::warning_con(
+                        self.component.as_str(),
+                        "Could not find EE.log in any known location",
+                    );
+                    helper::send_message_to_window("EELogParser:Status", Some(self.get_status()?));
+                    *self.wf_ee_path.lock()? = PathBuf::new();
+                }
+                return Ok(false);
+            }
+        };
+        if path != current_path {
+            logger::info_con(
+                self.component.as_str(),
+                format!("Using EE.log at {}", path.display()).as_str(),
+            );
+            *self.wf_ee_path.lock()? = path;
+            // Start at the end of the new file
+            self.cold_start.store(true, Ordering::SeqCst);
+            *self.last_line_index.lock()? = 0;
+            helper::send_message_to_window("EELogParser:Status", Some(self.get_status()?));
+        }
+        Ok(true)
+    }
+
+    pub fn get_event_ids(&self) -> Result<Vec<&'static str>, AppError> {
+        Ok(self.events.lock()?.get_ids())
+    }
+
+    /// A trading event that collects the trades of a replayed log instead of storing them.
+    pub fn replay_trading(&self) -> Result<OnTradingEvent, AppError> {
+        OnTradingEvent::replay(
+            Arc::clone(&self.settings),
+            Arc::clone(&self.mh),
+            Arc::clone(&self.cache),
+            Arc::clone(&self.db),
+            Arc::clone(&self.wfm),
+        )
+    }
+
+    pub fn backfill(&self) -> BackfillModule {
+        BackfillModule {
+            client: self,
+            debug_id: "ee_log_backfill".to_string(),
+        }
+    }
+
     fn check(&self) -> Result<(), AppError> {
-        let new_lines_result = self.read_new_lines(self.cold_start.load(Ordering::SeqCst));
+        if !self.update_path()? {
+            return Ok(());
+        }
+        let path = self.get_ee_log_path()?;
+        let is_starting = self.cold_start.load(Ordering::SeqCst);
+        let new_lines_result = self.read_new_lines(&path, is_starting);
 
         // Events to check
-        let event_conversation = self.event_conversation.lock()?.clone();
-        let mut event_trading = self.event_trading.lock()?;
+        let events = self.events.lock()?;
+
+        // The lines already in the log are not acted on, but tell the events where the game is
+        if is_starting {
+            match Self::read_tail(&path, RESTORE_TAIL_SIZE) {
+                Ok(lines) => events.restore(&lines),
+                Err(e) => logger::warning_con(
+                    self.component.as_str(),
+                    format!("Could not read the end of EE.log: {}", e).as_str(),
+                ),
+            }
+        }
 
         match new_lines_result {
             Ok(new_lines) => {
                 for line in new_lines {
-                    if event_conversation.check(line.0, &line.1)? {
-                        continue;
-                    }
-                    if event_trading.check(line.0, &line.1)? {
-                        continue;
-                    }
+                    events.dispatch(line.0, &line.1);
                 }
             }
             Err(err) => {
@@ -121,9 +286,25 @@ impl EELogParser {
         Ok(())
     }
 
-    fn read_new_lines(&self, is_starting: bool) -> io::Result<Vec<(usize, String)>> {
+    /// Reads the complete lines in the last `size` bytes of the file.
+    fn read_tail(path: &Path, size: u64) -> io::Result<Vec<String>> {
+        let mut file = File::open(path)?;
+        let start = file.metadata()?.len().saturating_sub(size);
+        file.seek(SeekFrom::Start(start))?;
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)?;
+        let content = String::from_utf8_lossy(&content);
+        let mut lines = content.lines();
+        // The first line is cut off unless the whole file was read
+        if start > 0 {
+            lines.next();
+        }
+        Ok(lines.map(|line| line.to_string()).collect())
+    }
+
+    fn read_new_lines(&self, path: &Path, is_starting: bool) -> io::Result<Vec<(usize, String)>> {
         let mut new_lines: Vec<(usize, String)> = Vec::new();
-        let mut file = File::open(&self.wf_ee_path)?;
+        let mut file = File::open(path)?;
 
         let metadata = file.metadata()?;
         let current_file_size = metadata.len();
@@ -1,4 +1,5 @@
 use crate::error::AppError;
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
 use eyre::eyre;
 use regex::Regex;
 
@@ -49,3 +50,34 @@ pub fn get_range_of_lines(
 
     Ok(lines)
 }
+
+/// Returns the seconds since game start that prefix every EE.log line, e.g. `"123.456 Sys [Info]: ..."`.
+pub fn get_line_offset(input: &str) -> Option<f64> {
+    let offset = input.split_whitespace().next()?;
+    offset.parse::<f64>().ok()
+}
+
+/// Returns the UTC time the log was started from the `Current time:` line written at game launch.
+pub fn get_log_start_time(input: &str) -> Option<DateTime<Utc>> {
+    let re = Regex::new(r"Current time: .+ \[UTC: (?<utc>.+)\]").ok()?;
+    let captures = re.captures(input)?;
+    let utc = captures.name("utc")?.as_str();
+    let utc = utc.split_whitespace().collect::<Vec<&str>>().join(" ");
+    let date = NaiveDateTime::parse_from_str(&utc, "%a %b %d %H:%M:%S %Y").ok()?;
+    Some(Utc.from_utc_datetime(&date))
+}
+
+/// Parses a date given by the frontend, either as RFC 3339 or as `YYYY-MM-DD`.
+pub fn parse_date(input: &str, end_of_day: bool) -> Result<DateTime<Utc>, AppError> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(input) {
+        return Ok(date.with_timezone(&Utc));
+    }
+    let date = chrono::NaiveDate::parse_from_str(input, "%Y-%m-%d")
+        .map_err(|e| AppError::new("parse_date", eyre!(format!("{}: {}", input, e))))?;
+    let time = if end_of_day {
+        date.and_hms_opt(23, 59, 59)
+    } else {
+        date.and_hms_opt(0, 0, 0)
+    };
+    Ok(Utc.from_utc_datetime(&time.unwrap()))
+}
@@ -1,3 +1,23 @@
+use crate::error::AppError;
+
 pub mod helper;
+pub mod on_game_session;
 pub mod on_new_conversation;
 pub mod on_new_trading;
+pub mod on_new_whisper;
+pub mod trade_log;
+
+/// A detector that reacts to new lines of the EE.log.
+pub trait EELogEvent: Send + std::fmt::Debug {
+    /// Unique id of the event, used as the key of its enable flag in the settings.
+    fn id(&self) -> &'static str;
+
+    /// Checks a new line of the log, returns `true` when the line was handled by the event.
+    fn check(&mut self, index: usize, input: &str) -> Result<bool, AppError>;
+
+    /// Rebuilds the state of the event from lines logged before the parser started.
+    /// Unlike `check` nothing is acted on, by default the lines are ignored.
+    fn restore(&mut self, _lines: &[String]) -> Result<(), AppError> {
+        Ok(())
+    }
+}
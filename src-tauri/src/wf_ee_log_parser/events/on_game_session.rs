@@ -0,0 +1,288 @@
+use std::sync::{Arc, Mutex};
+
+use eyre::eyre;
+use serde_json::json;
+
+use crate::{
+    auth::AuthState,
+    error::{self, AppError},
+    helper,
+    live_scraper::client::LiveScraperClient,
+    settings::SettingsState,
+    wfm_client::client::WFMClient,
+};
+
+use super::EELogEvent;
+
+enum Events {
+    GameStart,
+    Login,
+    Shutdown,
+}
+impl Events {
+    fn as_str_list(&self) -> Vec<String> {
+        match self {
+            Events::GameStart => vec![r"Sys \[Diag\]: Current time:".to_string()],
+            Events::Login => vec![r"Sys \[Info\]: Logged in (?<name>.+?) \(".to_string()],
+            Events::Shutdown => vec![r"Sys \[Info\]: Main Shutdown Initiated".to_string()],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameState {
+    Unknown,
+    // The game is running but the player is not logged in yet
+    Running,
+    LoggedIn,
+    Closed,
+}
+impl GameState {
+    pub fn as_str(&self) -> &str {
+        match self {
+            GameState::Unknown => "unknown",
+            GameState::Running => "running",
+            GameState::LoggedIn => "logged_in",
+            GameState::Closed => "closed",
+        }
+    }
+}
+
+pub struct OnGameSessionEvent {
+    settings: Arc<Mutex<SettingsState>>,
+    auth: Arc<Mutex<AuthState>>,
+    wfm: Arc<Mutex<WFMClient>>,
+    live_scraper: Arc<Mutex<LiveScraperClient>>,
+    state: GameState,
+    paused_live_scraper: bool,
+    // Orders we hid when the game was closed
+    hidden_orders: Arc<Mutex<Vec<String>>>,
+}
+
+impl std::fmt::Debug for OnGameSessionEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OnGameSessionEvent")
+            .field("state", &self.state)
+            .field("paused_live_scraper", &self.paused_live_scraper)
+            .finish()
+    }
+}
+
+impl OnGameSessionEvent {
+    pub fn new(
+        settings: Arc<Mutex<SettingsState>>,
+        auth: Arc<Mutex<AuthState>>,
+        wfm: Arc<Mutex<WFMClient>>,
+        live_scraper: Arc<Mutex<LiveScraperClient>>,
+    ) -> Self {
+        Self {
+            settings,
+            auth,
+            wfm,
+            live_scraper,
+            state: GameState::Unknown,
+            paused_live_scraper: false,
+            hidden_orders: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn get_state(&self) -> GameState {
+        self.state
+    }
+
+    fn get_state_from_line(input: &str) -> Result<Option<GameState>, AppError> {
+        let events = [
+            (Events::GameStart, GameState::Running),
+            (Events::Login, GameState::LoggedIn),
+            (Events::Shutdown, GameState::Closed),
+        ];
+        for (event, state) in events {
+            let (found, _) =
+                crate::wf_ee_log_parser::events::helper::match_pattern(input, event.as_str_list())
+                    .map_err(|e| AppError::new("OnGameSessionEvent", eyre!(e)))?;
+            if found {
+                return Ok(Some(state));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The state after the last line that changes it, if any does.
+    fn get_last_state(lines: &[String]) -> Result<Option<GameState>, AppError> {
+        let mut state = None;
+        for line in lines {
+            if let Some(line_state) = Self::get_state_from_line(line)? {
+                state = Some(line_state);
+            }
+        }
+        Ok(state)
+    }
+
+    fn on_state_changed(&mut self) -> Result<(), AppError> {
+        let settings = self.settings.lock()?.clone().ee_log.game_session;
+        helper::send_message_to_window(
+            "EELogParser:GameSession",
+            Some(json!({ "state": self.state.as_str() })),
+        );
+
+        // Update the warframe.market status
+        if settings.auto_status {
+            let status = match self.state {
+                GameState::LoggedIn => Some("ingame"),
+                GameState::Running => Some("online"),
+                GameState::Closed => Some(settings.closed_status.as_str()),
+                GameState::Unknown => None,
+            };
+            if let Some(status) = status {
+                self.auth.lock()?.set_status(status)?;
+            }
+        }
+
+        // Pause the live scraper while the game is closed
+        if settings.pause_live_scraper {
+            let mut live_scraper = self.live_scraper.lock()?;
+            if self.state == GameState::Closed && live_scraper.is_running() {
+                live_scraper.stop_loop();
+                self.paused_live_scraper = true;
+            } else if self.state == GameState::LoggedIn && self.paused_live_scraper {
+                self.paused_live_scraper = false;
+                live_scraper.start_loop()?;
+            }
+        }
+
+        // Hide our orders while the game is closed
+        if settings.hide_orders {
+            match self.state {
+                GameState::Closed => self.set_orders_visibility(false)?,
+                GameState::LoggedIn => self.set_orders_visibility(true)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn set_orders_visibility(&self, visible: bool) -> Result<(), AppError> {
+        let wfm = self.wfm.lock()?.clone();
+        let hidden_orders = Arc::clone(&self.hidden_orders);
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = Self::update_orders_visibility(wfm, hidden_orders, visible).await {
+                error::create_log_file("ee_log_parser.log".to_string(), &e);
+            }
+        });
+        Ok(())
+    }
+
+    async fn update_orders_visibility(
+        wfm: WFMClient,
+        hidden_orders: Arc<Mutex<Vec<String>>>,
+        visible: bool,
+    ) -> Result<(), AppError> {
+        let orders = wfm.orders().get_my_orders().await?;
+        let mut orders_vec = orders.sell_orders;
+        orders_vec.extend(orders.buy_orders);
+        if visible {
+            // Only show the orders we hid ourselves
+            let hidden = std::mem::take(&mut *hidden_orders.lock()?);
+            for order in orders_vec.iter().filter(|o| hidden.contains(&o.id)) {
+                wfm.orders()
+                    .update(&order.id, order.platinum as i32, order.quantity as i32, true)
+                    .await?;
+            }
+        } else {
+            for order in orders_vec.iter().filter(|o| o.visible) {
+                wfm.orders()
+                    .update(&order.id, order.platinum as i32, order.quantity as i32, false)
+                    .await?;
+                hidden_orders.lock()?.push(order.id.clone());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl EELogEvent for OnGameSessionEvent {
+    fn id(&self) -> &'static str {
+        "on_game_session"
+    }
+
+    fn check(&mut self, _: usize, input: &str) -> Result<bool, AppError> {
+        let state = match Self::get_state_from_line(input)? {
+            Some(state) => state,
+            None => return Ok(false),
+        };
+        if state != self.state {
+            self.state = state;
+            self.on_state_changed()?;
+        }
+        Ok(true)
+    }
+
+    fn restore(&mut self, lines: &[String]) -> Result<(), AppError> {
+        let state = match Self::get_last_state(lines)? {
+            Some(state) => state,
+            None => return Ok(()),
+        };
+        if state != self.state {
+            self.state = state;
+            // Only the window is told, the status and orders were set while the game ran
+            helper::send_message_to_window(
+                "EELogParser:GameSession",
+                Some(json!({ "state": self.state.as_str() })),
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_state_from_a_line() {
+        let cases = [
+            (
+                "0.000 Sys [Diag]: Current time: Sat Mar  2 18:04:11 2024 [UTC: Sat Mar  2 17:04:11 2024]",
+                Some(GameState::Running),
+            ),
+            (
+                "21.402 Sys [Info]: Logged in MockUser (5a1b2c3d4e5f60718293a4b5) Platform: PC",
+                Some(GameState::LoggedIn),
+            ),
+            (
+                "5321.118 Sys [Info]: Main Shutdown Initiated.",
+                Some(GameState::Closed),
+            ),
+            ("21.402 Sys [Info]: Logged in", None),
+            ("104.230 Script [Info]: Dialog.lua: Dialog::CreateOkCancel", None),
+        ];
+        for (line, expected) in cases {
+            let state = OnGameSessionEvent::get_state_from_line(line).unwrap();
+            assert_eq!(state, expected, "{}", line);
+        }
+    }
+
+    #[test]
+    fn keeps_the_last_state_of_the_log() {
+        let lines: Vec<String> = [
+            "0.000 Sys [Diag]: Current time: Sat Mar  2 18:04:11 2024",
+            "21.402 Sys [Info]: Logged in MockUser (5a1b2c3d4e5f60718293a4b5) Platform: PC",
+            "104.230 Script [Info]: Dialog.lua: Dialog::CreateOkCancel",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+        assert_eq!(
+            OnGameSessionEvent::get_last_state(&lines).unwrap(),
+            Some(GameState::LoggedIn)
+        );
+
+        let mut closed = lines.clone();
+        closed.push("5321.118 Sys [Info]: Main Shutdown Initiated.".to_string());
+        assert_eq!(
+            OnGameSessionEvent::get_last_state(&closed).unwrap(),
+            Some(GameState::Closed)
+        );
+        assert_eq!(OnGameSessionEvent::get_last_state(&lines[2..]).unwrap(), None);
+    }
+}
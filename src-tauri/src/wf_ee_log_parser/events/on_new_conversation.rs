@@ -1,9 +1,8 @@
-use std::{
-    path::PathBuf,
-    sync::{Arc, Mutex},
-};
+use std::sync::{Arc, Mutex};
 
 use crate::{error::AppError, handler::MonitorHandler, settings::SettingsState};
+
+use super::EELogEvent;
 use eyre::eyre;
 
 enum Events {
@@ -26,15 +25,30 @@ pub struct OnNewConversationEvent {
 }
 
 impl OnNewConversationEvent {
-    pub fn new(
-        settings: Arc<Mutex<SettingsState>>,
-        helper: Arc<Mutex<MonitorHandler>>,
-        _: PathBuf,
-    ) -> Self {
+    pub fn new(settings: Arc<Mutex<SettingsState>>, helper: Arc<Mutex<MonitorHandler>>) -> Self {
         Self { settings, helper }
     }
 
-    pub fn check(&self, _: usize, input: &str) -> Result<bool, AppError> {
+    /// Returns the name of the player that opened a new conversation, if the line is one.
+    pub fn get_username(input: &str) -> Result<Option<String>, AppError> {
+        let (found, captures) = crate::wf_ee_log_parser::events::helper::match_pattern(
+            input,
+            Events::Conversation.as_str_list(),
+        )
+        .map_err(|e| AppError::new("OnNewConversationEvent", eyre!(e)))?;
+        if !found {
+            return Ok(None);
+        }
+        Ok(captures.get(0).cloned().flatten())
+    }
+}
+
+impl EELogEvent for OnNewConversationEvent {
+    fn id(&self) -> &'static str {
+        "on_new_conversation"
+    }
+
+    fn check(&mut self, _: usize, input: &str) -> Result<bool, AppError> {
         let settings = self
             .settings
             .lock()?
@@ -46,13 +60,8 @@ impl OnNewConversationEvent {
         if !settings.system_notify && !settings.discord_notify {
             return Ok(false);
         }
-        let (found, captures) = crate::wf_ee_log_parser::events::helper::match_pattern(
-            input,
-            Events::Conversation.as_str_list(),
-        )
-        .map_err(|e| AppError::new("OnNewConversationEvent", eyre!(e)))?;
-        if found {
-            let username = captures.get(0).unwrap().clone().unwrap();
+        let username = Self::get_username(input)?;
+        if let Some(username) = username.clone() {
             let content = settings.content.replace("<PLAYER_NAME>", username.as_str());
             // If system notification is enabled, show it
             if settings.system_notify {
@@ -73,6 +82,6 @@ impl OnNewConversationEvent {
                 );
             }
         }
-        Ok(found)
+        Ok(username.is_some())
     }
 }
@@ -0,0 +1,256 @@
+use std::sync::{Arc, Mutex};
+
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    cache::client::CacheClient,
+    database::client::DBClient,
+    enums::OrderType,
+    error::AppError,
+    handler::MonitorHandler,
+    helper,
+    settings::SettingsState,
+    wf_ee_log_parser::events::helper::match_pattern,
+    wfm_client::client::WFMClient,
+};
+
+use super::EELogEvent;
+
+enum Events {
+    Whisper,
+    Offer,
+    Rank,
+}
+impl Events {
+    fn as_str_list(&self) -> Vec<String> {
+        match self {
+            Events::Whisper => vec![
+                r"(?<name>[\w\-\.]+): (?<message>(?i:Hi! I want to (?:buy|sell):|WT[BS]\b).+ for \d+ (?i:platinum|plat|p)\b.*)".to_string(),
+            ],
+            // The groups are the offer type, the item name and the price
+            Events::Offer => vec![
+                r#"(?i)I want to (buy|sell):\s*"(.+?)"\s+for\s+(\d+)\s+platinum"#.to_string(),
+                r"(?i)\bWT([BS])\b\s*:?\s*\[?(.+?)\]?\s+for\s+(\d+)\s*(?:platinum|plat|p)\b".to_string(),
+            ],
+            Events::Rank => vec![r"(?i)\s*\(rank (\d+)\)".to_string()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhisperOffer {
+    pub user_name: String,
+    // What the player wants to do, "buy" or "sell"
+    pub offer_type: String,
+    pub item_name: String,
+    pub rank: Option<i64>,
+    pub price: i64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WhisperMatch {
+    // The offer is for one of our orders at the listed price
+    Match,
+    // We have an order for the item, but at another price
+    PriceMismatch(i64),
+    NoOrder,
+    UnknownItem,
+}
+impl WhisperMatch {
+    pub fn as_str(&self) -> &str {
+        match self {
+            WhisperMatch::Match => "match",
+            WhisperMatch::PriceMismatch(_) => "price_mismatch",
+            WhisperMatch::NoOrder => "no_order",
+            WhisperMatch::UnknownItem => "unknown_item",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OnNewWhisperEvent {
+    settings: Arc<Mutex<SettingsState>>,
+    helper: Arc<Mutex<MonitorHandler>>,
+    cache: Arc<Mutex<CacheClient>>,
+    wfm: Arc<Mutex<WFMClient>>,
+    db: Arc<Mutex<DBClient>>,
+}
+
+impl OnNewWhisperEvent {
+    pub fn new(
+        settings: Arc<Mutex<SettingsState>>,
+        helper: Arc<Mutex<MonitorHandler>>,
+        cache: Arc<Mutex<CacheClient>>,
+        wfm: Arc<Mutex<WFMClient>>,
+        db: Arc<Mutex<DBClient>>,
+    ) -> Self {
+        Self {
+            settings,
+            helper,
+            cache,
+            wfm,
+            db,
+        }
+    }
+
+    /// Parses a whisper using the warframe.market "WTB/WTS ... for N platinum" templates.
+    pub fn parse_offer(user_name: &str, message: &str) -> Result<Option<WhisperOffer>, AppError> {
+        let (found, captures) = match_pattern(message, Events::Offer.as_str_list())
+            .map_err(|e| AppError::new("OnNewWhisperEvent", eyre!(e)))?;
+        if !found {
+            return Ok(None);
+        }
+        let kind = captures[0].clone().unwrap_or_default().to_lowercase();
+        let mut item_name = captures[1].clone().unwrap_or_default();
+        let price = captures[2].clone().unwrap_or_default().parse::<i64>().unwrap_or(0);
+
+        // Get the rank from names like "Primed Flow (rank 10)"
+        let (has_rank, rank) = match_pattern(&item_name, Events::Rank.as_str_list())
+            .map_err(|e| AppError::new("OnNewWhisperEvent", eyre!(e)))?;
+        let rank = if has_rank {
+            let rank = rank[0].clone().and_then(|r| r.parse::<i64>().ok());
+            let re = regex::Regex::new(&Events::Rank.as_str_list()[0])
+                .map_err(|e| AppError::new("OnNewWhisperEvent", eyre!(e)))?;
+            item_name = re.replace(&item_name, "").to_string();
+            rank
+        } else {
+            None
+        };
+
+        let offer_type = if kind == "buy" || kind == "b" {
+            "buy"
+        } else {
+            "sell"
+        };
+        Ok(Some(WhisperOffer {
+            user_name: user_name.to_string(),
+            offer_type: offer_type.to_string(),
+            item_name: item_name.trim().to_string(),
+            rank,
+            price,
+            message: message.to_string(),
+        }))
+    }
+
+    /// Finds the order of ours the offer is for, a player who wants to buy matches our sell orders.
+    async fn match_offer(
+        &self,
+        offer: &WhisperOffer,
+        url_name: Option<String>,
+    ) -> Result<(WhisperMatch, Option<String>), AppError> {
+        let url_name = match url_name {
+            Some(url_name) => url_name,
+            None => return Ok((WhisperMatch::UnknownItem, None)),
+        };
+        let wfm = self.wfm.lock()?.clone();
+        let orders = wfm.orders().get_my_orders().await?;
+        let (orders, order_type) = if offer.offer_type == "buy" {
+            (orders.sell_orders, OrderType::Sell)
+        } else {
+            (orders.buy_orders, OrderType::Buy)
+        };
+        let order = orders.iter().find(|order| {
+            order.order_type == order_type
+                && order.item.as_ref().map(|item| item.url_name.as_str()) == Some(url_name.as_str())
+                && (offer.rank.is_none() || order.mod_rank == offer.rank)
+        });
+        match order {
+            Some(order) if order.platinum == offer.price => {
+                Ok((WhisperMatch::Match, Some(order.id.clone())))
+            }
+            Some(order) => Ok((
+                WhisperMatch::PriceMismatch(order.platinum),
+                Some(order.id.clone()),
+            )),
+            None => Ok((WhisperMatch::NoOrder, None)),
+        }
+    }
+
+    fn notify(&self, offer: &WhisperOffer, whisper_match: &WhisperMatch) -> Result<(), AppError> {
+        let settings = self.settings.lock()?.clone().notifications.on_whisper_offer;
+        if !settings.system_notify && !settings.discord_notify {
+            return Ok(());
+        }
+        let match_text = match whisper_match {
+            WhisperMatch::Match => "Matches your listing".to_string(),
+            WhisperMatch::PriceMismatch(price) => format!("Your listing is {}p", price),
+            WhisperMatch::NoOrder => "No matching listing".to_string(),
+            WhisperMatch::UnknownItem => "Unknown item".to_string(),
+        };
+        let content = settings
+            .content
+            .replace("<PLAYER_NAME>", &offer.user_name)
+            .replace("<ORDER_TYPE>", &offer.offer_type)
+            .replace("<ITEM_NAME>", &offer.item_name)
+            .replace("<PRICE>", &offer.price.to_string())
+            .replace("<MATCH>", &match_text);
+        if settings.system_notify {
+            self.helper.lock()?.show_notification(
+                settings.title.as_str(),
+                &content,
+                Some("assets/icons/icon.png"),
+                Some("Default"),
+            );
+        }
+        if settings.discord_notify && settings.webhook.is_some() {
+            helper::send_message_to_discord(
+                settings.webhook.unwrap_or("".to_string()),
+                settings.title,
+                content,
+                settings.user_ids.clone(),
+            );
+        }
+        Ok(())
+    }
+
+    async fn on_offer(&self, offer: WhisperOffer) -> Result<(), AppError> {
+        let item = self.cache.lock()?.items().find_type_by_name(&offer.item_name)?;
+        let url_name = item.map(|item| item.url_name);
+        let (whisper_match, order_id) = self.match_offer(&offer, url_name.clone()).await?;
+        self.notify(&offer, &whisper_match)?;
+
+        // Store the whisper so the trade can be linked to it later
+        let db = self.db.lock()?.clone();
+        let whisper = db
+            .whisper()
+            .create(
+                &offer.user_name,
+                &offer.offer_type,
+                url_name,
+                &offer.item_name,
+                offer.rank,
+                offer.price,
+                order_id,
+                whisper_match.as_str(),
+                &offer.message,
+            )
+            .await?;
+        helper::send_message_to_window("Client:Whisper:Received", Some(json!(whisper)));
+        Ok(())
+    }
+}
+
+impl EELogEvent for OnNewWhisperEvent {
+    fn id(&self) -> &'static str {
+        "on_new_whisper"
+    }
+
+    fn check(&mut self, _: usize, input: &str) -> Result<bool, AppError> {
+        let (found, captures) = match_pattern(input, Events::Whisper.as_str_list())
+            .map_err(|e| AppError::new("OnNewWhisperEvent", eyre!(e)))?;
+        if !found {
+            return Ok(false);
+        }
+        let user_name = captures[0].clone().unwrap_or_default();
+        let message = captures[1].clone().unwrap_or_default();
+        let offer = match Self::parse_offer(&user_name, &message)? {
+            Some(offer) => offer,
+            None => return Ok(false),
+        };
+        tauri::async_runtime::block_on(self.on_offer(offer))?;
+        Ok(true)
+    }
+}
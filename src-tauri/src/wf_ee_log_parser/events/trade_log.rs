@@ -0,0 +1,422 @@
+use serde::{Deserialize, Serialize};
+
+use crate::structs::{Item, TradeClassification, WarframeLanguage};
+
+use super::on_new_trading::{PlayerTradeStruct, TradeItemStruct};
+
+#[derive(Debug, Clone)]
+pub struct TradeLogMessages {
+    pub detect_line: &'static str,
+    pub detect_trade_confirmation_line: &'static str,
+    pub detect_trade_failed_line: &'static str,
+    pub will_receive_line_first_part: &'static str,
+    pub will_receive_line_second_part: &'static str,
+    pub platinum_name: &'static str,
+}
+
+impl TradeLogMessages {
+    /// Returns the messages of the trade dialogs for a game language, if it is supported.
+    pub fn for_language(lang: &WarframeLanguage) -> Option<TradeLogMessages> {
+        match lang {
+            WarframeLanguage::English => Some(TradeLogMessages {
+                detect_line:
+                    "description=Are you sure you want to accept this trade? You are offering",
+                detect_trade_confirmation_line:
+                    "description=The trade was successful!, leftItem=/Menu/Confirm_Item_Ok",
+                detect_trade_failed_line:
+                    "description=The trade failed., leftItem=/Menu/Confirm_Item_Ok",
+                will_receive_line_first_part: "and will receive from ",
+                will_receive_line_second_part: " the following:",
+                platinum_name: "Platinum",
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// What a line of the log meant for the trade being read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TradeLogStep {
+    // The line is not part of a trade
+    Ignored,
+    // A trade dialog was opened, a dialog that was still waiting for confirmation is returned as cancelled
+    Started { cancelled: Option<Vec<String>> },
+    Continued,
+    Accepted(Vec<String>),
+    Failed(Vec<String>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeLogStatus {
+    Accepted,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeLogResult {
+    pub status: TradeLogStatus,
+    pub trade: PlayerTradeStruct,
+    #[serde(skip)]
+    pub logs: Vec<String>,
+}
+
+/// Collects the lines of the trade dialogs and follows them until the trade is confirmed or failed.
+#[derive(Debug, Clone)]
+pub struct TradeLogReader {
+    messages: TradeLogMessages,
+    current_trade_logs: Vec<String>,
+    getting_trade_message_multiline: bool,
+    waiting_for_trade_message_confirmation: bool,
+}
+
+impl TradeLogReader {
+    pub fn new(messages: TradeLogMessages) -> Self {
+        Self {
+            messages,
+            current_trade_logs: Vec::new(),
+            getting_trade_message_multiline: false,
+            waiting_for_trade_message_confirmation: false,
+        }
+    }
+
+    pub fn set_messages(&mut self, messages: TradeLogMessages) {
+        self.messages = messages;
+    }
+
+    pub fn messages(&self) -> &TradeLogMessages {
+        &self.messages
+    }
+
+    pub fn reset(&mut self) {
+        self.current_trade_logs = Vec::new();
+        self.getting_trade_message_multiline = false;
+        self.waiting_for_trade_message_confirmation = false;
+    }
+
+    pub fn push(&mut self, input: &str) -> TradeLogStep {
+        if self.getting_trade_message_multiline {
+            // The dialog ends at the next log entry
+            if input.contains("[Info]") || input.contains("[Error]") || input.contains("[Warning]")
+            {
+                self.getting_trade_message_multiline = false;
+                self.waiting_for_trade_message_confirmation = true;
+            } else {
+                self.current_trade_logs.push(input.to_string());
+                return TradeLogStep::Continued;
+            }
+        }
+
+        // Start of a Trade
+        if input.contains("[Info]: Dialog.lua: Dialog::CreateOkCancel(description=")
+            && input.contains(self.messages.detect_line)
+        {
+            let cancelled = if self.waiting_for_trade_message_confirmation {
+                Some(std::mem::take(&mut self.current_trade_logs))
+            } else {
+                None
+            };
+            self.reset();
+            self.current_trade_logs.push(input.to_string());
+            if input
+                .contains(", leftItem=/Menu/Confirm_Item_Ok, rightItem=/Menu/Confirm_Item_Cancel)")
+            {
+                self.waiting_for_trade_message_confirmation = true;
+            } else {
+                self.getting_trade_message_multiline = true;
+            }
+            return TradeLogStep::Started { cancelled };
+        }
+
+        // Waiting for trade confirmation / trade failed
+        if self.waiting_for_trade_message_confirmation
+            && input.contains("[Info]: Dialog.lua: Dialog::CreateOk(description=")
+        {
+            if input.contains(self.messages.detect_trade_confirmation_line) {
+                let logs = std::mem::take(&mut self.current_trade_logs);
+                self.reset();
+                return TradeLogStep::Accepted(logs);
+            } else if input.contains(self.messages.detect_trade_failed_line) {
+                let logs = std::mem::take(&mut self.current_trade_logs);
+                self.reset();
+                return TradeLogStep::Failed(logs);
+            }
+            return TradeLogStep::Continued;
+        }
+        TradeLogStep::Ignored
+    }
+}
+
+/// Builds the trade from the lines of its dialog.
+/// The items are looked up in `items` to find their warframe.market id.
+pub fn parse_trade(
+    logs: &[String],
+    messages: &TradeLogMessages,
+    items: &[Item],
+) -> PlayerTradeStruct {
+    let mut trade = PlayerTradeStruct {
+        crated_at: "".to_string(),
+        user_name: "".to_string(),
+        trade_type: TradeClassification::Unknown,
+        total_platinum: 0,
+        offerings: Vec::new(),
+        receiving: Vec::new(),
+        whisper_id: None,
+    };
+
+    // The first entry can hold the whole dialog
+    let mut lines: Vec<String> = vec![];
+    for (index, log) in logs.iter().enumerate() {
+        if index == 0 {
+            lines.extend(log.split('\n').map(|line| line.to_string()));
+        } else {
+            lines.push(log.clone());
+        }
+    }
+
+    let mut offering = true;
+    for log in lines.iter() {
+        if log == "\n" || log == "" || log.contains(messages.detect_line) {
+            continue;
+        }
+        // Find the user name
+        if log.contains(messages.will_receive_line_first_part)
+            && log.contains(messages.will_receive_line_second_part)
+        {
+            trade.user_name = log
+                .replace(messages.will_receive_line_first_part, "")
+                .replace(messages.will_receive_line_second_part, "")
+                .replace("\u{e000}", "")
+                .trim()
+                .to_string();
+            offering = false;
+            continue;
+        }
+
+        let mut line = log.clone();
+        if let Some(index) = line.find(", leftItem=/") {
+            line.truncate(index);
+        }
+        let line = line.replace("\r", "").replace("\n", "");
+
+        let (mut item_name, quantity) = match line.split_once(" x ") {
+            Some((name, quantity)) => (name.to_string(), quantity.trim().parse().unwrap_or(1)),
+            None => (line, 1),
+        };
+        item_name = item_name.trim().to_string();
+        if item_name == messages.platinum_name {
+            item_name = "plat".to_string();
+        }
+        if item_name == "" {
+            continue;
+        }
+
+        let items = if offering {
+            &mut trade.offerings
+        } else {
+            &mut trade.receiving
+        };
+        match items.iter_mut().find(|p| p.name == item_name) {
+            Some(traded_object) => traded_object.quantity += quantity,
+            None => items.push(TradeItemStruct {
+                wfm_id: None,
+                wfm_url_name: None,
+                name: item_name.clone(),
+                quantity,
+                display_name: item_name,
+                rank: 0,
+            }),
+        }
+    }
+
+    for item in trade.offerings.iter_mut().chain(trade.receiving.iter_mut()) {
+        if !resolve_item(item, items) {
+            item.display_name = item.name.clone();
+        }
+    }
+
+    // Get the total platinum amount
+    trade.total_platinum = trade
+        .offerings
+        .iter()
+        .chain(trade.receiving.iter())
+        .filter(|p| p.name == "plat")
+        .map(|p| p.quantity)
+        .sum::<i32>();
+
+    let receiving_plat = trade.receiving.iter().any(|p| p.name == "plat");
+    let offering_plat = trade.offerings.iter().any(|p| p.name == "plat");
+    trade.trade_type = if receiving_plat && trade.offerings.len() == 1 {
+        TradeClassification::Sale
+    } else if offering_plat && trade.receiving.len() == 1 {
+        TradeClassification::Purchase
+    } else {
+        TradeClassification::Trade
+    };
+    trade
+}
+
+/// Reads every trade of a log, trades still open at the end of the log are left out.
+pub fn parse_trade_log(
+    lines: &[&str],
+    messages: &TradeLogMessages,
+    items: &[Item],
+) -> Vec<TradeLogResult> {
+    let mut reader = TradeLogReader::new(messages.clone());
+    let mut results: Vec<TradeLogResult> = vec![];
+    let mut add = |status: TradeLogStatus, logs: Vec<String>| {
+        results.push(TradeLogResult {
+            status,
+            trade: parse_trade(&logs, messages, items),
+            logs,
+        })
+    };
+    for line in lines {
+        match reader.push(line) {
+            TradeLogStep::Started {
+                cancelled: Some(logs),
+            } => add(TradeLogStatus::Cancelled, logs),
+            TradeLogStep::Accepted(logs) => add(TradeLogStatus::Accepted, logs),
+            TradeLogStep::Failed(logs) => add(TradeLogStatus::Failed, logs),
+            _ => {}
+        }
+    }
+    results
+}
+
+/// Fills in the rank, the warframe.market id and the display name of a traded item.
+/// Returns false when nothing is known about the item.
+fn resolve_item(item: &mut TradeItemStruct, items: &[Item]) -> bool {
+    item.rank = -1;
+    if let (Some(start), true) = (item.name.rfind('('), item.name.ends_with(')')) {
+        let item_details = item.name[start..].to_string();
+        // The name is followed by one space
+        let name_part = item.name[..start.saturating_sub(1)].to_string();
+        if item_details.len() > 3 {
+            // Get the rank of the item
+            let item_rank = item_details.replace("(", "").replace(")", "");
+            for s in item_rank.split(' ') {
+                if let Ok(result) = s.parse::<i32>() {
+                    item.rank = result;
+                    break;
+                }
+            }
+            // Check if the item is a riven mod
+            if item_details.contains("(RIVEN RANK ") {
+                // Veiled rivens keep their generic name, e.g. "Rifle Riven Mod"
+                if name_part.ends_with(" Riven Mod") {
+                    item.display_name = name_part + " (Veiled)";
+                } else if let Some((weapon_name, mod_name)) = name_part.rsplit_once(' ') {
+                    item.name = format!("/AF_Special/Riven/{}/{}", weapon_name, mod_name);
+                    item.display_name = name_part.clone();
+                }
+                return true;
+            }
+            match items.iter().find(|p| p.item_name == name_part) {
+                Some(ch_item) => {
+                    item.wfm_id = Some(ch_item.id.clone());
+                    item.wfm_url_name = Some(ch_item.url_name.clone());
+                    item.display_name = ch_item.item_name.clone();
+                }
+                None => item.display_name = name_part,
+            }
+            return true;
+        }
+    }
+    // Arcanes end with a rank glyph
+    if item.name.chars().count() != item.name.len() {
+        let arcane_name_part = item.name[..item.name.rfind(' ').unwrap_or(0)].to_string();
+        if let Some(ch_item) = items.iter().find(|p| p.item_name == arcane_name_part) {
+            item.wfm_id = Some(ch_item.id.clone());
+            item.wfm_url_name = Some(ch_item.url_name.clone());
+        }
+        item.display_name = arcane_name_part;
+        return true;
+    }
+    // Items without a rank are listed by their plain name
+    match items.iter().find(|p| p.item_name == item.name) {
+        Some(ch_item) => {
+            item.wfm_id = Some(ch_item.id.clone());
+            item.wfm_url_name = Some(ch_item.url_name.clone());
+            item.display_name = ch_item.item_name.clone();
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn item(item_name: &str, url_name: &str) -> Item {
+        Item {
+            item_name: item_name.to_string(),
+            id: format!("id_{}", url_name),
+            url_name: url_name.to_string(),
+            thumb: "".to_string(),
+            wikia_url: None,
+            trade_tax: None,
+            mr_requirement: None,
+            set_items: None,
+            tags: None,
+            mod_max_rank: None,
+            subtypes: None,
+        }
+    }
+
+    fn get_items() -> Vec<Item> {
+        vec![
+            item("Ash Prime Systems Blueprint", "ash_prime_systems_blueprint"),
+            item("Ash Prime Chassis Blueprint", "ash_prime_chassis_blueprint"),
+            item("Ash Prime Neuroptics Blueprint", "ash_prime_neuroptics_blueprint"),
+            item("Primed Flow", "primed_flow"),
+            item("Arcane Energize", "arcane_energize"),
+            item("Forma Blueprint", "forma_blueprint"),
+        ]
+    }
+
+    /// Compares every fixture with its golden file, run with `UPDATE_GOLDEN=1` to rewrite them.
+    #[test]
+    fn trade_log_fixtures() {
+        let folder = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/trade_log");
+        let messages = TradeLogMessages::for_language(&WarframeLanguage::English).unwrap();
+        let items = get_items();
+        let mut checked = 0;
+        for entry in std::fs::read_dir(&folder).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().map_or(true, |ext| ext != "log") {
+                continue;
+            }
+            let content = std::fs::read_to_string(&path).unwrap();
+            let lines: Vec<&str> = content.lines().collect();
+            let results =
+                serde_json::to_value(parse_trade_log(&lines, &messages, &items)).unwrap();
+
+            let golden = path.with_extension("json");
+            if std::env::var("UPDATE_GOLDEN").is_ok() {
+                let json = serde_json::to_string_pretty(&results).unwrap() + "\n";
+                std::fs::write(&golden, json).unwrap();
+            }
+            let expected: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(&golden).unwrap()).unwrap();
+            assert_eq!(results, expected, "{}", path.display());
+            checked += 1;
+        }
+        assert!(checked > 0, "No fixtures found in {}", folder.display());
+    }
+
+    #[test]
+    fn reader_ignores_lines_outside_of_trades() {
+        let messages = TradeLogMessages::for_language(&WarframeLanguage::English).unwrap();
+        let mut reader = TradeLogReader::new(messages);
+        assert_eq!(
+            reader.push(" 10.000 Sys [Info]: Dialog.lua: Dialog::CreateOk(description=The trade was successful!, leftItem=/Menu/Confirm_Item_Ok)"),
+            TradeLogStep::Ignored
+        );
+        assert_eq!(reader.push("Platinum x 10"), TradeLogStep::Ignored);
+    }
+}
@@ -1,2 +1,4 @@
 pub mod client;
 pub mod events;
+pub mod modules;
+pub mod registry;
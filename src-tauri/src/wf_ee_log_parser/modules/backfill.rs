@@ -0,0 +1,266 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use chrono::{DateTime, Utc};
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    error::{self, AppError},
+    helper, logger,
+    wf_ee_log_parser::{
+        client::EELogParser,
+        events::{
+            helper as events_helper,
+            on_new_conversation::OnNewConversationEvent,
+            on_new_trading::{PlayerTradeStruct, TradeItemStruct},
+            EELogEvent,
+        },
+    },
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackfillTrade {
+    #[serde(flatten)]
+    pub trade: PlayerTradeStruct,
+    pub logs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackfillConversation {
+    pub user_name: String,
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackfillPreview {
+    pub path: String,
+    pub log_started_at: Option<String>,
+    pub trades: Vec<BackfillTrade>,
+    pub duplicates: Vec<BackfillTrade>,
+    pub conversations: Vec<BackfillConversation>,
+}
+
+pub struct BackfillModule<'a> {
+    pub client: &'a EELogParser,
+    pub debug_id: String,
+}
+
+impl<'a> BackfillModule<'a> {
+    /// Replays an existing EE.log and returns the trades that are not stored yet.
+    /// The preview is kept until it is committed or a new preview is made.
+    pub async fn preview(
+        &self,
+        path: Option<PathBuf>,
+        from_date: Option<String>,
+        to_date: Option<String>,
+    ) -> Result<BackfillPreview, AppError> {
+        let path = match path {
+            Some(path) => path,
+            None => self.client.get_ee_log_path()?,
+        };
+        let from_date = match from_date {
+            Some(date) => Some(events_helper::parse_date(&date, false)?),
+            None => None,
+        };
+        let to_date = match to_date {
+            Some(date) => Some(events_helper::parse_date(&date, true)?),
+            None => None,
+        };
+
+        let bytes = std::fs::read(&path).map_err(|e| {
+            AppError::new(
+                "EELogParser:Backfill",
+                eyre!(format!("Could not read {}: {}", path.display(), e)),
+            )
+        })?;
+        let content = String::from_utf8_lossy(&bytes);
+        let lines: Vec<&str> = content.lines().collect();
+
+        // Find out when the log was started, fall back to the last write of the file
+        let log_started_at = match lines
+            .iter()
+            .find_map(|line| events_helper::get_log_start_time(line))
+        {
+            Some(started_at) => Some(started_at),
+            None => {
+                let last_offset = lines
+                    .iter()
+                    .rev()
+                    .find_map(|line| events_helper::get_line_offset(line))
+                    .unwrap_or(0.0);
+                std::fs::metadata(&path)
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+                    .map(|modified| {
+                        DateTime::<Utc>::from(modified)
+                            - chrono::Duration::milliseconds((last_offset * 1000.0) as i64)
+                    })
+            }
+        };
+
+        let total_lines = lines.len();
+        let mut event_trading = self.client.replay_trading()?;
+        let mut conversations: Vec<BackfillConversation> = vec![];
+        let mut log_time: Option<DateTime<Utc>> = None;
+        for (index, line) in lines.into_iter().enumerate() {
+            if let (Some(started_at), Some(offset)) =
+                (log_started_at, events_helper::get_line_offset(line))
+            {
+                log_time =
+                    Some(started_at + chrono::Duration::milliseconds((offset * 1000.0) as i64));
+            }
+            event_trading.set_log_time(log_time);
+
+            // A single bad line should not stop the import
+            match OnNewConversationEvent::get_username(line) {
+                Ok(Some(user_name)) => {
+                    if is_in_range(log_time, from_date, to_date) {
+                        conversations.push(BackfillConversation {
+                            user_name,
+                            created_at: log_time.map(|time| time.to_rfc3339()),
+                        });
+                    }
+                    continue;
+                }
+                Ok(None) => {}
+                Err(e) => error::create_log_file(self.client.log_file.clone(), &e),
+            }
+            if let Err(e) = event_trading.check(index + 1, line) {
+                error::create_log_file(self.client.log_file.clone(), &e);
+            }
+            if index % 10000 == 0 {
+                helper::emit_progress(
+                    "ee_log_backfill",
+                    "ee_log.backfill.progress",
+                    Some(json!({ "current": index, "total": total_lines })),
+                    false,
+                );
+            }
+        }
+        helper::emit_progress("ee_log_backfill", "ee_log.backfill.progress", None, true);
+        let replayed_trades = event_trading.take_replayed_trades();
+
+        // Filter by date range and remove the trades we already know about
+        let mut stored = self.get_stored_trade_keys().await?;
+        let mut trades: Vec<BackfillTrade> = vec![];
+        let mut duplicates: Vec<BackfillTrade> = vec![];
+        for (trade, logs) in replayed_trades {
+            let crated_at = DateTime::parse_from_rfc3339(&trade.crated_at)
+                .ok()
+                .map(|date| date.with_timezone(&Utc));
+            if !is_in_range(crated_at, from_date, to_date) {
+                continue;
+            }
+            let key = get_trade_key(&trade);
+            let backfill_trade = BackfillTrade { trade, logs };
+            match stored.get_mut(&key) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    duplicates.push(backfill_trade);
+                }
+                _ => trades.push(backfill_trade),
+            }
+        }
+
+        let preview = BackfillPreview {
+            path: path.to_str().unwrap_or_default().to_string(),
+            log_started_at: log_started_at.map(|date| date.to_rfc3339()),
+            trades,
+            duplicates,
+            conversations,
+        };
+        logger::info_file(
+            "EELogParser:Backfill",
+            format!(
+                "Previewed {}: {} new trades, {} duplicates, {} conversations",
+                preview.path,
+                preview.trades.len(),
+                preview.duplicates.len(),
+                preview.conversations.len()
+            )
+            .as_str(),
+            Some(self.client.log_file.as_str()),
+        );
+        *self.client.backfill_preview.lock()? = Some(preview.clone());
+        Ok(preview)
+    }
+
+    /// Stores the trades of the last preview and returns how many were added.
+    pub async fn commit(&self) -> Result<usize, AppError> {
+        let preview = self.client.backfill_preview.lock()?.take();
+        let preview = match preview {
+            Some(preview) => preview,
+            None => {
+                return Err(AppError::new(
+                    "EELogParser:Backfill",
+                    eyre!("There is no backfill preview to commit"),
+                ))
+            }
+        };
+        let count = preview.trades.len();
+        let db = self.client.db.lock()?.clone();
+        for trade in preview.trades.iter() {
+            db.trade().create(&trade.trade, &trade.logs).await?;
+        }
+        logger::info_file(
+            "EELogParser:Backfill",
+            format!("Imported {} trades from {}", count, preview.path).as_str(),
+            Some(self.client.log_file.as_str()),
+        );
+        Ok(count)
+    }
+
+    /// Counts the stored trades by key.
+    async fn get_stored_trade_keys(&self) -> Result<HashMap<String, usize>, AppError> {
+        let db = self.client.db.lock()?.clone();
+        let mut keys: HashMap<String, usize> = HashMap::new();
+        for trade in db.trade().get_all().await? {
+            *keys
+                .entry(get_trade_key(&trade.to_player_trade()))
+                .or_insert(0) += 1;
+        }
+        Ok(keys)
+    }
+}
+
+fn is_in_range(
+    date: Option<DateTime<Utc>>,
+    from_date: Option<DateTime<Utc>>,
+    to_date: Option<DateTime<Utc>>,
+) -> bool {
+    if from_date.is_none() && to_date.is_none() {
+        return true;
+    }
+    match date {
+        Some(date) => {
+            from_date.map_or(true, |from| date >= from) && to_date.map_or(true, |to| date <= to)
+        }
+        None => false,
+    }
+}
+
+/// Builds a key that identifies a trade by its partner, items and the minute it happened.
+/// The minute absorbs the small gap between a live trade's clock time and its log time.
+fn get_trade_key(trade: &PlayerTradeStruct) -> String {
+    let items = |items: &Vec<TradeItemStruct>| {
+        let mut items: Vec<String> = items
+            .iter()
+            .map(|item| format!("{}x{}", item.name, item.quantity))
+            .collect();
+        items.sort();
+        items.join(",")
+    };
+    let minute = DateTime::parse_from_rfc3339(&trade.crated_at)
+        .map(|date| date.with_timezone(&Utc).format("%Y-%m-%dT%H:%M").to_string())
+        .unwrap_or_default();
+    format!(
+        "{}|{}|{:?}|{}|{}|{}",
+        minute,
+        trade.user_name,
+        trade.trade_type,
+        trade.total_platinum,
+        items(&trade.offerings),
+        items(&trade.receiving)
+    )
+}
@@ -0,0 +1,228 @@
+use std::{
+    collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex},
+};
+
+use eyre::eyre;
+
+use crate::{
+    error::{self, AppError},
+    settings::SettingsState,
+};
+
+use super::events::EELogEvent;
+
+#[derive(Debug)]
+pub struct EELogEventRegistry {
+    settings: Arc<Mutex<SettingsState>>,
+    events: Vec<Arc<Mutex<dyn EELogEvent>>>,
+    log_file: String,
+}
+
+impl EELogEventRegistry {
+    pub fn new(settings: Arc<Mutex<SettingsState>>, log_file: &str) -> Self {
+        Self {
+            settings,
+            events: Vec::new(),
+            log_file: log_file.to_string(),
+        }
+    }
+
+    pub fn register(&mut self, event: Arc<Mutex<dyn EELogEvent>>) {
+        self.events.push(event);
+    }
+
+    pub fn get_ids(&self) -> Vec<&'static str> {
+        self.events
+            .iter()
+            .map(|event| event.lock().unwrap_or_else(|e| e.into_inner()).id())
+            .collect()
+    }
+
+    /// Lets every enabled event rebuild its state from the lines already in the log.
+    pub fn restore(&self, lines: &[String]) {
+        let enabled = self.get_enabled();
+        for event in self.events.iter() {
+            let mut event = event.lock().unwrap_or_else(|e| e.into_inner());
+            let id = event.id();
+            if !enabled.get(id).cloned().unwrap_or(true) {
+                continue;
+            }
+            let result = panic::catch_unwind(AssertUnwindSafe(|| event.restore(lines)));
+            match result {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => error::create_log_file(self.log_file.clone(), &e),
+                Err(_) => error::create_log_file(
+                    self.log_file.clone(),
+                    &AppError::new(
+                        "EELogParser",
+                        eyre!(format!("Event {} panicked while restoring its state", id)),
+                    ),
+                ),
+            }
+        }
+    }
+
+    fn get_enabled(&self) -> HashMap<String, bool> {
+        match self.settings.lock() {
+            Ok(settings) => settings.ee_log.events.clone(),
+            Err(e) => e.into_inner().ee_log.events.clone(),
+        }
+    }
+
+    /// Passes the line to every enabled event until one of them handles it.
+    /// An event that fails or panics is logged and skipped, the others still get the line.
+    pub fn dispatch(&self, index: usize, input: &str) {
+        let enabled = self.get_enabled();
+        for event in self.events.iter() {
+            let mut event = event.lock().unwrap_or_else(|e| e.into_inner());
+            let id = event.id();
+            if !enabled.get(id).cloned().unwrap_or(true) {
+                continue;
+            }
+            let result = panic::catch_unwind(AssertUnwindSafe(|| event.check(index, input)));
+            match result {
+                Ok(Ok(true)) => break,
+                Ok(Ok(false)) => {}
+                Ok(Err(e)) => error::create_log_file(self.log_file.clone(), &e),
+                Err(_) => error::create_log_file(
+                    self.log_file.clone(),
+                    &AppError::new(
+                        "EELogParser",
+                        eyre!(format!("Event {} panicked on line {}", id, index)),
+                    ),
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use eyre::eyre;
+
+    use crate::{error::AppError, settings::SettingsState};
+
+    use super::{EELogEvent, EELogEventRegistry};
+
+    #[derive(Clone, Copy, Debug)]
+    enum Outcome {
+        Handled,
+        Ignored,
+        Failed,
+        Panicked,
+    }
+
+    #[derive(Debug)]
+    struct TestEvent {
+        id: &'static str,
+        outcome: Outcome,
+        seen: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    impl EELogEvent for TestEvent {
+        fn id(&self) -> &'static str {
+            self.id
+        }
+
+        fn check(&mut self, _: usize, _: &str) -> Result<bool, AppError> {
+            self.seen.lock().unwrap().push(self.id);
+            match self.outcome {
+                Outcome::Handled => Ok(true),
+                Outcome::Ignored => Ok(false),
+                Outcome::Failed => Err(AppError::new("TestEvent", eyre!("failed"))),
+                Outcome::Panicked => panic!("{} panicked", self.id),
+            }
+        }
+
+        fn restore(&mut self, _: &[String]) -> Result<(), AppError> {
+            self.seen.lock().unwrap().push(self.id);
+            Ok(())
+        }
+    }
+
+    fn registry(
+        events: &[(&'static str, Outcome)],
+        disabled: &[&str],
+    ) -> (EELogEventRegistry, Arc<Mutex<Vec<&'static str>>>) {
+        let mut settings = SettingsState::default();
+        for id in disabled {
+            settings.ee_log.events.insert(id.to_string(), false);
+        }
+        let mut registry =
+            EELogEventRegistry::new(Arc::new(Mutex::new(settings)), "ee_log_parser_test.log");
+        let seen = Arc::new(Mutex::new(vec![]));
+        for (id, outcome) in events {
+            registry.register(Arc::new(Mutex::new(TestEvent {
+                id,
+                outcome: *outcome,
+                seen: Arc::clone(&seen),
+            })));
+        }
+        (registry, seen)
+    }
+
+    #[test]
+    fn the_first_event_that_handles_a_line_wins() {
+        let (registry, seen) = registry(
+            &[
+                ("on_new_conversation", Outcome::Ignored),
+                ("on_new_whisper", Outcome::Handled),
+                ("on_trading", Outcome::Handled),
+            ],
+            &[],
+        );
+        registry.dispatch(1, "line");
+        assert_eq!(*seen.lock().unwrap(), vec!["on_new_conversation", "on_new_whisper"]);
+    }
+
+    #[test]
+    fn skips_disabled_events() {
+        let (registry, seen) = registry(
+            &[
+                ("on_new_conversation", Outcome::Handled),
+                ("on_trading", Outcome::Ignored),
+            ],
+            &["on_new_conversation"],
+        );
+        registry.dispatch(1, "line");
+        registry.restore(&["line".to_string()]);
+        assert_eq!(*seen.lock().unwrap(), vec!["on_trading", "on_trading"]);
+    }
+
+    #[test]
+    fn events_without_a_flag_are_enabled() {
+        let (registry, seen) = registry(&[("on_unknown", Outcome::Handled)], &[]);
+        registry.dispatch(1, "line");
+        assert_eq!(*seen.lock().unwrap(), vec!["on_unknown"]);
+    }
+
+    #[test]
+    fn a_failing_or_panicking_event_does_not_stop_the_others() {
+        let (registry, seen) = registry(
+            &[
+                ("on_new_conversation", Outcome::Panicked),
+                ("on_new_whisper", Outcome::Failed),
+                ("on_trading", Outcome::Handled),
+            ],
+            &[],
+        );
+        registry.dispatch(1, "line");
+        // The panicked event is still usable for the next line
+        registry.dispatch(2, "line");
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                "on_new_conversation",
+                "on_new_whisper",
+                "on_trading",
+                "on_new_conversation",
+                "on_new_whisper",
+                "on_trading"
+            ]
+        );
+    }
+}
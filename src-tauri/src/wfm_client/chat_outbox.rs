@@ -0,0 +1,280 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    error::{AppError, WfmError},
+    helper, logger,
+    request_scheduler::RequestPriority,
+};
+
+use super::{client::WFMClient, modules::chat::ChatMessage};
+
+// How often queued messages are retried
+const FLUSH_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutboxMessage {
+    // The temporary id the message is shown with until it is sent
+    pub id: String,
+    pub chat_id: String,
+    pub message: String,
+    pub created: String,
+    pub attempts: i64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SendStatus {
+    Sent { message: ChatMessage },
+    Queued { message: OutboxMessage },
+}
+
+/// Chat messages that could not be sent yet, kept on disk and retried in the background.
+#[derive(Clone)]
+pub struct ChatOutbox {
+    component: String,
+    log_file: String,
+    path: PathBuf,
+    is_running: Arc<AtomicBool>,
+    messages: Arc<Mutex<Vec<OutboxMessage>>>,
+    pub wfm: Arc<Mutex<WFMClient>>,
+}
+
+impl ChatOutbox {
+    pub fn new(wfm: Arc<Mutex<WFMClient>>) -> Self {
+        let path = helper::get_app_roaming_path().join("chat_outbox.json");
+        let outbox = ChatOutbox {
+            component: "ChatOutbox".to_string(),
+            log_file: "chat_outbox.log".to_string(),
+            path,
+            is_running: Arc::new(AtomicBool::new(false)),
+            messages: Arc::new(Mutex::new(vec![])),
+            wfm,
+        };
+        if let Err(e) = outbox.load() {
+            logger::warning(
+                &outbox.component,
+                format!("Could not load the outbox: {}", e.cause()).as_str(),
+                true,
+                Some(&outbox.log_file),
+            );
+        }
+        outbox
+    }
+
+    /// Keeps the queue in `path` instead, for tests.
+    #[cfg(test)]
+    pub fn with_path(mut self, path: PathBuf) -> Self {
+        self.path = path;
+        self.messages = Arc::new(Mutex::new(vec![]));
+        self
+    }
+
+    fn load(&self) -> Result<(), AppError> {
+        if !self.path.exists() {
+            return Ok(());
+        }
+        let mut content = String::new();
+        File::open(&self.path)
+            .and_then(|mut file| file.read_to_string(&mut content))
+            .map_err(|e| AppError::new(&self.component, eyre!(e.to_string())))?;
+        let messages: Vec<OutboxMessage> = serde_json::from_str(&content)
+            .map_err(|e| AppError::new(&self.component, eyre!(e.to_string())))?;
+        *self.messages.lock()? = messages;
+        Ok(())
+    }
+
+    fn save(&self) -> Result<(), AppError> {
+        let messages = self.messages.lock()?.clone();
+        let json = serde_json::to_string_pretty(&messages)
+            .map_err(|e| AppError::new(&self.component, eyre!(e.to_string())))?;
+        let mut file = File::create(&self.path)
+            .map_err(|e| AppError::new(&self.component, eyre!(e.to_string())))?;
+        file.write_all(json.as_bytes())
+            .map_err(|e| AppError::new(&self.component, eyre!(e.to_string())))?;
+        helper::emit_update("ChatOutbox", "SET", Some(json!(messages)));
+        Ok(())
+    }
+
+    pub fn get_messages(&self) -> Result<Vec<OutboxMessage>, AppError> {
+        Ok(self.messages.lock()?.clone())
+    }
+
+    /// Errors that may go away on their own, the message is kept and sent later.
+    fn is_retryable(error: &AppError) -> bool {
+        matches!(
+            error.wfm_error(),
+            Some(WfmError::Unreachable { .. })
+                | Some(WfmError::RateLimited { .. })
+                | Some(WfmError::ServerError { .. })
+        )
+    }
+
+    /// Sends a message right away, or queues it when warframe.market can not be reached.
+    pub async fn send(
+        &self,
+        id: &str,
+        chat_id: &str,
+        message: &str,
+    ) -> Result<SendStatus, AppError> {
+        let wfm = self.wfm.lock()?.clone();
+        match wfm.chat().send_message(chat_id, message).await {
+            Ok(message) => Ok(SendStatus::Sent { message }),
+            Err(e) if Self::is_retryable(&e) => {
+                let queued = OutboxMessage {
+                    id: id.to_string(),
+                    chat_id: chat_id.to_string(),
+                    message: message.to_string(),
+                    created: chrono::Utc::now().to_rfc3339(),
+                    attempts: 1,
+                    last_error: Some(e.cause()),
+                };
+                self.messages.lock()?.push(queued.clone());
+                self.save()?;
+                logger::info(
+                    &self.component,
+                    format!("Message to chat {} was queued: {}", chat_id, e.cause()).as_str(),
+                    true,
+                    Some(&self.log_file),
+                );
+                Ok(SendStatus::Queued { message: queued })
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn remove(&self, id: &str) -> Result<(), AppError> {
+        self.messages.lock()?.retain(|message| message.id != id);
+        self.save()
+    }
+
+    /// Tries to send every queued message once, in the order they were written.
+    pub async fn flush(&self) -> Result<(), AppError> {
+        let queued = self.messages.lock()?.clone();
+        if queued.is_empty() {
+            return Ok(());
+        }
+        // Queued messages wait while the session is expired instead of failing
+        let wfm = self.wfm.lock()?.with_priority(RequestPriority::Background);
+        for entry in queued {
+            let result = wfm.chat().send_message(&entry.chat_id, &entry.message).await;
+            let mut messages = self.messages.lock()?;
+            match result {
+                Ok(_) => messages.retain(|message| message.id != entry.id),
+                Err(e) if Self::is_retryable(&e) => {
+                    if let Some(message) = messages.iter_mut().find(|m| m.id == entry.id) {
+                        message.attempts += 1;
+                        message.last_error = Some(e.cause());
+                    }
+                    // Still offline, keep the order of the remaining messages
+                    drop(messages);
+                    break;
+                }
+                Err(e) => {
+                    logger::warning(
+                        &self.component,
+                        format!("Dropped message to chat {}: {}", entry.chat_id, e.cause())
+                            .as_str(),
+                        true,
+                        Some(&self.log_file),
+                    );
+                    messages.retain(|message| message.id != entry.id);
+                }
+            }
+        }
+        self.save()
+    }
+
+    pub fn start(&self) {
+        if self.is_running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let outbox = self.clone();
+        tauri::async_runtime::spawn(async move {
+            while outbox.is_running.load(Ordering::SeqCst) {
+                tokio::time::sleep(FLUSH_INTERVAL).await;
+                if let Err(e) = outbox.flush().await {
+                    logger::warning(
+                        &outbox.component,
+                        format!("Could not flush the outbox: {}", e.cause()).as_str(),
+                        true,
+                        Some(&outbox.log_file),
+                    );
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::{ChatOutbox, SendStatus};
+    use crate::wfm_client::{
+        client::WFMClient,
+        mock_server::{MockResponse, MockWfmServer},
+    };
+
+    fn outbox(server: &MockWfmServer, wfm: WFMClient) -> ChatOutbox {
+        let port = server.address.rsplit(':').next().unwrap_or_default();
+        ChatOutbox::new(Arc::new(Mutex::new(wfm)))
+            .with_path(std::env::temp_dir().join(format!("qf_mock_outbox_{}.json", port)))
+    }
+
+    #[tokio::test]
+    async fn queues_messages_while_warframe_market_is_down() {
+        let server = MockWfmServer::start().await;
+        let wfm = server.client();
+        let chat = wfm.chat().start_chat("MockTrader").await.unwrap();
+        let outbox = outbox(&server, wfm);
+        server.script(
+            "POST",
+            &format!("v1/im/chats/{}/messages", chat.id),
+            MockResponse::error(503, "request", "app.mock.unavailable"),
+        );
+
+        match outbox.send("temp_1", &chat.id, "hello").await.unwrap() {
+            SendStatus::Queued { message } => assert_eq!(message.attempts, 1),
+            SendStatus::Sent { .. } => panic!("The message should be queued"),
+        }
+        outbox.flush().await.unwrap();
+        assert!(outbox.get_messages().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn queues_messages_when_warframe_market_is_unreachable() {
+        let server = MockWfmServer::start().await;
+        let wfm = server.client();
+        let outbox = outbox(&server, wfm);
+        drop(server);
+
+        match outbox.send("temp_1", "chat_1", "hello").await.unwrap() {
+            SendStatus::Queued { .. } => {}
+            SendStatus::Sent { .. } => panic!("The message should be queued"),
+        }
+        assert_eq!(outbox.get_messages().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn does_not_queue_messages_warframe_market_rejects() {
+        let server = MockWfmServer::start().await;
+        let outbox = outbox(&server, server.client());
+
+        assert!(outbox.send("temp_1", "missing_chat", "hello").await.is_err());
+        assert!(outbox.get_messages().unwrap().is_empty());
+    }
+}
@@ -1,5 +1,7 @@
 use std::{
     collections::HashMap,
+    future::Future,
+    pin::Pin,
     sync::{Arc, Mutex},
     time::Duration,
 };
@@ -15,23 +17,37 @@ use serde_json::{json, Value};
 
 use crate::{
     auth::AuthState,
+    credentials::CredentialStore,
     enums::LogLevel,
-    error::{ApiResult, AppError, ErrorApiResponse},
+    error::{ApiResult, AppError, ErrorApiResponse, WfmError},
     helper,
+    http_recorder::HttpRecorder,
     logger::{self},
-    rate_limiter::RateLimiter,
+    request_scheduler::{RequestPriority, RequestScheduler, SchedulerStatus},
+    retry_policy::RetryPolicy,
 };
 
-use super::modules::{
-    auction::AuctionModule, auth::AuthModule, chat::ChatModule, item::ItemModule,
-    order::OrderModule,
+use super::{
+    modules::{
+        auction::AuctionModule, auth::AuthModule, chat::ChatModule, item::ItemModule,
+        order::OrderModule, profile::ProfileModule,
+    },
+    response_cache::ResponseCache,
+    session::SessionState,
 };
 
 #[derive(Clone, Debug)]
 pub struct WFMClient {
-    endpoint: String,
     component: String,
-    limiter: Arc<tokio::sync::Mutex<RateLimiter>>,
+    scheduler: Arc<RequestScheduler>,
+    priority: RequestPriority,
+    cache: Arc<Mutex<ResponseCache>>,
+    retry: RetryPolicy,
+    session: Arc<SessionState>,
+    // How long background requests wait for the user to log in again
+    session_wait: Duration,
+    credentials: CredentialStore,
+    recorder: Arc<HttpRecorder>,
     pub log_file: String,
     pub auth: Arc<Mutex<AuthState>>,
     pub settings: Arc<Mutex<crate::settings::SettingsState>>,
@@ -42,19 +58,152 @@ impl WFMClient {
         auth: Arc<Mutex<AuthState>>,
         settings: Arc<Mutex<crate::settings::SettingsState>>,
     ) -> Self {
+        let requests = settings.lock().unwrap().requests.clone();
         WFMClient {
-            endpoint: "https://api.warframe.market/v1/".to_string(),
             component: "WarframeMarket".to_string(),
-            limiter: Arc::new(tokio::sync::Mutex::new(RateLimiter::new(
-                1.0,
-                Duration::new(1, 0),
-            ))),
+            scheduler: Arc::new(RequestScheduler::new(requests.rate, requests.burst)),
+            priority: RequestPriority::Interactive,
+            cache: Arc::new(Mutex::new(ResponseCache::default())),
+            retry: RetryPolicy::default(),
+            session: Arc::new(SessionState::default()),
+            session_wait: Duration::from_secs(15 * 60),
+            credentials: CredentialStore::default(),
+            recorder: Arc::new(HttpRecorder::default()),
             log_file: "wfmAPICalls.log".to_string(),
             auth,
             settings,
         }
     }
 
+    /// A client sharing the same scheduler whose requests are queued with `priority`.
+    /// Requests that change orders or auctions are always queued as mutations,
+    /// unless the client is interactive.
+    pub fn with_priority(&self, priority: RequestPriority) -> Self {
+        let mut client = self.clone();
+        client.priority = priority;
+        client
+    }
+
+    pub fn get_scheduler_status(&self) -> SchedulerStatus {
+        self.scheduler.get_status()
+    }
+
+    /// Applies the request rate and burst from the settings to the shared scheduler.
+    pub fn set_request_limits(&self, rate: f64, burst: f64) {
+        self.scheduler.set_limits(rate, burst);
+    }
+
+    /// A client that keeps the credentials used to log in again in `store`.
+    pub fn with_credentials(&self, store: CredentialStore) -> Self {
+        let mut client = self.clone();
+        client.credentials = store;
+        client
+    }
+
+    /// The recorder shared by all clones of this client and the price scraper.
+    pub fn recorder(&self) -> Arc<HttpRecorder> {
+        Arc::clone(&self.recorder)
+    }
+
+    pub fn credentials(&self) -> &CredentialStore {
+        &self.credentials
+    }
+
+    pub fn is_session_expired(&self) -> bool {
+        self.session.is_expired()
+    }
+
+    /// Waits until the user is logged in again if the session expired.
+    /// Gives up with a warning when that does not happen in time.
+    pub async fn wait_for_session(&self) -> Result<(), AppError> {
+        if self.session.wait_until_valid(self.session_wait).await {
+            return Ok(());
+        }
+        Err(AppError::new_with_level(
+            self.component.as_str(),
+            eyre!(
+                "The session expired and was not renewed within {} minutes",
+                self.session_wait.as_secs() / 60
+            ),
+            LogLevel::Warning,
+        ))
+    }
+
+    /// Lets the paused background requests go on, called once the user is logged in again.
+    pub fn resume_session(&self) {
+        self.session.resume();
+    }
+
+    /// Called when warframe.market no longer accepts `token`.
+    /// Logs in again with the stored credentials, returns true if the session is valid again.
+    async fn on_unauthorized(&self, token: Option<String>) -> Result<bool, AppError> {
+        let _relogin = self.session.lock_relogin().await;
+
+        // Another request may have logged in again while this one was waiting
+        let current = self.auth.lock()?.access_token.clone();
+        if !self.session.is_expired() && current.is_some() && current != token {
+            return Ok(true);
+        }
+        if self.session.expire() {
+            logger::warning(
+                self.component.as_str(),
+                "The session expired, a re-login is required",
+                true,
+                Some(&self.log_file),
+            );
+        }
+
+        let credentials = match self.credentials.load() {
+            Ok(Some(credentials)) => credentials,
+            Ok(None) => return Ok(false),
+            Err(e) => {
+                logger::warning(
+                    self.component.as_str(),
+                    format!("Could not load the stored credentials: {}", e.cause()).as_str(),
+                    true,
+                    Some(&self.log_file),
+                );
+                return Ok(false);
+            }
+        };
+        // Boxed since logging in sends a request itself
+        let login: Pin<Box<dyn Future<Output = Result<AuthState, AppError>> + Send + '_>> =
+            Box::pin(self.auth().login(credentials.email, credentials.password));
+        match login.await {
+            Ok(user) if user.access_token.is_some() => {
+                // Scoped so the guard is never held across an await
+                {
+                    let mut auth = self.auth.lock()?;
+                    auth.update(user);
+                    // Tests must not overwrite the user's auth.json
+                    let saved = if cfg!(test) { Ok(()) } else { auth.save_to_file() };
+                    if let Err(e) = saved {
+                        logger::warning(
+                            self.component.as_str(),
+                            format!("Could not save the session: {}", e.cause()).as_str(),
+                            true,
+                            Some(&self.log_file),
+                        );
+                    }
+                    auth.send_to_window();
+                }
+                logger::info_con(self.component.as_str(), "Logged in again with the stored credentials");
+                self.session.resume();
+                Ok(true)
+            }
+            Ok(_) => Ok(false),
+            Err(e) => {
+                logger::warning(
+                    self.component.as_str(),
+                    format!("Could not log in again: {}", e.cause()).as_str(),
+                    true,
+                    Some(&self.log_file),
+                );
+                Ok(false)
+            }
+        }
+    }
+
     pub fn debug(&self, id: &str, component: &str, msg: &str, file: Option<bool>) {
         let settings = self.settings.lock().unwrap().clone();
         if !settings.debug.contains(&"*".to_owned()) && !settings.debug.contains(&id.to_owned()) {
@@ -85,6 +234,12 @@ impl WFMClient {
         eyre_report: eyre::ErrReport,
         level: LogLevel,
     ) -> AppError {
+        // Transient failures are logged as warnings so background loops keep running
+        let level = if err.error == "TransientError" {
+            LogLevel::Warning
+        } else {
+            level
+        };
         return AppError::new_api(
             format!("{}:{}", self.component, component).as_str(),
             err,
@@ -93,6 +248,19 @@ impl WFMClient {
         );
     }
 
+    fn get_payload(response: &Value, payload_key: Option<&str>) -> Value {
+        match payload_key {
+            Some(payload_key) => response["payload"][payload_key].clone(),
+            None => response["payload"].clone(),
+        }
+    }
+
+    /// Drops the cached responses of a route like "GET profile/{}/orders".
+    pub fn invalidate_cache(&self, route: &str) -> Result<(), AppError> {
+        self.cache.lock()?.invalidate_route(route);
+        Ok(())
+    }
+
     async fn send_request<T: DeserializeOwned>(
         &self,
         method: Method,
@@ -100,37 +268,77 @@ impl WFMClient {
         payload_key: Option<&str>,
         body: Option<Value>,
     ) -> Result<ApiResult<T>, AppError> {
-        let auth = self.auth.lock()?.clone();
-        let mut rate_limiter = self.limiter.lock().await;
+        let is_auth = url.trim_start_matches('/').starts_with("auth/");
+        let interactive = self.priority == RequestPriority::Interactive;
+
+        // Background work is paused while the user has to log in again
+        if !interactive && !is_auth {
+            self.wait_for_session().await?;
+        }
 
-        rate_limiter.wait_for_token().await;
+        let token = self.auth.lock()?.access_token.clone();
+        let result = self
+            .send_once(method.clone(), url, payload_key, body.clone())
+            .await;
+        let unauthorized = matches!(&result, Ok(ApiResult::Error(e, _)) if e.status_code == 401);
+        if !unauthorized || is_auth || token.is_none() {
+            return result;
+        }
 
-        let packageinfo = crate::PACKAGEINFO
+        if self.on_unauthorized(token).await? {
+            return self.send_once(method, url, payload_key, body).await;
+        }
+        if !interactive {
+            self.wait_for_session().await?;
+            return self.send_once(method, url, payload_key, body).await;
+        }
+        result
+    }
+
+    async fn send_once<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        url: &str,
+        payload_key: Option<&str>,
+        body: Option<Value>,
+    ) -> Result<ApiResult<T>, AppError> {
+        let auth = self.auth.lock()?.clone();
+
+        // The package info is only set when running inside tauri
+        let version = crate::PACKAGEINFO
             .lock()
             .unwrap()
             .clone()
-            .expect("Could not get package info");
+            .map(|info| info.version.to_string())
+            .unwrap_or("dev".to_string());
 
         let client = Client::new();
-        let new_url = format!("{}{}", self.endpoint, url);
-        let request = client
-            .request(method.clone(), Url::parse(&new_url).unwrap())
-            .header(
-                "Authorization",
-                format!("JWT {}", auth.access_token.unwrap_or("".to_string())),
-            )
-            .header(
-                "User-Agent",
-                format!("Quantframe {}", packageinfo.version.to_string()),
-            )
-            .header("Language", auth.region);
+        let new_url = format!(
+            "{}{}",
+            self.settings.lock()?.endpoints.get_wfm_api(),
+            url.trim_start_matches('/')
+        );
 
-        let request = match body.clone() {
-            Some(content) => request.json(&content),
-            None => request,
+        let priority = match self.priority {
+            RequestPriority::Interactive => RequestPriority::Interactive,
+            _ if method != Method::GET => RequestPriority::Mutation,
+            priority => priority,
         };
+        let endpoint = RequestScheduler::endpoint_key(method.as_str(), url);
 
-        let response = request.send().await;
+        // Serve GET requests from the cache while they are fresh,
+        // a session records or replays every request instead
+        if method == Method::GET
+            && !self.recorder.is_active()
+            && ResponseCache::serves(&endpoint, self.priority)
+        {
+            let cached = self.cache.lock()?.get(url);
+            if let Some((response, headers)) = cached {
+                if let Ok(payload) = serde_json::from_value(Self::get_payload(&response, payload_key)) {
+                    return Ok(ApiResult::Success(payload, headers));
+                }
+            }
+        }
 
         // Create default error response
         let mut error_def = ErrorApiResponse {
@@ -141,25 +349,152 @@ impl WFMClient {
             body: body.clone(),
             url: Some(new_url.clone()),
             method: Some(method.to_string()),
+            errors: vec![],
         };
 
-        if let Err(e) = response {
-            error_def.messages.push(e.to_string());
-            return Err(AppError::new_api(
+        // While replaying a session the recorded response is used and nothing is sent
+        let replayed = self
+            .recorder
+            .replay("wfm", method.as_str(), url, body.as_ref())?;
+
+        let request_url = Url::parse(&new_url).map_err(|e| {
+            AppError::new_api(
                 "WarframeMarket",
-                error_def,
-                eyre!(format!("There was an error sending the request: {}", e)),
+                error_def.clone(),
+                eyre!(format!("Invalid request url {}: {}", new_url, e)),
                 LogLevel::Critical,
-            ));
+            )
+        })?;
+
+        let mut attempt = 0;
+        let (status, headers, content) = match replayed {
+            Some(exchange) => (exchange.status(), exchange.headers(), exchange.response),
+            None => {
+                // Timeouts, 429 and 5xx are retried for requests that are safe to send again
+                let send = || async {
+                    self.scheduler.acquire(priority, &endpoint).await;
+
+                    let request = client
+                        .request(method.clone(), request_url.clone())
+                        .timeout(self.retry.timeout)
+                        .header(
+                            "Authorization",
+                            format!(
+                                "JWT {}",
+                                auth.access_token.clone().unwrap_or("".to_string())
+                            ),
+                        )
+                        .header("User-Agent", format!("Quantframe {}", version))
+                        .header("Language", auth.region.clone());
+
+                    let request = match body.clone() {
+                        Some(content) => request.json(&content),
+                        None => request,
+                    };
+
+                    let response = request.send().await;
+                    self.scheduler.report(
+                        &endpoint,
+                        response.as_ref().ok().map(|r| r.status().as_u16()),
+                    );
+                    response
+                };
+                let on_retry = |cause: String, delay: Duration, attempt: u32| {
+                    logger::warning(
+                        self.component.as_str(),
+                        format!(
+                            "{} {} failed with {}, retrying in {:.1}s ({}/{})",
+                            method,
+                            url,
+                            cause,
+                            delay.as_secs_f64(),
+                            attempt,
+                            self.retry.max_retries
+                        )
+                        .as_str(),
+                        true,
+                        Some(&self.log_file),
+                    );
+                };
+                let (response, retries) = self.retry.send(&method, &new_url, send, on_retry).await;
+                attempt = retries;
+
+                if let Err(e) = response {
+                    error_def.messages.push(e.to_string());
+                    if RetryPolicy::is_transient_error(&e) {
+                        error_def.errors.push(WfmError::Unreachable {
+                            message: e.to_string(),
+                        });
+                    }
+                    let transient_left = attempt < self.retry.max_retries;
+                    let level = if RetryPolicy::is_transient_error(&e) && transient_left {
+                        error_def.error = "TransientError".to_string();
+                        LogLevel::Warning
+                    } else {
+                        LogLevel::Critical
+                    };
+                    return Err(AppError::new_api(
+                        "WarframeMarket",
+                        error_def,
+                        eyre!(format!("There was an error sending the request: {}", e)),
+                        level,
+                    ));
+                }
+
+                // Get the response data from the response
+                let response_data = response.unwrap();
+                let status = response_data.status();
+                let headers = response_data.headers().clone();
+                let content = response_data.text().await.unwrap_or_default();
+                self.recorder.record(
+                    "wfm",
+                    method.as_str(),
+                    url,
+                    body.as_ref(),
+                    status,
+                    &headers,
+                    &content,
+                );
+                (status, headers, content)
+            }
+        };
+
+        // Our own changes make the cached orders and auctions stale
+        if method != Method::GET {
+            self.cache.lock()?.invalidate_after(url);
         }
 
-        // Get the response data from the response
-        let response_data = response.unwrap();
-        error_def.status_code = response_data.status().as_u16() as i64;
-        let headers = response_data.headers().clone();
-        let content = response_data.text().await.unwrap_or_default();
+        // Attempts are only left when the request could not be retried,
+        // a transient failure then is a warning and the caller can send it again later
+        let transient_left = attempt < self.retry.max_retries;
+
+        error_def.status_code = status.as_u16() as i64;
         error_def.raw_response = Some(content.clone());
 
+        // Too many requests or a server error that is still there after the retries
+        if RetryPolicy::is_transient_status(status) {
+            error_def.error = if transient_left {
+                "TransientError".to_string()
+            } else {
+                "ApiError".to_string()
+            };
+            error_def.messages.push(format!("request: {}", status));
+            error_def
+                .errors
+                .push(WfmError::from_status(error_def.status_code, &headers));
+            return Ok(ApiResult::Error(error_def, headers));
+        }
+
+        // The token expired or was revoked, send_request decides what to do with it
+        if status == StatusCode::UNAUTHORIZED && !url.trim_start_matches('/').starts_with("auth/") {
+            error_def.error = "Unauthorized".to_string();
+            error_def.messages.push(format!("request: {}", status));
+            error_def
+                .errors
+                .push(WfmError::from_status(error_def.status_code, &headers));
+            return Ok(ApiResult::Error(error_def, headers));
+        }
+
         // Convert the response to a Value object
         let response: Value = serde_json::from_str(content.as_str()).map_err(|e| {
             error_def.messages.push(e.to_string());
@@ -198,10 +533,21 @@ impl WFMClient {
                                 LogLevel::Critical,
                             )
                         })?;
+                    for message in messages.iter() {
+                        error_def.errors.push(WfmError::parse(
+                            error_def.status_code,
+                            &key,
+                            message,
+                        ));
+                    }
                     error_def
                         .messages
                         .push(format!("{}: {}", key, messages.join(", ")));
                 } else {
+                    let message = value.as_str().map(|s| s.to_string()).unwrap_or(value.to_string());
+                    error_def
+                        .errors
+                        .push(WfmError::parse(error_def.status_code, &key, &message));
                     error_def.messages.push(format!("{}: {:?}", key, value));
                 }
             }
@@ -209,14 +555,18 @@ impl WFMClient {
         }
 
         // Get the payload from the response if it exists
-        let mut data = response["payload"].clone();
-        if let Some(payload_key) = payload_key {
-            data = response["payload"][payload_key].clone();
-        }
+        let data = Self::get_payload(&response, payload_key);
 
         // Convert the response to a T object
-        match serde_json::from_value(data.clone()) {
-            Ok(payload) => Ok(ApiResult::Success(payload, headers)),
+        match serde_json::from_value(data) {
+            Ok(payload) => {
+                if method == Method::GET {
+                    self.cache
+                        .lock()?
+                        .insert(url, &endpoint, response, headers.clone());
+                }
+                Ok(ApiResult::Success(payload, headers))
+            }
             Err(e) => {
                 error_def.messages.push(e.to_string());
                 error_def.error = "ParseError".to_string();
@@ -308,4 +658,147 @@ impl WFMClient {
             debug_id: "wfm_client_chat".to_string(),
         }
     }
+
+    pub fn profile(&self) -> ProfileModule {
+        ProfileModule {
+            client: self,
+            debug_id: "wfm_client_profile".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        credentials::{CredentialStore, Credentials},
+        enums::LogLevel,
+        request_scheduler::RequestPriority,
+        wfm_client::mock_server::{order_json, MockResponse, MockWfmServer},
+    };
+
+    #[tokio::test]
+    async fn retries_transient_failures() {
+        let server = MockWfmServer::start().await;
+        server.script(
+            "GET",
+            "v1/profile/MockUser/orders",
+            MockResponse::error(429, "request", "app.mock.too_many_requests")
+                .with_header("Retry-After", "0"),
+        );
+        server.script(
+            "GET",
+            "v1/profile/MockUser/orders",
+            MockResponse::error(503, "request", "app.mock.unavailable"),
+        );
+        let wfm = server.client();
+
+        assert!(wfm.orders().get_my_orders().await.is_ok());
+        assert_eq!(server.requests().len(), 3);
+
+        // Creating an order is not idempotent, the failure is left to the caller as a warning
+        server.script(
+            "POST",
+            "v1/profile/orders",
+            MockResponse::error(503, "request", "app.mock.unavailable"),
+        );
+        let error = wfm
+            .orders()
+            .create("item_1", "sell", 100, 1, true, None)
+            .await
+            .unwrap_err();
+        assert_eq!(error.log_level(), LogLevel::Warning);
+        assert_eq!(server.requests().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn the_scraper_reads_order_books_fresh() {
+        let server = MockWfmServer::start().await;
+        server.set_order_book(
+            "primed_flow",
+            vec![order_json("a", "primed_flow", "sell", 40, 1, Some(0), "SellerA", "ingame")],
+        );
+        let wfm = server.client();
+        let scraper = wfm.with_priority(RequestPriority::Scraping);
+        let order_book_reads = || {
+            server
+                .requests()
+                .iter()
+                .filter(|request| request.path == "v1/items/primed_flow/orders")
+                .count()
+        };
+
+        wfm.orders().get_ordres_by_item("primed_flow").await.unwrap();
+        wfm.orders().get_ordres_by_item("primed_flow").await.unwrap();
+        assert_eq!(order_book_reads(), 1);
+        scraper.orders().get_ordres_by_item("primed_flow").await.unwrap();
+        scraper.orders().get_ordres_by_item("primed_flow").await.unwrap();
+        assert_eq!(order_book_reads(), 3);
+    }
+
+    #[tokio::test]
+    async fn background_requests_wait_for_the_session() {
+        let server = MockWfmServer::start().await;
+        let wfm = server.client();
+        let background = wfm.with_priority(RequestPriority::Background);
+        wfm.session.expire();
+
+        assert!(wfm.orders().get_my_orders().await.is_ok());
+        let pending =
+            tokio::spawn(async move { background.orders().get_my_orders().await.is_ok() });
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        assert!(!pending.is_finished());
+        wfm.resume_session();
+        assert!(pending.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn background_requests_stop_waiting_for_the_session() {
+        let server = MockWfmServer::start().await;
+        let mut background = server.client().with_priority(RequestPriority::Background);
+        background.session_wait = std::time::Duration::from_millis(100);
+        background.session.expire();
+
+        let error = background.orders().get_my_orders().await.unwrap_err();
+        assert_eq!(error.log_level(), LogLevel::Warning);
+        assert!(error.cause().contains("was not renewed"));
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn logs_in_again_when_the_session_expires() {
+        let server = MockWfmServer::start().await;
+        let port = server.address.rsplit(':').next().unwrap_or_default().to_string();
+        let store = CredentialStore::File(
+            std::env::temp_dir().join(format!("quantframe_credentials_{}.json", port)),
+        );
+        store
+            .save(&Credentials {
+                email: "mock@warframe.market".to_string(),
+                password: "password".to_string(),
+            })
+            .unwrap();
+        server.script(
+            "GET",
+            "v1/profile/MockUser/orders",
+            MockResponse::error(401, "request", "app.account.unauthorized"),
+        );
+        let wfm = server
+            .client()
+            .with_credentials(store.clone())
+            .with_priority(RequestPriority::Scraping);
+        wfm.auth.lock().unwrap().access_token = Some("expired-token".to_string());
+
+        assert!(wfm.orders().get_my_orders().await.is_ok());
+        assert_eq!(
+            wfm.auth.lock().unwrap().access_token.as_deref(),
+            Some("mock-token")
+        );
+        assert!(!wfm.is_session_expired());
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            paths,
+            vec!["v1/profile/MockUser/orders", "v1/auth/signin", "v1/profile/MockUser/orders"]
+        );
+        store.clear().unwrap();
+    }
 }
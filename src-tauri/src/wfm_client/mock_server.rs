@@ -0,0 +1,709 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+use crate::{
+    auth::AuthState,
+    cache::client::CacheClient,
+    database::client::DBClient,
+    settings::SettingsState,
+    structs::Item,
+    wfm_client::client::WFMClient,
+};
+
+/// A request received by the mock server.
+#[derive(Clone, Debug)]
+pub struct MockRequest {
+    pub method: String,
+    // Path without the api prefix and the query, like "profile/orders"
+    pub path: String,
+    pub query: String,
+    pub body: Option<Value>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MockResponse {
+    pub status: u16,
+    pub body: Value,
+    pub headers: Vec<(String, String)>,
+}
+
+impl MockResponse {
+    pub fn payload(payload: Value) -> Self {
+        Self::new(200, json!({ "payload": payload }))
+    }
+
+    /// An api error, like `MockResponse::error(400, "order_id", "app.form.not_exist")`.
+    pub fn error(status: u16, field: &str, message: &str) -> Self {
+        Self::new(status, json!({ "error": { field: [message] } }))
+    }
+
+    pub fn new(status: u16, body: Value) -> Self {
+        Self {
+            status,
+            body,
+            headers: vec![],
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// The data served by the mock, tests change it to script the market.
+#[derive(Debug, Default)]
+pub struct MockState {
+    pub user: Value,
+    pub items: Vec<Value>,
+    pub item_details: HashMap<String, Value>,
+    // Orders of other players by item url name
+    pub order_book: HashMap<String, Vec<Value>>,
+    pub my_orders: Vec<Value>,
+    pub auctions: Vec<Value>,
+    pub auction_search: Vec<Value>,
+    pub riven_items: Vec<Value>,
+    pub riven_attributes: Vec<Value>,
+    pub chats: Vec<Value>,
+    pub chat_messages: HashMap<String, Vec<Value>>,
+    // Profiles and reviews of other players by ingame name
+    pub profiles: HashMap<String, Value>,
+    pub reviews: HashMap<String, Vec<Value>>,
+    // relics.run price history by day
+    pub price_history: HashMap<String, Value>,
+    // Responses returned once for a method and path, before the built in routes
+    pub scripted: Vec<(String, String, VecDeque<MockResponse>)>,
+    pub requests: Vec<MockRequest>,
+    next_id: u64,
+}
+
+impl MockState {
+    fn next_id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{}{:020}", prefix, self.next_id)
+    }
+}
+
+/// A local warframe.market and relics.run for tests.
+/// The api is served under `/v1/` and the price history under `/history/`.
+pub struct MockWfmServer {
+    pub address: String,
+    pub state: Arc<Mutex<MockState>>,
+    handle: JoinHandle<()>,
+}
+
+impl Drop for MockWfmServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+impl MockWfmServer {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Could not bind the mock server");
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(MockState {
+            user: json!({
+                "banned": false,
+                "id": "mock_user_id",
+                "avatar": null,
+                "ingame_name": "MockUser",
+                "locale": "en",
+                "platform": "pc",
+                "region": "en",
+                "role": "user",
+                "status": "online",
+            }),
+            ..Default::default()
+        }));
+        let server_state = Arc::clone(&state);
+        let handle = tokio::spawn(async move {
+            loop {
+                let (stream, _) = match listener.accept().await {
+                    Ok(connection) => connection,
+                    Err(_) => break,
+                };
+                let state = Arc::clone(&server_state);
+                tokio::spawn(async move {
+                    let _ = handle_connection(stream, state).await;
+                });
+            }
+        });
+        Self {
+            address,
+            state,
+            handle,
+        }
+    }
+
+    pub fn wfm_api(&self) -> String {
+        format!("{}/v1/", self.address)
+    }
+
+    pub fn relics_run(&self) -> String {
+        format!("{}/history/", self.address)
+    }
+
+    /// Settings that point both endpoints to the mock.
+    pub fn settings(&self) -> SettingsState {
+        let mut settings = SettingsState::default();
+        settings.debug = vec![];
+        settings.endpoints.wfm_api = self.wfm_api();
+        settings.endpoints.relics_run = self.relics_run();
+        settings
+    }
+
+    /// A client logged in as the mock user.
+    pub fn client(&self) -> WFMClient {
+        let mut auth = AuthState::default();
+        auth.ingame_name = self.state.lock().unwrap().user["ingame_name"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        auth.access_token = Some("mock-token".to_string());
+        WFMClient::new(
+            Arc::new(Mutex::new(auth)),
+            Arc::new(Mutex::new(self.settings())),
+        )
+    }
+
+    /// A cache with the items of the mock, saved next to the other temporary files.
+    pub fn cache(&self) -> CacheClient {
+        let items: Vec<Item> =
+            serde_json::from_value(json!(self.state.lock().unwrap().items)).unwrap();
+        let port = self.address.rsplit(':').next().unwrap_or_default();
+        let cache = CacheClient::new(Arc::new(Mutex::new(self.client())))
+            .with_file_path(std::env::temp_dir().join(format!("qf_mock_cache_{}.json", port)));
+        cache.cache_data.lock().unwrap().item.items = items;
+        cache
+    }
+
+    /// A database in memory that uses the cache of the mock.
+    pub async fn database(&self) -> DBClient {
+        DBClient::in_memory(
+            Arc::new(Mutex::new(self.cache())),
+            Arc::new(Mutex::new(self.client())),
+        )
+        .await
+    }
+
+    /// Returns `response` the next time `method` is called on `path`.
+    pub fn script(&self, method: &str, path: &str, response: MockResponse) {
+        let mut state = self.state.lock().unwrap();
+        let method = method.to_uppercase();
+        match state
+            .scripted
+            .iter_mut()
+            .find(|(m, p, _)| *m == method && p == path)
+        {
+            Some((_, _, responses)) => responses.push_back(response),
+            None => state
+                .scripted
+                .push((method, path.to_string(), VecDeque::from([response]))),
+        }
+    }
+
+    pub fn add_profile(&self, ingame_name: &str, reputation: i64, status: &str) {
+        self.state.lock().unwrap().profiles.insert(
+            ingame_name.to_string(),
+            json!({
+                "id": format!("{}_id", ingame_name.to_lowercase()),
+                "ingame_name": ingame_name,
+                "avatar": null,
+                "background": null,
+                "about_raw": "",
+                "reputation": reputation,
+                "region": "en",
+                "platform": "pc",
+                "status": status,
+                "last_seen": "2024-01-01T00:00:00.000+00:00",
+                "banned": false,
+                "role": "user",
+            }),
+        );
+    }
+
+    pub fn add_review(&self, ingame_name: &str, from: &str, review_type: &str, text: &str) {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id("review_");
+        state
+            .reviews
+            .entry(ingame_name.to_string())
+            .or_default()
+            .push(json!({
+                "id": id,
+                "user_from": {
+                    "id": format!("{}_id", from.to_lowercase()),
+                    "ingame_name": from,
+                    "avatar": null,
+                    "reputation": 10,
+                },
+                "text": text,
+                "review_type": review_type,
+                "date": "2024-01-01T00:00:00.000+00:00",
+                "hidden": false,
+            }));
+    }
+
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Adds an item, with details of an item that is not part of a set unless it has some.
+    pub fn add_item(&self, id: &str, url_name: &str, item_name: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.items.push(json!({
+            "id": id,
+            "url_name": url_name,
+            "item_name": item_name,
+            "thumb": format!("items/images/en/thumbs/{}.png", url_name),
+        }));
+        state
+            .item_details
+            .entry(url_name.to_string())
+            .or_insert_with(|| {
+                json!({
+                    "id": id,
+                    "items_in_set": [{
+                        "id": id,
+                        "url_name": url_name,
+                        "set_root": false,
+                        "trading_tax": 2000,
+                        "tags": [],
+                        "en": { "item_name": item_name },
+                    }],
+                })
+            });
+    }
+
+    pub fn set_order_book(&self, url_name: &str, orders: Vec<Value>) {
+        self.state
+            .lock()
+            .unwrap()
+            .order_book
+            .insert(url_name.to_string(), orders);
+    }
+
+    pub fn add_my_order(&self, order: Value) {
+        self.state.lock().unwrap().my_orders.push(order);
+    }
+}
+
+/// Builds an order like the ones returned by warframe.market.
+pub fn order_json(
+    id: &str,
+    url_name: &str,
+    order_type: &str,
+    platinum: i64,
+    quantity: i64,
+    mod_rank: Option<i64>,
+    user_name: &str,
+    status: &str,
+) -> Value {
+    json!({
+        "id": id,
+        "platinum": platinum,
+        "visible": true,
+        "order_type": order_type,
+        "user": {
+            "reputation": 10.0,
+            "ingame_name": user_name,
+            "id": format!("{}_id", user_name),
+            "status": status,
+        },
+        "last_update": "2024-01-01T00:00:00.000+00:00",
+        "region": "en",
+        "platform": "pc",
+        "creation_date": "2024-01-01T00:00:00.000+00:00",
+        "subtype": null,
+        "quantity": quantity,
+        "mod_rank": mod_rank,
+        "item": {
+            "id": format!("{}_id", url_name),
+            "url_name": url_name,
+            "icon": "",
+            "icon_format": null,
+            "thumb": "",
+            "sub_icon": null,
+            "mod_max_rank": mod_rank,
+            "subtypes": null,
+            "tags": [],
+            "ducats": null,
+            "quantity_for_set": null,
+            "vaulted": null,
+            "en": { "item_name": url_name },
+        },
+    })
+}
+
+/// Builds a riven auction like the ones returned by warframe.market.
+pub fn riven_auction_json(
+    id: &str,
+    weapon_url_name: &str,
+    mod_name: &str,
+    buyout_price: i64,
+    owner_name: &str,
+    owner_status: &str,
+) -> Value {
+    json!({
+        "visible": true,
+        "minimal_reputation": 0,
+        "item": {
+            "type": "riven",
+            "weapon_url_name": weapon_url_name,
+            "re_rolls": 0,
+            "attributes": [
+                { "positive": true, "value": 100.0, "url_name": "critical_chance" },
+            ],
+            "name": mod_name,
+            "mod_rank": 0,
+            "polarity": "madurai",
+            "mastery_level": 8,
+        },
+        "buyout_price": buyout_price,
+        "note": "",
+        "starting_price": buyout_price,
+        "owner": {
+            "ingame_name": owner_name,
+            "last_seen": "2024-01-01T00:00:00.000+00:00",
+            "reputation": 10,
+            "locale": "en",
+            "status": owner_status,
+            "id": format!("{}_id", owner_name),
+            "region": "en",
+            "avatar": null,
+        },
+        "platform": "pc",
+        "closed": false,
+        "top_bid": null,
+        "winner": null,
+        "is_marked_for": null,
+        "marked_operation_at": null,
+        "created": "2024-01-01T00:00:00.000+00:00",
+        "updated": "2024-01-01T00:00:00.000+00:00",
+        "note_raw": "",
+        "is_direct_sell": true,
+        "id": id,
+        "private": false,
+    })
+}
+
+async fn handle_connection(stream: TcpStream, state: Arc<Mutex<MockState>>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_uppercase();
+    let target = parts.next().unwrap_or_default().to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 || header == "\r\n" {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await?;
+
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path.to_string(), query.to_string()),
+        None => (target.clone(), "".to_string()),
+    };
+    let request = MockRequest {
+        method,
+        path: path.trim_matches('/').to_string(),
+        query,
+        body: serde_json::from_slice(&body).ok(),
+    };
+    let response = respond(&mut state.lock().unwrap(), request);
+
+    let content = response.body.to_string();
+    let mut head = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        content.len()
+    );
+    for (name, value) in response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    let stream = reader.get_mut();
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(content.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+fn not_found() -> MockResponse {
+    MockResponse::error(404, "request", "app.mock.not_found")
+}
+
+fn respond(state: &mut MockState, request: MockRequest) -> MockResponse {
+    state.requests.push(request.clone());
+
+    if let Some((_, _, responses)) = state
+        .scripted
+        .iter_mut()
+        .find(|(m, p, _)| *m == request.method && p == &request.path)
+    {
+        if let Some(response) = responses.pop_front() {
+            return response;
+        }
+    }
+
+    // relics.run
+    if let Some(file) = request.path.strip_prefix("history/") {
+        let day = file
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .trim_start_matches("price_history_")
+            .trim_end_matches(".json");
+        return match state.price_history.get(day) {
+            Some(history) => MockResponse::new(200, history.clone()),
+            None => MockResponse::new(404, json!({})),
+        };
+    }
+
+    let path = match request.path.strip_prefix("v1/") {
+        Some(path) => path.to_string(),
+        None => return not_found(),
+    };
+    let segments: Vec<&str> = path.split('/').collect();
+    let body = request.body.clone().unwrap_or(json!({}));
+    match (request.method.as_str(), segments.as_slice()) {
+        ("POST", ["auth", "signin"]) => MockResponse::payload(json!({ "user": state.user }))
+            .with_header("set-cookie", "JWT=mock-token; Domain=.warframe.market; Path=/"),
+        ("GET", ["items"]) => MockResponse::payload(json!({ "items": state.items })),
+        ("GET", ["items", url_name]) => match state.item_details.get(*url_name) {
+            Some(item) => MockResponse::payload(json!({ "item": item })),
+            None => MockResponse::error(404, "item", "app.item.not_exist"),
+        },
+        ("GET", ["items", url_name, "orders"]) => {
+            let orders = state.order_book.get(*url_name).cloned().unwrap_or_default();
+            MockResponse::payload(json!({ "orders": orders }))
+        }
+        ("GET", ["profile", name]) => {
+            if state.user["ingame_name"] == *name {
+                let mut profile = state.user.clone();
+                profile["reputation"] = json!(0);
+                profile["last_seen"] = json!("2024-01-01T00:00:00.000+00:00");
+                return MockResponse::payload(json!({ "profile": profile }));
+            }
+            match state.profiles.get(*name) {
+                Some(profile) => MockResponse::payload(json!({ "profile": profile })),
+                None => MockResponse::error(404, "user", "app.profile.not_exist"),
+            }
+        }
+        ("GET", ["profile", name, "reviews", page]) => {
+            // Ten reviews a page, like warframe.market
+            let page = page.parse::<usize>().unwrap_or(1).max(1);
+            let reviews: Vec<Value> = state
+                .reviews
+                .get(*name)
+                .cloned()
+                .unwrap_or_default()
+                .into_iter()
+                .skip((page - 1) * 10)
+                .take(10)
+                .collect();
+            MockResponse::payload(json!({ "reviews": reviews }))
+        }
+        ("GET", ["profile", _, "orders"]) => {
+            let (sell_orders, buy_orders): (Vec<Value>, Vec<Value>) = state
+                .my_orders
+                .iter()
+                .cloned()
+                .partition(|order| order["order_type"] == "sell");
+            MockResponse::payload(json!({ "sell_orders": sell_orders, "buy_orders": buy_orders }))
+        }
+        ("POST", ["profile", "orders"]) => {
+            let item_id = body["item"].as_str().unwrap_or_default().to_string();
+            let url_name = state
+                .items
+                .iter()
+                .find(|item| item["id"] == item_id.as_str())
+                .and_then(|item| item["url_name"].as_str().map(|s| s.to_string()))
+                .unwrap_or(item_id.clone());
+            let user_name = state.user["ingame_name"].as_str().unwrap_or_default().to_string();
+            let mut order = order_json(
+                &state.next_id("order"),
+                &url_name,
+                body["order_type"].as_str().unwrap_or("sell"),
+                body["platinum"].as_i64().unwrap_or(0),
+                body["quantity"].as_i64().unwrap_or(1),
+                body["rank"].as_f64().map(|rank| rank as i64),
+                &user_name,
+                "ingame",
+            );
+            order["visible"] = body["visible"].clone();
+            order["item"]["id"] = json!(item_id);
+            state.my_orders.push(order.clone());
+            MockResponse::payload(json!({ "order": order }))
+        }
+        ("PUT", ["profile", "orders", "close", id]) => {
+            match state.my_orders.iter().position(|order| order["id"] == *id) {
+                Some(index) => {
+                    let mut order = state.my_orders[index].clone();
+                    let quantity = order["quantity"].as_i64().unwrap_or(1) - 1;
+                    order["quantity"] = json!(quantity);
+                    if quantity <= 0 {
+                        state.my_orders.remove(index);
+                    } else {
+                        state.my_orders[index] = order.clone();
+                    }
+                    MockResponse::payload(json!({ "order": order }))
+                }
+                None => MockResponse::error(404, "order_id", "app.form.not_exist"),
+            }
+        }
+        ("PUT", ["profile", "orders", id]) => {
+            match state.my_orders.iter_mut().find(|order| order["id"] == *id) {
+                Some(order) => {
+                    for key in ["platinum", "quantity", "visible"] {
+                        if !body[key].is_null() {
+                            order[key] = body[key].clone();
+                        }
+                    }
+                    MockResponse::payload(json!({ "order": order }))
+                }
+                None => MockResponse::error(404, "order_id", "app.form.not_exist"),
+            }
+        }
+        ("DELETE", ["profile", "orders", id]) => {
+            match state.my_orders.iter().position(|order| order["id"] == *id) {
+                Some(index) => {
+                    state.my_orders.remove(index);
+                    MockResponse::payload(json!({ "order_id": id }))
+                }
+                None => MockResponse::error(404, "order_id", "app.delete_order.order_not_exist"),
+            }
+        }
+        ("GET", ["profile", _, "auctions"]) => {
+            MockResponse::payload(json!({ "auctions": state.auctions }))
+        }
+        ("POST", ["auctions", "create"]) => {
+            let user_name = state.user["ingame_name"].as_str().unwrap_or_default().to_string();
+            let mut auction = riven_auction_json(
+                &state.next_id("auction"),
+                body["item"]["weapon_url_name"].as_str().unwrap_or_default(),
+                body["item"]["name"].as_str().unwrap_or_default(),
+                body["buyout_price"].as_i64().unwrap_or(0),
+                &user_name,
+                "ingame",
+            );
+            for key in ["note", "starting_price", "minimal_reputation", "private"] {
+                if !body[key].is_null() {
+                    auction[key] = body[key].clone();
+                }
+            }
+            if body["item"].is_object() {
+                auction["item"] = body["item"].clone();
+            }
+            state.auctions.push(auction.clone());
+            MockResponse::payload(json!({ "auction": auction }))
+        }
+        ("PUT", ["auctions", "entry", id, "close"]) => {
+            match state.auctions.iter().position(|auction| auction["id"] == *id) {
+                Some(index) => {
+                    state.auctions.remove(index);
+                    MockResponse::payload(json!({ "auction_id": id }))
+                }
+                None => MockResponse::error(404, "auction_id", "app.form.not_exist"),
+            }
+        }
+        ("PUT", ["auctions", "entry", id]) => {
+            match state.auctions.iter_mut().find(|auction| auction["id"] == *id) {
+                Some(auction) => {
+                    for key in ["buyout_price", "minimal_reputation", "note", "starting_price", "visible"] {
+                        if !body[key].is_null() {
+                            auction[key] = body[key].clone();
+                        }
+                    }
+                    MockResponse::payload(json!({ "auction": auction }))
+                }
+                None => MockResponse::error(404, "auction_id", "app.form.not_exist"),
+            }
+        }
+        ("GET", ["auctions", "search"]) => {
+            MockResponse::payload(json!({ "auctions": state.auction_search }))
+        }
+        ("GET", ["riven", "items"]) => MockResponse::payload(json!({ "items": state.riven_items })),
+        ("GET", ["riven", "attributes"]) => {
+            MockResponse::payload(json!({ "attributes": state.riven_attributes }))
+        }
+        ("GET", ["im", "chats"]) => MockResponse::payload(json!({ "chats": state.chats })),
+        ("GET", ["im", "chats", id]) => {
+            let messages = state.chat_messages.get(*id).cloned().unwrap_or_default();
+            MockResponse::payload(json!({ "messages": messages }))
+        }
+        ("POST", ["im", "chats"]) => {
+            let user_name = body["user_name"].as_str().unwrap_or_default().to_string();
+            let existing = state
+                .chats
+                .iter()
+                .find(|chat| chat["chat_name"] == user_name.as_str())
+                .cloned();
+            let chat = existing.unwrap_or_else(|| {
+                let chat = json!({
+                    "id": format!("chat_{}", state.chats.len() + 1),
+                    "chat_with": [],
+                    "unread_count": 0,
+                    "chat_name": user_name,
+                    "messages": [],
+                    "last_update": "2024-01-01T00:00:00.000+00:00",
+                });
+                state.chats.push(chat.clone());
+                chat
+            });
+            MockResponse::payload(json!({ "chat": chat }))
+        }
+        ("POST", ["im", "chats", id, "messages"]) => {
+            if !state.chats.iter().any(|chat| chat["id"] == *id) {
+                return MockResponse::error(404, "chat_id", "app.form.not_exist");
+            }
+            let messages = state.chat_messages.entry(id.to_string()).or_default();
+            let message = json!({
+                "message": body["message"],
+                "raw_message": body["message"],
+                "id": format!("message_{}", messages.len() + 1),
+                "chat_id": id,
+                "send_date": "2024-01-01T00:00:00.000+00:00",
+                "message_from": "mock_user",
+            });
+            messages.push(message.clone());
+            MockResponse::payload(json!({ "message": message }))
+        }
+        ("PUT", ["im", "chats", id, "read"]) => {
+            match state.chats.iter_mut().find(|chat| chat["id"] == *id) {
+                Some(chat) => {
+                    chat["unread_count"] = json!(0);
+                    MockResponse::payload(json!({ "chat_id": id }))
+                }
+                None => MockResponse::error(404, "chat_id", "app.form.not_exist"),
+            }
+        }
+        ("DELETE", ["im", "chats", id]) => {
+            state.chats.retain(|chat| chat["id"] != *id);
+            state.chat_messages.remove(*id);
+            MockResponse::payload(json!({ "chat_id": id }))
+        }
+        _ => not_found(),
+    }
+}
@@ -1,2 +1,8 @@
+pub mod chat_outbox;
 pub mod client;
+#[cfg(test)]
+pub mod mock_server;
 pub mod modules;
+pub mod response_cache;
+pub mod session;
+pub mod websocket;
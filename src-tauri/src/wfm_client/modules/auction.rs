@@ -4,7 +4,7 @@ use eyre::eyre;
 use serde_json::json;
 
 use crate::{
-    error::{ApiResult, AppError},
+    error::{ApiResult, AppError, WfmError},
     helper, logger,
     structs::{
         Auction, AuctionItem, AuctionOwner, RivenAttribute, RivenAttributeInfo, RivenTypeInfo,
@@ -347,11 +347,8 @@ impl<'a> AuctionModule<'a> {
                 return Ok(payload);
             }
             Ok(ApiResult::Error(error, _headers)) => {
-                let log_level = match error.messages.get(0) {
-                    Some(message)
-                        if message.contains("app.form.not_exist")
-                            || message.contains("app.form.invalid") =>
-                    {
+                let log_level = match error.wfm_error() {
+                    Some(WfmError::NotFound { .. }) | Some(WfmError::Validation { .. }) => {
                         crate::enums::LogLevel::Warning
                     }
                     _ => crate::enums::LogLevel::Error,
@@ -408,3 +405,39 @@ pub async fn auction_search(
         .await?;
     Ok(live_auctions)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        enums::LogLevel,
+        wfm_client::mock_server::{riven_auction_json, MockResponse, MockWfmServer},
+    };
+
+    #[tokio::test]
+    async fn gets_and_deletes_my_auctions() {
+        let server = MockWfmServer::start().await;
+        server.state.lock().unwrap().auctions.push(riven_auction_json(
+            "a1", "braton", "acri-visican", 300, "MockUser", "ingame",
+        ));
+        let wfm = server.client();
+
+        let auctions = wfm.auction().get_my_auctions().await.unwrap();
+        assert_eq!(auctions.len(), 1);
+        wfm.auction().delete("a1").await.unwrap();
+        assert!(wfm.auction().get_my_auctions().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn deleting_a_closed_auction_is_a_warning() {
+        let server = MockWfmServer::start().await;
+        server.script(
+            "PUT",
+            "v1/auctions/entry/a1/close",
+            MockResponse::error(400, "_form", "app.form.invalid"),
+        );
+        let wfm = server.client();
+
+        let error = wfm.auction().delete("a1").await.unwrap_err();
+        assert_eq!(error.log_level(), LogLevel::Warning);
+    }
+}
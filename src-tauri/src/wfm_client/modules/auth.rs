@@ -4,7 +4,7 @@ use serde_json::json;
 
 use crate::{
     auth::AuthState,
-    error::{self, ApiResult, AppError},
+    error::{self, ApiResult, AppError, WfmError},
     wfm_client::client::WFMClient,
 };
 pub struct AuthModule<'a> {
@@ -79,9 +79,7 @@ impl<'a> AuthModule<'a> {
                 Ok(true)
             }
             Err(e) => {
-                if e.cause()
-                    .contains("app.post_order.already_created_no_duplicates")
-                {
+                if let Some(WfmError::AlreadyExists { .. }) = e.wfm_error() {
                     return Ok(true);
                 }
                 auth.access_token = None;
@@ -1,5 +1,6 @@
 use eyre::eyre;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 
 use crate::{
     error::{ApiResult, AppError},
@@ -97,6 +98,87 @@ impl<'a> ChatModule<'a> {
             }
         };
     }
+    pub async fn send_message(&self, chat_id: &str, message: &str) -> Result<ChatMessage, AppError> {
+        let url = format!("im/chats/{}/messages", chat_id);
+        let body = json!({ "message": message });
+        match self.client.post(&url, Some("message"), body).await {
+            Ok(ApiResult::Success(payload, _headers)) => {
+                self.client.debug(
+                    &self.debug_id,
+                    "Chat:SendMessage",
+                    format!("Message was sent to chat {}.", chat_id).as_str(),
+                    None,
+                );
+                return Ok(payload);
+            }
+            Ok(ApiResult::Error(error, _headers)) => {
+                return Err(self.client.create_api_error(
+                    "Chat:SendMessage",
+                    error,
+                    eyre!("There was an error sending a message to chat {}", chat_id),
+                    crate::enums::LogLevel::Error,
+                ));
+            }
+            Err(err) => {
+                return Err(err);
+            }
+        };
+    }
+
+    pub async fn mark_read(&self, chat_id: &str) -> Result<String, AppError> {
+        let url = format!("im/chats/{}/read", chat_id);
+        match self.client.put(&url, Some("chat_id"), None).await {
+            Ok(ApiResult::Success(payload, _headers)) => {
+                self.client.debug(
+                    &self.debug_id,
+                    "Chat:MarkRead",
+                    format!("Chat {} was marked as read.", chat_id).as_str(),
+                    None,
+                );
+                return Ok(payload);
+            }
+            Ok(ApiResult::Error(error, _headers)) => {
+                return Err(self.client.create_api_error(
+                    "Chat:MarkRead",
+                    error,
+                    eyre!("There was an error marking chat {} as read", chat_id),
+                    crate::enums::LogLevel::Error,
+                ));
+            }
+            Err(err) => {
+                return Err(err);
+            }
+        };
+    }
+
+    /// Opens a chat with a user, warframe.market returns the existing chat if there is one.
+    pub async fn start_chat(&self, user_name: &str) -> Result<ChatData, AppError> {
+        let body = json!({ "user_name": user_name });
+        match self.client.post("im/chats", Some("chat"), body).await {
+            Ok(ApiResult::Success(payload, _headers)) => {
+                self.client.debug(
+                    &self.debug_id,
+                    "Chat:StartChat",
+                    format!("Chat with {} was started.", user_name).as_str(),
+                    None,
+                );
+                self.emit("CREATE_OR_UPDATE", serde_json::to_value(&payload).unwrap());
+                return Ok(payload);
+            }
+            Ok(ApiResult::Error(error, _headers)) => {
+                return Err(self.client.create_api_error(
+                    "Chat:StartChat",
+                    error,
+                    eyre!("There was an error starting a chat with {}", user_name),
+                    crate::enums::LogLevel::Error,
+                ));
+            }
+            Err(err) => {
+                return Err(err);
+            }
+        };
+    }
+
     pub fn emit(&self, operation: &str, data: serde_json::Value) {
         helper::emit_update("ChatMessages", operation, Some(data));
     }
@@ -170,3 +252,21 @@ pub struct ChatMessage {
     #[serde(rename = "raw_message")]
     pub raw_message: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::wfm_client::mock_server::MockWfmServer;
+
+    #[tokio::test]
+    async fn sends_chat_messages() {
+        let server = MockWfmServer::start().await;
+        let wfm = server.client();
+
+        let chat = wfm.chat().start_chat("MockTrader").await.unwrap();
+        assert_eq!(chat.chat_name, "MockTrader");
+        let message = wfm.chat().send_message(&chat.id, "hello").await.unwrap();
+        assert_eq!(message.chat_id, chat.id);
+        wfm.chat().mark_read(&chat.id).await.unwrap();
+        assert_eq!(wfm.chat().get_chat(chat.id.clone()).await.unwrap().len(), 1);
+    }
+}
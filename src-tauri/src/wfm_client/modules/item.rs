@@ -61,3 +61,66 @@ impl<'a> ItemModule<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{structs::ItemDetails, wfm_client::mock_server::MockWfmServer};
+
+    #[tokio::test]
+    async fn gets_item_details_with_set_parts() {
+        let server = MockWfmServer::start().await;
+        let part = |id: &str, url_name: &str, name: &str, ducats: i64, quantity: i64| {
+            json!({
+                "id": id,
+                "url_name": url_name,
+                "set_root": false,
+                "ducats": ducats,
+                "trading_tax": 2000,
+                "quantity_for_set": quantity,
+                "rarity": "rare",
+                "vaulted": true,
+                "tags": ["prime", "component"],
+                "thumb": format!("items/images/en/thumbs/{}.png", url_name),
+                "en": { "item_name": name, "description": "", "wiki_link": null },
+                "de": { "item_name": format!("{} (DE)", name) },
+            })
+        };
+        server.state.lock().unwrap().item_details.insert(
+            "braton_prime_set".to_string(),
+            json!({
+                "id": "set_id",
+                "items_in_set": [
+                    {
+                        "id": "set_id",
+                        "url_name": "braton_prime_set",
+                        "set_root": true,
+                        "trading_tax": 8000,
+                        "vaulted": true,
+                        "tags": ["prime", "set"],
+                        "en": { "item_name": "Braton Prime Set" },
+                    },
+                    part("barrel_id", "braton_prime_barrel", "Braton Prime Barrel", 45, 1),
+                    part("link_id", "braton_prime_link", "Braton Prime Link", 15, 2),
+                ],
+            }),
+        );
+        let wfm = server.client();
+
+        let details = wfm.items().get_item("braton_prime_set".to_string()).await.unwrap();
+        assert!(details.is_set());
+        assert_eq!(details.parts().len(), 2);
+        assert_eq!(details.set_ducats(), 75);
+        let barrel = details.find("braton_prime_barrel").unwrap();
+        assert_eq!(barrel.rarity.as_deref(), Some("rare"));
+        assert_eq!(barrel.vaulted, Some(true));
+        assert_eq!(barrel.name("de"), Some("Braton Prime Barrel (DE)"));
+        assert_eq!(barrel.name("ko"), Some("Braton Prime Barrel"));
+
+        // The cache keeps the translations together
+        let cached: ItemDetails =
+            serde_json::from_value(serde_json::to_value(&details).unwrap()).unwrap();
+        assert_eq!(cached.find("braton_prime_link").unwrap().translations.len(), 2);
+    }
+}
@@ -2,4 +2,5 @@ pub mod auction;
 pub mod auth;
 pub mod item;
 pub mod chat;
-pub mod order;
\ No newline at end of file
+pub mod order;
+pub mod profile;
@@ -6,7 +6,7 @@ use serde_json::json;
 
 use crate::{
     enums::OrderType,
-    error::{ApiResult, AppError},
+    error::{ApiResult, AppError, WfmError},
     helper,
     structs::{Order, Ordres},
     wfm_client::client::WFMClient,
@@ -102,10 +102,8 @@ impl<'a> OrderModule<'a> {
                 return Ok(payload);
             }
             Ok(ApiResult::Error(error, _headers)) => {
-                let log_level = match error.messages.get(0) {
-                    Some(message) if message.contains("app.delete_order.order_not_exist") => {
-                        crate::enums::LogLevel::Warning
-                    }
+                let log_level = match error.wfm_error() {
+                    Some(WfmError::NotFound { .. }) => crate::enums::LogLevel::Warning,
                     _ => crate::enums::LogLevel::Error,
                 };
                 return Err(self.client.create_api_error(
@@ -135,10 +133,8 @@ impl<'a> OrderModule<'a> {
                 return Ok(payload);
             }
             Ok(ApiResult::Error(error, _headers)) => {
-                let log_level = match error.messages.get(0) {
-                    Some(message) if message.contains("app.delete_order.order_not_exist") => {
-                        crate::enums::LogLevel::Warning
-                    }
+                let log_level = match error.wfm_error() {
+                    Some(WfmError::NotFound { .. }) => crate::enums::LogLevel::Warning,
                     _ => crate::enums::LogLevel::Error,
                 };
                 return Err(self.client.create_api_error(
@@ -188,13 +184,8 @@ impl<'a> OrderModule<'a> {
                 return Ok(payload);
             }
             Ok(ApiResult::Error(error, _headers)) => {
-                let log_level = match error.messages.get(0) {
-                    Some(message)
-                        if message.contains("app.delete_order.order_not_exist")
-                            || message.contains("app.form.not_exist") =>
-                    {
-                        crate::enums::LogLevel::Warning
-                    }
+                let log_level = match error.wfm_error() {
+                    Some(WfmError::NotFound { .. }) => crate::enums::LogLevel::Warning,
                     _ => crate::enums::LogLevel::Error,
                 };
                 return Err(self.client.create_api_error(
@@ -248,13 +239,8 @@ impl<'a> OrderModule<'a> {
                     payload
                 }
                 Ok(ApiResult::Error(error, _headers)) => {
-                    let log_level = match error.messages.get(0) {
-                        Some(message)
-                            if message.contains("app.close_order.order_not_exist")
-                                || message.contains("app.form.not_exist") =>
-                        {
-                            crate::enums::LogLevel::Warning
-                        }
+                    let log_level = match error.wfm_error() {
+                        Some(WfmError::NotFound { .. }) => crate::enums::LogLevel::Warning,
                         _ => crate::enums::LogLevel::Error,
                     };
                     return Err(self.client.create_api_error(
@@ -459,3 +445,62 @@ impl<'a> OrderModule<'a> {
     }
     // End Helper
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        enums::OrderType,
+        error::WfmError,
+        wfm_client::mock_server::{order_json, MockResponse, MockWfmServer},
+    };
+
+    #[tokio::test]
+    async fn manages_my_orders() {
+        let server = MockWfmServer::start().await;
+        server.add_item("item_1", "ash_prime_set", "Ash Prime Set");
+        let wfm = server.client();
+
+        let order = wfm
+            .orders()
+            .create("item_1", "sell", 100, 1, true, None)
+            .await
+            .unwrap();
+        assert_eq!(order.platinum, 100);
+        assert_eq!(order.item.unwrap().url_name, "ash_prime_set");
+
+        wfm.orders().update(&order.id, 90, 2, true).await.unwrap();
+        let orders = wfm.orders().get_my_orders().await.unwrap();
+        assert_eq!(orders.sell_orders.len(), 1);
+        assert_eq!(orders.sell_orders[0].platinum, 90);
+        assert_eq!(orders.sell_orders[0].order_type, OrderType::Sell);
+
+        wfm.orders().delete(&order.id).await.unwrap();
+        assert!(wfm.orders().get_my_orders().await.unwrap().sell_orders.is_empty());
+
+        let error = wfm.orders().delete(&order.id).await.unwrap_err();
+        assert!(matches!(
+            error.wfm_error(),
+            Some(WfmError::NotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn gets_the_order_book_and_api_errors() {
+        let server = MockWfmServer::start().await;
+        server.set_order_book(
+            "primed_flow",
+            vec![order_json("o1", "primed_flow", "sell", 40, 1, Some(10), "Seller", "ingame")],
+        );
+        server.script(
+            "GET",
+            "v1/items/primed_flow/orders",
+            MockResponse::error(400, "request", "app.mock.invalid"),
+        );
+        let wfm = server.client();
+
+        assert!(wfm.orders().get_ordres_by_item("primed_flow").await.is_err());
+        let orders = wfm.orders().get_ordres_by_item("primed_flow").await.unwrap();
+        assert_eq!(orders.height(), 1);
+        assert_eq!(server.requests().len(), 2);
+    }
+}
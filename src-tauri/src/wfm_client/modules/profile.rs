@@ -0,0 +1,250 @@
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{ApiResult, AppError},
+    structs::Order,
+    wfm_client::client::WFMClient,
+};
+
+pub struct ProfileModule<'a> {
+    pub client: &'a WFMClient,
+    pub debug_id: String,
+}
+
+impl<'a> ProfileModule<'a> {
+    pub async fn get_profile(&self, ingame_name: &str) -> Result<UserProfile, AppError> {
+        let url = format!("profile/{}", ingame_name);
+        match self.client.get::<UserProfile>(&url, Some("profile")).await {
+            Ok(ApiResult::Success(payload, _headers)) => {
+                self.client.debug(
+                    &self.debug_id,
+                    "Profile:GetProfile",
+                    format!("Profile of {} was fetched.", ingame_name).as_str(),
+                    None,
+                );
+                return Ok(payload);
+            }
+            Ok(ApiResult::Error(error, _headers)) => {
+                return Err(self.client.create_api_error(
+                    "Profile:GetProfile",
+                    error,
+                    eyre!("There was an error fetching the profile of {}", ingame_name),
+                    crate::enums::LogLevel::Error,
+                ));
+            }
+            Err(err) => {
+                return Err(err);
+            }
+        };
+    }
+
+    /// Reviews are paged by warframe.market, the first page is 1.
+    pub async fn get_reviews(
+        &self,
+        ingame_name: &str,
+        page: i64,
+    ) -> Result<Vec<UserReview>, AppError> {
+        let url = format!("profile/{}/reviews/{}", ingame_name, page.max(1));
+        match self.client.get::<Vec<UserReview>>(&url, Some("reviews")).await {
+            Ok(ApiResult::Success(payload, _headers)) => {
+                self.client.debug(
+                    &self.debug_id,
+                    "Profile:GetReviews",
+                    format!("{} reviews of {} were fetched.", payload.len(), ingame_name)
+                        .as_str(),
+                    None,
+                );
+                return Ok(payload);
+            }
+            Ok(ApiResult::Error(error, _headers)) => {
+                return Err(self.client.create_api_error(
+                    "Profile:GetReviews",
+                    error,
+                    eyre!("There was an error fetching the reviews of {}", ingame_name),
+                    crate::enums::LogLevel::Error,
+                ));
+            }
+            Err(err) => {
+                return Err(err);
+            }
+        };
+    }
+
+    pub async fn get_reputation(&self, ingame_name: &str) -> Result<f64, AppError> {
+        Ok(self.get_profile(ingame_name).await?.reputation)
+    }
+
+    pub async fn get_last_seen(&self, ingame_name: &str) -> Result<UserPresence, AppError> {
+        let profile = self.get_profile(ingame_name).await?;
+        Ok(UserPresence {
+            status: profile.status,
+            last_seen: profile.last_seen,
+        })
+    }
+
+    /// The orders of a user that other players can see, newest first.
+    pub async fn get_active_orders(&self, ingame_name: &str) -> Result<Vec<Order>, AppError> {
+        let orders = self.client.orders().get_user_orders(ingame_name).await?;
+        let mut active: Vec<Order> = orders
+            .sell_orders
+            .into_iter()
+            .chain(orders.buy_orders.into_iter())
+            .filter(|order| order.visible)
+            .collect();
+        active.sort_by(|a, b| b.last_update.cmp(&a.last_update));
+        Ok(active)
+    }
+
+    /// Everything needed to decide whether to trade with a user.
+    pub async fn get_summary(&self, ingame_name: &str) -> Result<UserSummary, AppError> {
+        let profile = self.get_profile(ingame_name).await?;
+        let reviews = self.get_reviews(ingame_name, 1).await?;
+        let orders = self.get_active_orders(ingame_name).await?;
+        let recent_positive_reviews = reviews
+            .iter()
+            .filter(|review| review.review_type == "good")
+            .count() as i64;
+        Ok(UserSummary {
+            recent_negative_reviews: reviews.len() as i64 - recent_positive_reviews,
+            recent_positive_reviews,
+            profile,
+            reviews,
+            orders,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UserProfile {
+    #[serde(rename = "id")]
+    pub id: String,
+
+    #[serde(rename = "ingame_name")]
+    pub ingame_name: String,
+
+    #[serde(rename = "avatar")]
+    pub avatar: Option<String>,
+
+    #[serde(rename = "background")]
+    pub background: Option<String>,
+
+    #[serde(rename = "about_raw", default)]
+    pub about: Option<String>,
+
+    #[serde(rename = "reputation")]
+    pub reputation: f64,
+
+    #[serde(rename = "region")]
+    pub region: String,
+
+    #[serde(rename = "platform")]
+    pub platform: String,
+
+    #[serde(rename = "status")]
+    pub status: String,
+
+    #[serde(rename = "last_seen")]
+    pub last_seen: Option<String>,
+
+    #[serde(rename = "banned", default)]
+    pub banned: bool,
+
+    #[serde(rename = "role", default)]
+    pub role: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReviewUser {
+    #[serde(rename = "id")]
+    pub id: String,
+
+    #[serde(rename = "ingame_name")]
+    pub ingame_name: String,
+
+    #[serde(rename = "avatar")]
+    pub avatar: Option<String>,
+
+    #[serde(rename = "reputation", default)]
+    pub reputation: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UserReview {
+    #[serde(rename = "id")]
+    pub id: String,
+
+    #[serde(rename = "user_from")]
+    pub user_from: ReviewUser,
+
+    #[serde(rename = "text")]
+    pub text: String,
+
+    // "good" or "bad"
+    #[serde(rename = "review_type")]
+    pub review_type: String,
+
+    #[serde(rename = "date")]
+    pub date: String,
+
+    #[serde(rename = "hidden", default)]
+    pub hidden: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UserPresence {
+    pub status: String,
+    pub last_seen: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UserSummary {
+    pub profile: UserProfile,
+    // The first page of reviews, the newest ten
+    pub reviews: Vec<UserReview>,
+    // Counted over the first page only
+    pub recent_positive_reviews: i64,
+    pub recent_negative_reviews: i64,
+    pub orders: Vec<Order>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::wfm_client::mock_server::{order_json, MockWfmServer};
+
+    #[tokio::test]
+    async fn summarizes_a_profile() {
+        let server = MockWfmServer::start().await;
+        server.add_profile("Trader", 42, "ingame");
+        server.add_review("Trader", "Buyer", "good", "fast trade");
+        server.add_review("Trader", "Seller", "bad", "did not show up");
+        server.state.lock().unwrap().my_orders.push(order_json(
+            "o1", "primed_flow", "sell", 40, 1, Some(10), "Trader", "ingame",
+        ));
+        let wfm = server.client();
+
+        let summary = wfm.profile().get_summary("Trader").await.unwrap();
+        assert_eq!(summary.profile.reputation, 42.0);
+        assert_eq!(summary.recent_positive_reviews, 1);
+        assert_eq!(summary.recent_negative_reviews, 1);
+        assert_eq!(summary.orders.len(), 1);
+        let presence = wfm.profile().get_last_seen("Trader").await.unwrap();
+        assert_eq!(presence.status, "ingame");
+        assert!(wfm.profile().get_profile("Nobody").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn counts_the_reviews_of_the_first_page() {
+        let server = MockWfmServer::start().await;
+        server.add_profile("Trader", 42, "ingame");
+        for _ in 0..12 {
+            server.add_review("Trader", "Buyer", "good", "fast trade");
+        }
+        let wfm = server.client();
+
+        let summary = wfm.profile().get_summary("Trader").await.unwrap();
+        assert_eq!(summary.reviews.len(), 10);
+        assert_eq!(summary.recent_positive_reviews, 10);
+        assert_eq!(summary.recent_negative_reviews, 0);
+    }
+}
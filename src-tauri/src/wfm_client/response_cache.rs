@@ -0,0 +1,113 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+
+use crate::request_scheduler::RequestPriority;
+
+// How long a GET response is kept, by route. Routes that are not listed are never cached.
+const TTLS: [(&str, u64); 9] = [
+    ("GET items", 3600),
+    ("GET items/{}", 3600),
+    ("GET items/{}/orders", 15),
+    ("GET profile/{}/orders", 30),
+    ("GET profile/{}/auctions", 30),
+    ("GET profile/{}", 300),
+    ("GET profile/{}/reviews/{}", 300),
+    ("GET riven/items", 3600),
+    ("GET riven/attributes", 3600),
+];
+
+// Routes that are stale after a request that changes something under the given path
+const INVALIDATIONS: [(&str, &[&str]); 2] = [
+    (
+        "profile/orders",
+        &["GET profile/{}/orders", "GET items/{}/orders"],
+    ),
+    (
+        "auctions",
+        &["GET profile/{}/auctions", "GET auctions/search"],
+    ),
+];
+
+// Routes the live scraper always reads fresh, it prices its orders from them
+const SCRAPING_UNCACHED: [&str; 1] = ["GET items/{}/orders"];
+
+#[derive(Clone, Debug)]
+struct CacheEntry {
+    route: String,
+    response: Value,
+    headers: HeaderMap,
+    expires: Instant,
+}
+
+/// Responses of GET requests, kept for a per route time to live.
+#[derive(Clone, Debug, Default)]
+pub struct ResponseCache {
+    entries: HashMap<String, CacheEntry>,
+}
+
+impl ResponseCache {
+    pub fn ttl(route: &str) -> Option<Duration> {
+        TTLS.iter()
+            .find(|(cached_route, _)| *cached_route == route)
+            .map(|(_, seconds)| Duration::from_secs(*seconds))
+    }
+
+    /// Whether a request with `priority` may be answered from the cache.
+    pub fn serves(route: &str, priority: RequestPriority) -> bool {
+        priority != RequestPriority::Scraping || !SCRAPING_UNCACHED.contains(&route)
+    }
+
+    pub fn get(&mut self, url: &str) -> Option<(Value, HeaderMap)> {
+        match self.entries.get(url) {
+            Some(entry) if entry.expires > Instant::now() => {
+                Some((entry.response.clone(), entry.headers.clone()))
+            }
+            Some(_) => {
+                self.entries.remove(url);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&mut self, url: &str, route: &str, response: Value, headers: HeaderMap) {
+        let ttl = match Self::ttl(route) {
+            Some(ttl) => ttl,
+            None => return,
+        };
+        self.entries.insert(
+            url.to_string(),
+            CacheEntry {
+                route: route.to_string(),
+                response,
+                headers,
+                expires: Instant::now() + ttl,
+            },
+        );
+    }
+
+    pub fn invalidate_route(&mut self, route: &str) {
+        self.entries.retain(|_, entry| entry.route != route);
+    }
+
+    /// Drops the responses a request to `url` may have changed.
+    pub fn invalidate_after(&mut self, url: &str) {
+        let url = url.trim_start_matches('/');
+        for (path, routes) in INVALIDATIONS.iter() {
+            if url.starts_with(path) {
+                for route in routes.iter() {
+                    self.invalidate_route(route);
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
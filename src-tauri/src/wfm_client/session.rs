@@ -0,0 +1,59 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use serde_json::json;
+use tokio::sync::Notify;
+
+use crate::helper;
+
+/// Whether the JWT is still accepted by warframe.market.
+/// Background requests wait here while it is expired and go on once the user is logged in again.
+#[derive(Debug, Default)]
+pub struct SessionState {
+    expired: AtomicBool,
+    // Held while logging in again, so only the first request that sees a 401 does it
+    relogin: tokio::sync::Mutex<()>,
+    resumed: Notify,
+}
+
+impl SessionState {
+    pub fn is_expired(&self) -> bool {
+        self.expired.load(Ordering::SeqCst)
+    }
+
+    /// Marks the session as expired, returns false if it already was.
+    pub fn expire(&self) -> bool {
+        if self.expired.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        helper::send_message_to_window("Client:Session", Some(json!({ "expired": true })));
+        true
+    }
+
+    pub fn resume(&self) {
+        if self.expired.swap(false, Ordering::SeqCst) {
+            helper::send_message_to_window("Client:Session", Some(json!({ "expired": false })));
+        }
+        self.resumed.notify_waiters();
+    }
+
+    pub async fn lock_relogin(&self) -> tokio::sync::MutexGuard<'_, ()> {
+        self.relogin.lock().await
+    }
+
+    /// Waits until the session is valid again, returns false if it still is not after `limit`.
+    pub async fn wait_until_valid(&self, limit: Duration) -> bool {
+        let wait = async {
+            loop {
+                let resumed = self.resumed.notified();
+                if !self.is_expired() {
+                    return;
+                }
+                resumed.await;
+            }
+        };
+        tokio::time::timeout(limit, wait).await.is_ok()
+    }
+}
@@ -0,0 +1,345 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use eyre::eyre;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite::{client::IntoClientRequest, http::HeaderValue, Message},
+};
+
+use crate::{
+    auth::AuthState,
+    error::AppError,
+    handler::MonitorHandler,
+    helper,
+    live_scraper::client::LiveScraperClient,
+    logger,
+    request_scheduler::RequestPriority,
+    settings::SettingsState,
+};
+
+use super::{client::WFMClient, modules::chat::ChatMessage};
+
+// The socket is reconnected when nothing was received for this long
+const STALE_AFTER: Duration = Duration::from_secs(180);
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// The warframe.market websocket, kept open from Rust so chat notifications
+/// and the live scraper keep working while the window is closed.
+#[derive(Clone)]
+pub struct WFMSocket {
+    component: String,
+    log_file: String,
+    is_running: Arc<AtomicBool>,
+    is_connected: Arc<AtomicBool>,
+    sender: UnboundedSender<Value>,
+    receiver: Arc<tokio::sync::Mutex<UnboundedReceiver<Value>>>,
+    pub auth: Arc<Mutex<AuthState>>,
+    pub settings: Arc<Mutex<SettingsState>>,
+    pub mh: Arc<Mutex<MonitorHandler>>,
+    pub live_scraper: Arc<Mutex<LiveScraperClient>>,
+    pub wfm: Arc<Mutex<WFMClient>>,
+}
+
+impl WFMSocket {
+    pub fn new(
+        auth: Arc<Mutex<AuthState>>,
+        settings: Arc<Mutex<SettingsState>>,
+        mh: Arc<Mutex<MonitorHandler>>,
+        live_scraper: Arc<Mutex<LiveScraperClient>>,
+        wfm: Arc<Mutex<WFMClient>>,
+    ) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        WFMSocket {
+            component: "WFMSocket".to_string(),
+            log_file: "socket.log".to_string(),
+            is_running: Arc::new(AtomicBool::new(false)),
+            is_connected: Arc::new(AtomicBool::new(false)),
+            sender,
+            receiver: Arc::new(tokio::sync::Mutex::new(receiver)),
+            auth,
+            settings,
+            mh,
+            live_scraper,
+            // Reconnecting waits while the session is expired
+            wfm: Arc::new(Mutex::new(
+                wfm.lock().unwrap().with_priority(RequestPriority::Background),
+            )),
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.is_connected.load(Ordering::SeqCst)
+    }
+
+    /// Queues a message like `{"type": "@WS/USER/SET_STATUS", "payload": "ingame"}`,
+    /// it is sent as soon as the socket is connected.
+    pub fn send(&self, message: Value) -> Result<(), AppError> {
+        self.sender
+            .send(message)
+            .map_err(|e| AppError::new(&self.component, eyre!(e.to_string())))
+    }
+
+    /// Keeps the socket connected while a user is logged in, reconnecting with backoff.
+    pub fn start(&self) {
+        if self.is_running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let socket = self.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut receiver = socket.receiver.lock().await;
+            let mut delay = MIN_RECONNECT_DELAY;
+            while socket.is_running.load(Ordering::SeqCst) {
+                let wfm = socket.wfm.lock().unwrap().clone();
+                // Still expired after the wait, check again on the next round
+                if wfm.wait_for_session().await.is_err() {
+                    continue;
+                }
+                let token = socket.auth.lock().unwrap().access_token.clone();
+                let token = match token {
+                    Some(token) if !token.is_empty() => token,
+                    _ => {
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+                };
+                match socket.listen(&token, &mut receiver).await {
+                    Ok(_) => delay = MIN_RECONNECT_DELAY,
+                    Err(e) => {
+                        logger::warning(
+                            &socket.component,
+                            format!(
+                                "Connection failed: {}, reconnecting in {}s",
+                                e.cause(),
+                                delay.as_secs()
+                            )
+                            .as_str(),
+                            true,
+                            Some(&socket.log_file),
+                        );
+                        tokio::time::sleep(delay).await;
+                        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                        continue;
+                    }
+                }
+                tokio::time::sleep(delay).await;
+            }
+        });
+    }
+
+    pub fn stop(&self) {
+        self.is_running.store(false, Ordering::SeqCst);
+    }
+
+    fn error(&self, e: impl std::fmt::Display) -> AppError {
+        AppError::new(&self.component, eyre!(e.to_string()))
+    }
+
+    fn set_connected(&self, connected: bool) {
+        self.is_connected.store(connected, Ordering::SeqCst);
+        helper::send_message_to_window(
+            "Client:Socket:Status",
+            Some(json!({ "connected": connected })),
+        );
+    }
+
+    /// Connects with `token` and handles messages until the socket is closed,
+    /// the user logs out or nothing was received for a while.
+    async fn listen(
+        &self,
+        token: &str,
+        receiver: &mut UnboundedReceiver<Value>,
+    ) -> Result<(), AppError> {
+        let auth = self.auth.lock()?.clone();
+        let url = self
+            .settings
+            .lock()?
+            .endpoints
+            .get_wfm_socket(&auth.platform);
+        let mut request = url.into_client_request().map_err(|e| self.error(e))?;
+        request.headers_mut().insert(
+            "Cookie",
+            HeaderValue::from_str(&format!("JWT={}", token)).map_err(|e| self.error(e))?,
+        );
+        let (stream, _) = connect_async(request).await.map_err(|e| self.error(e))?;
+        let (mut write, mut read) = stream.split();
+        self.set_connected(true);
+        logger::info_con(&self.component, "Connected to warframe.market");
+
+        let mut status = auth.status.clone();
+        let mut subscribed = false;
+        let mut last_message = Instant::now();
+        let mut tick = tokio::time::interval(Duration::from_secs(5));
+        if let Some(status) = &status {
+            let message = json!({ "type": "@WS/USER/SET_STATUS", "payload": status });
+            write
+                .send(Message::Text(message.to_string()))
+                .await
+                .map_err(|e| self.error(e))?;
+        }
+
+        let result = 'listen: loop {
+            tokio::select! {
+                message = read.next() => match message {
+                    Some(Ok(Message::Text(text))) => {
+                        last_message = Instant::now();
+                        self.on_message(&text);
+                    }
+                    Some(Ok(Message::Ping(data))) => {
+                        last_message = Instant::now();
+                        if let Err(e) = write.send(Message::Pong(data)).await {
+                            break Err(self.error(e));
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | None => break Ok(()),
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => break Err(self.error(e)),
+                },
+                Some(message) = receiver.recv() => {
+                    if let Err(e) = write.send(Message::Text(message.to_string())).await {
+                        break Err(self.error(e));
+                    }
+                }
+                _ = tick.tick() => {
+                    let current = self.auth.lock().unwrap().clone();
+                    // Logged out or logged in as someone else
+                    if current.access_token.as_deref() != Some(token) {
+                        break Ok(());
+                    }
+                    if last_message.elapsed() > STALE_AFTER {
+                        logger::warning(
+                            &self.component,
+                            "No message received for a while, reconnecting",
+                            true,
+                            Some(&self.log_file),
+                        );
+                        break Ok(());
+                    }
+                    let mut messages = vec![];
+                    if current.status != status {
+                        status = current.status.clone();
+                        if let Some(status) = &status {
+                            messages.push(json!({ "type": "@WS/USER/SET_STATUS", "payload": status }));
+                        }
+                    }
+                    // New orders are only followed while the live scraper can use them
+                    let scraping = self.live_scraper.lock().unwrap().is_running();
+                    if scraping != subscribed {
+                        subscribed = scraping;
+                        let action = if scraping { "SUBSCRIBE" } else { "UNSUBSCRIBE" };
+                        messages.push(json!({ "type": format!("@WS/{}/MOST_RECENT", action) }));
+                    }
+                    for message in messages {
+                        if let Err(e) = write.send(Message::Text(message.to_string())).await {
+                            break 'listen Err(self.error(e));
+                        }
+                    }
+                }
+            }
+        };
+        self.set_connected(false);
+        let _ = write.close().await;
+        result
+    }
+
+    fn on_message(&self, text: &str) {
+        let json: Value = match serde_json::from_str(text) {
+            Ok(json) => json,
+            Err(e) => {
+                logger::warning_file(
+                    &self.component,
+                    format!("Could not parse message: {}, {}", text, e).as_str(),
+                    Some(&self.log_file),
+                );
+                return;
+            }
+        };
+        let event = json["type"].as_str().unwrap_or_default().replace("@WS/", "");
+        let payload = json["payload"].clone();
+
+        if !event.contains("MESSAGE/ONLINE_COUNT") {
+            logger::debug_file(
+                &self.component,
+                format!("{}: {}", event, payload).as_str(),
+                Some("socketEvents.log"),
+            );
+        }
+        helper::send_message_to_window(
+            "Client:Socket",
+            Some(json!({ "event": event, "payload": payload })),
+        );
+
+        match event.as_str() {
+            "chats/NEW_MESSAGE" => match serde_json::from_value::<ChatMessage>(payload) {
+                Ok(message) => {
+                    let auth = self.auth.lock().unwrap().clone();
+                    let settings = self.settings.lock().unwrap().clone();
+                    let mh = self.mh.lock().unwrap();
+                    notify_chat_message(&message, &auth, &settings, &mh);
+                }
+                Err(e) => logger::warning_file(
+                    &self.component,
+                    format!("Could not parse chat message: {}", e).as_str(),
+                    Some(&self.log_file),
+                ),
+            },
+            "SUBSCRIPTIONS/MOST_RECENT/NEW_ORDER" => {
+                if let Some(url_name) = payload["order"]["item"]["url_name"].as_str() {
+                    self.live_scraper.lock().unwrap().on_order_changed(url_name);
+                }
+            }
+            event if event.contains("ERROR") => logger::warning(
+                &self.component,
+                format!("{}: {}", event, payload).as_str(),
+                true,
+                Some(&self.log_file),
+            ),
+            _ => {}
+        }
+    }
+}
+
+/// Shows the chat notification and sends it to discord, as set in the notification settings.
+pub fn notify_chat_message(
+    message: &ChatMessage,
+    auth: &AuthState,
+    settings: &SettingsState,
+    mh: &MonitorHandler,
+) {
+    let settings = settings.notifications.on_wfm_chat_message.clone();
+
+    if auth.id == message.message_from {
+        return;
+    }
+
+    let content = settings.content.replace(
+        "<WFM_MESSAGE>",
+        message.raw_message.as_deref().unwrap_or_default(),
+    );
+    if settings.system_notify {
+        mh.show_notification(
+            &settings.title,
+            &content,
+            Some("https://i.imgur.com/UggEVVI.jpeg"),
+            Some("Default"),
+        );
+    }
+
+    if settings.discord_notify && settings.webhook.is_some() {
+        helper::send_message_to_discord(
+            settings.webhook.unwrap_or("".to_string()),
+            settings.title,
+            content,
+            settings.user_ids,
+        );
+    }
+}
@@ -1,4 +1,4 @@
-import { SetupResponse, Wfm, TransactionEntryDto, Settings, CreateTransactionEntryDto, CreateStockItemEntryDto, StockItemDto, CreateStockRivenEntryDto, StockRivenDto } from '../types'
+import { SetupResponse, Wfm, TransactionEntryDto, TradeEntryDto, TradePageDto, TradeQueryDto, ProfitQueryDto, ProfitReportDto, DataTable, DataFormat, ImportDataDto, ImportReportDto, BackupInfoDto, IntegrityReportDto, Settings, CreateTransactionEntryDto, CreateStockItemEntryDto, StockItemDto, StockLotDto, StockEventDto, CreateStockRivenEntryDto, StockRivenDto } from '../types'
 import { invoke } from '@tauri-apps/api';
 import { SendTauriEvent, SendTauriUpdateDataEvent } from '../utils/tauri';
 const api = {
@@ -13,6 +13,9 @@ const api = {
     export_logs: async (): Promise<any> => {
       return await invoke("export_logs")
     },
+    get_request_status: async (): Promise<Wfm.RequestStatusDto> => {
+      return await invoke("get_request_status") as Wfm.RequestStatusDto;
+    },
   },
   chat: {
     refresh_chats: async (exclude: string[]): Promise<any> => {
@@ -28,8 +31,28 @@ const api = {
     getChat: async (id: string): Promise<Wfm.ChatMessage[]> => {
       return await invoke("get_chat", { id }) as Wfm.ChatMessage[];
     },
-    on_new_wfm_message: async (message: Wfm.ChatMessage) => {
-      return await invoke("on_new_wfm_message", { message });
+    send_message: async (id: string, chat_id: string, message: string): Promise<Wfm.ChatSendStatus> => {
+      return await invoke("send_chat_message", { id, chatId: chat_id, message }) as Wfm.ChatSendStatus;
+    },
+    mark_read: async (id: string): Promise<string> => {
+      return await invoke("mark_chat_read", { id }) as string;
+    },
+    start_chat: async (user_name: string): Promise<Wfm.ChatData> => {
+      return await invoke("start_chat", { userName: user_name }) as Wfm.ChatData;
+    },
+    get_outbox: async (): Promise<Wfm.ChatOutboxMessage[]> => {
+      return await invoke("get_chat_outbox") as Wfm.ChatOutboxMessage[];
+    },
+    delete_outbox_message: async (id: string): Promise<void> => {
+      return await invoke("delete_chat_outbox_message", { id });
+    },
+  },
+  profile: {
+    get_summary: async (ingame_name: string): Promise<Wfm.UserSummaryDto> => {
+      return await invoke("get_user_summary", { ingameName: ingame_name }) as Wfm.UserSummaryDto;
+    },
+    get_reviews: async (ingame_name: string, page: number): Promise<Wfm.UserReviewDto[]> => {
+      return await invoke("get_user_reviews", { ingameName: ingame_name, page }) as Wfm.UserReviewDto[];
     },
   },
   debug: {
@@ -40,6 +63,18 @@ const api = {
         console.error(error)
       }
     },
+    start_http_recording: async (): Promise<Wfm.RecorderStatusDto> => {
+      return await invoke("start_http_recording") as Wfm.RecorderStatusDto;
+    },
+    start_http_replay: async (path: string): Promise<Wfm.RecorderStatusDto> => {
+      return await invoke("start_http_replay", { path }) as Wfm.RecorderStatusDto;
+    },
+    stop_http_recorder: async (): Promise<string | null> => {
+      return await invoke("stop_http_recorder") as string | null;
+    },
+    get_http_recorder_status: async (): Promise<Wfm.RecorderStatusDto> => {
+      return await invoke("get_http_recorder_status") as Wfm.RecorderStatusDto;
+    },
     reset_data: async (reset_type: string): Promise<any> => {
       try {
         return await invoke("reset_data", { resetType: reset_type })
@@ -48,11 +83,20 @@ const api = {
       }
     }
   },
+  ee_log: {
+    previewBackfill: async (path?: string, fromDate?: string, toDate?: string): Promise<any> => {
+      return await invoke("preview_ee_log_backfill", { path, fromDate, toDate })
+    },
+    commitBackfill: async (): Promise<number> => {
+      return await invoke("commit_ee_log_backfill") as number;
+    },
+  },
   auth: {
-    async login(email: string, password: string): Promise<Wfm.UserDto> {
+    async login(email: string, password: string, remember?: boolean): Promise<Wfm.UserDto> {
       const user = await invoke("login", {
         email: email,
         password: password,
+        remember: remember,
       }) as Wfm.UserDto;
       return user
     },
@@ -95,8 +139,35 @@ const api = {
         quantity: transaction.quantity,
         rank: transaction.rank
       }) as TransactionEntryDto;
+    },
+    async profit(query: ProfitQueryDto): Promise<ProfitReportDto> {
+      return await invoke("get_profit_report", {
+        method: query.method,
+        period: query.period,
+        fromDate: query.from_date,
+        toDate: query.to_date,
+      }) as ProfitReportDto;
     }
   },
+  trades: {
+    async list(query: TradeQueryDto): Promise<TradePageDto> {
+      return await invoke("get_trades", {
+        partner: query.partner,
+        item: query.item,
+        tradeType: query.trade_type,
+        fromDate: query.from_date,
+        toDate: query.to_date,
+        page: query.page,
+        limit: query.limit,
+      }) as TradePageDto;
+    },
+    async get(id: number): Promise<TradeEntryDto> {
+      return await invoke("get_trade", { id }) as TradeEntryDto;
+    },
+    async delete(id: number): Promise<TradeEntryDto> {
+      return await invoke("delete_trade", { id }) as TradeEntryDto;
+    },
+  },
   price_scraper: {
     async start_scraper(days: number): Promise<any> {
       SendTauriEvent("PriceScraper:OnChange", { max: 7, min: 0, current: 0.1 })
@@ -109,7 +180,38 @@ const api = {
       await invoke("toggle_live_scraper")
     }
   },
+  data: {
+    async export(table: DataTable, format: DataFormat, path?: string): Promise<string> {
+      return await invoke("export_data", { table, format, path }) as string;
+    },
+    async import(input: ImportDataDto): Promise<ImportReportDto> {
+      return await invoke("import_data", {
+        table: input.table,
+        path: input.path,
+        mapping: input.mapping,
+        mode: input.mode,
+        dryRun: input.dry_run,
+      }) as ImportReportDto;
+    },
+  },
+  backup: {
+    async create(): Promise<BackupInfoDto> {
+      return await invoke("create_backup") as BackupInfoDto;
+    },
+    async list(): Promise<BackupInfoDto[]> {
+      return await invoke("list_backups") as BackupInfoDto[];
+    },
+    async restore(name: string): Promise<BackupInfoDto> {
+      return await invoke("restore_backup", { name }) as BackupInfoDto;
+    },
+    async checkIntegrity(): Promise<IntegrityReportDto> {
+      return await invoke("check_database_integrity") as IntegrityReportDto;
+    },
+  },
   stock: {
+    timeline: async (stockType: "item" | "riven", id: number): Promise<StockEventDto[]> => {
+      return await invoke("get_stock_timeline", { stockType, id }) as StockEventDto[];
+    },
     item: {
       create: async (input: CreateStockItemEntryDto): Promise<StockItemDto> => {
         return await invoke("create_item_stock", {
@@ -132,6 +234,9 @@ const api = {
       },
       update: async (id: number, item: Partial<StockItemDto>): Promise<StockItemDto> => {
         return await invoke("update_item_stock", { id, miniumPrice: item.minium_price, hidden: item.hidden }) as StockItemDto;
+      },
+      lots: async (id: number): Promise<StockLotDto[]> => {
+        return await invoke("get_item_stock_lots", { id }) as StockLotDto[];
       }
     },
     riven: {
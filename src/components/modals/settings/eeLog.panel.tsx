@@ -0,0 +1,120 @@
+import { Button, Checkbox, Group, List, Select, Stack, Text, TextInput } from "@mantine/core";
+import { EELogSettings, GameSessionSettings } from "$types/index";
+import { useForm } from "@mantine/form";
+import { useEffect } from "react";
+import { useTranslateModal } from "../../../hooks";
+import { useAppContext } from "@contexts/index";
+import { TextColor } from "@components/textColor";
+interface EELogProps {
+  settings: EELogSettings | undefined;
+  updateSettings: (user: EELogSettings) => void;
+}
+
+export function EELogPanel({ updateSettings, settings }: EELogProps) {
+  const { ee_log_status } = useAppContext();
+  const roleForm = useForm({
+    initialValues: {
+      custom_folder: "",
+      on_game_session: true,
+      game_session: {
+        auto_status: true,
+        closed_status: "invisible",
+        pause_live_scraper: false,
+        hide_orders: false,
+      } as GameSessionSettings,
+    },
+    validate: {},
+  });
+
+  const useTranslateSettingsModal = (key: string, context?: { [key: string]: any }, i18Key?: boolean) => useTranslateModal(`settings.panels.ee_log.${key}`, { ...context }, i18Key)
+  const useTranslateFields = (key: string, context?: { [key: string]: any }, i18Key?: boolean) => useTranslateSettingsModal(`fields.${key}`, { ...context }, i18Key)
+
+  useEffect(() => {
+    if (!settings) return;
+    roleForm.setFieldValue("custom_folder", settings.custom_folder);
+    roleForm.setFieldValue("on_game_session", settings.events["on_game_session"] ?? true);
+    roleForm.setFieldValue("game_session", settings.game_session);
+  }, [settings]);
+
+  return (
+    <form method="post" onSubmit={roleForm.onSubmit(async (data) => {
+      if (!settings) return;
+      updateSettings({
+        ...settings,
+        custom_folder: data.custom_folder.trim(),
+        events: { ...settings.events, on_game_session: data.on_game_session },
+        game_session: data.game_session,
+      })
+    })}>
+      <Stack>
+        <TextInput
+          label={useTranslateFields("custom_folder.label")}
+          description={useTranslateFields("custom_folder.description")}
+          placeholder={useTranslateFields("custom_folder.placeholder")}
+          value={roleForm.values.custom_folder}
+          onChange={(event) => roleForm.setFieldValue("custom_folder", event.currentTarget.value)}
+          maw={600}
+        />
+        {ee_log_status && (ee_log_status.found ?
+          <TextColor size={"sm"} i18nKey={useTranslateSettingsModal("status.found", undefined, true)} values={{ path: ee_log_status.path }} />
+          :
+          <>
+            <Text size={"sm"} color="red">{useTranslateSettingsModal("status.not_found")}</Text>
+            <List size={"xs"}>
+              {ee_log_status.searched_folders.map((folder) => <List.Item key={folder}>{folder}</List.Item>)}
+            </List>
+          </>
+        )}
+        <Checkbox
+          label={useTranslateFields("on_game_session.label")}
+          description={useTranslateFields("on_game_session.description")}
+          checked={roleForm.values.on_game_session}
+          onChange={(event) => roleForm.setFieldValue("on_game_session", event.currentTarget.checked)}
+        />
+        <Checkbox
+          label={useTranslateFields("auto_status.label")}
+          description={useTranslateFields("auto_status.description")}
+          disabled={!roleForm.values.on_game_session}
+          checked={roleForm.values.game_session.auto_status}
+          onChange={(event) => roleForm.setFieldValue("game_session.auto_status", event.currentTarget.checked)}
+        />
+        <Select
+          label={useTranslateFields("closed_status.label")}
+          description={useTranslateFields("closed_status.description")}
+          disabled={!roleForm.values.on_game_session || !roleForm.values.game_session.auto_status}
+          value={roleForm.values.game_session.closed_status}
+          onChange={(value) => roleForm.setFieldValue("game_session.closed_status", value || "invisible")}
+          data={[
+            { value: "invisible", label: useTranslateFields("closed_status.options.invisible") },
+            { value: "online", label: useTranslateFields("closed_status.options.online") },
+          ]}
+          maw={300}
+        />
+        <Checkbox
+          label={useTranslateFields("pause_live_scraper.label")}
+          description={useTranslateFields("pause_live_scraper.description")}
+          disabled={!roleForm.values.on_game_session}
+          checked={roleForm.values.game_session.pause_live_scraper}
+          onChange={(event) => roleForm.setFieldValue("game_session.pause_live_scraper", event.currentTarget.checked)}
+        />
+        <Checkbox
+          label={useTranslateFields("hide_orders.label")}
+          description={useTranslateFields("hide_orders.description")}
+          disabled={!roleForm.values.on_game_session}
+          checked={roleForm.values.game_session.hide_orders}
+          onChange={(event) => roleForm.setFieldValue("game_session.hide_orders", event.currentTarget.checked)}
+        />
+      </Stack>
+      <Group position="right" mt={10} sx={{
+        position: "absolute",
+        bottom: 0,
+        right: 0,
+      }}>
+        <Button type="submit" variant="light" color="blue">
+          {useTranslateSettingsModal('save')}
+        </Button>
+      </Group>
+    </form>
+  );
+
+}
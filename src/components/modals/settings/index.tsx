@@ -7,6 +7,7 @@ import { NotificationsPanel } from "./notifications.panel";
 import { useState } from "react";
 import { modals } from "@mantine/modals";
 import { LoggingPanel } from "./logging.panel";
+import { EELogPanel } from "./eeLog.panel";
 
 interface SettingsModalProps {
   settings: Settings | undefined;
@@ -31,6 +32,7 @@ export function SettingsModal({ tradable_items, settings: settingsIn, updateSett
         <Tabs.Tab value="general">{useTranslateSettingsPanels("general.title")}</Tabs.Tab>
         <Tabs.Tab value="live_scraper">{useTranslateSettingsPanels("live_trading.title")}</Tabs.Tab>
         <Tabs.Tab value="notifications">{useTranslateSettingsPanels("notifications.title")}</Tabs.Tab>
+        <Tabs.Tab value="ee_log">{useTranslateSettingsPanels("ee_log.title")}</Tabs.Tab>
         <Tabs.Tab value="logging">{useTranslateSettingsPanels("logging.title")}</Tabs.Tab>
       </Tabs.List>
 
@@ -47,6 +49,13 @@ export function SettingsModal({ tradable_items, settings: settingsIn, updateSett
           }} tradable_items={tradable_items} />
         </Box>
       </Tabs.Panel>
+      <Tabs.Panel value="ee_log" pt="xs">
+        <Box h={"75vh"} sx={{ position: "relative" }}>
+          <EELogPanel settings={settings?.ee_log} updateSettings={(set) => {
+            handleUpdateSettings({ ee_log: set })
+          }} />
+        </Box>
+      </Tabs.Panel>
       <Tabs.Panel value="logging" pt="xs">
         <Box h={"75vh"} sx={{ position: "relative" }}>
           <LoggingPanel settings={settings} updateSettings={(set) => {
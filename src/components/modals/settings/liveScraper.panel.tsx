@@ -53,6 +53,7 @@ export function LiveScraperPanel({ settings, updateSettings, tradable_items }: L
           report_to_wfm: true,
           auto_trade: false,
           order_mode: "both",
+          lot_order: "fifo",
         },
         stock_riven: {
           range_threshold: 25,
@@ -207,6 +208,18 @@ export function LiveScraperPanel({ settings, updateSettings, tradable_items }: L
                       { description: useTranslateFields(`order_mode.sell_description`), value: "sell", label: useTranslateFields("order_mode.options.sell") },
                     ]}
                   />
+                  <Select
+                    label={useTranslateFields("lot_order.label")}
+                    description={useTranslateFields(`lot_order.${roleForm.values.live_trading.stock_item.lot_order}_description`)}
+                    value={roleForm.values.live_trading.stock_item.lot_order}
+                    onChange={(event) => roleForm.setFieldValue('live_trading.stock_item.lot_order', event || "")}
+                    data={[
+                      { description: useTranslateFields(`lot_order.fifo_description`), value: "fifo", label: useTranslateFields("lot_order.options.fifo") },
+                      { description: useTranslateFields(`lot_order.lifo_description`), value: "lifo", label: useTranslateFields("lot_order.options.lifo") },
+                      { description: useTranslateFields(`lot_order.cheapest_description`), value: "cheapest", label: useTranslateFields("lot_order.options.cheapest") },
+                      { description: useTranslateFields(`lot_order.expensive_description`), value: "expensive", label: useTranslateFields("lot_order.options.expensive") },
+                    ]}
+                  />
                   <Checkbox
                     label={useTranslateSettingsModal('strict_whitelist')}
                     description={useTranslateSettingsModal('strict_whitelist_description')}
@@ -107,6 +107,14 @@ export function NotificationsPanel({ settings, updateSettings }: LiveScraperProp
           webhook: "",
           user_ids: [] as string[],
         },
+        on_whisper_offer: {
+          system_notify: true,
+          discord_notify: false,
+          title: "New Whisper Offer",
+          content: "<PLAYER_NAME> wants to <ORDER_TYPE> <ITEM_NAME> for <PRICE>p. <MATCH>",
+          webhook: "",
+          user_ids: [] as string[],
+        },
       },
     },
     validate: {},
@@ -143,6 +151,11 @@ export function NotificationsPanel({ settings, updateSettings }: LiveScraperProp
             notifi={roleForm.values.notifications.on_wfm_chat_message}
             onChange={(key, event) => roleForm.setFieldValue(`notifications.on_wfm_chat_message.${key}`, event)}
           />
+          <Notification
+            i18Key="settings.panels.notifications.on_whisper_offer"
+            notifi={roleForm.values.notifications.on_whisper_offer}
+            onChange={(key, event) => roleForm.setFieldValue(`notifications.on_whisper_offer.${key}`, event)}
+          />
         </SimpleGrid>
       </Group>
       <Group position="right" mt={10} sx={{
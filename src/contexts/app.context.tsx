@@ -1,5 +1,5 @@
 import { createContext, useContext, useEffect, useState } from "react";
-import { AppInfo, RustError, Settings } from '$types/index';
+import { AppInfo, EELogStatusDto, RustError, Settings } from '$types/index';
 import { OnTauriEvent, OnTauriUpdateDataEvent, SendNotificationToWindow, SendTauriEvent, SendTauriUpdateDataEvent } from "../utils";
 import { useQuery } from "@tanstack/react-query";
 import api from "../api";
@@ -16,6 +16,7 @@ import { relaunch } from "@tauri-apps/api/process";
 type AppContextProps = {
   settings: Settings | undefined;
   app_info: AppInfo | undefined;
+  ee_log_status: EELogStatusDto | undefined;
 }
 
 type AppContextProviderProps = {
@@ -25,6 +26,7 @@ type AppContextProviderProps = {
 export const AppContext = createContext<AppContextProps>({
   settings: undefined,
   app_info: undefined,
+  ee_log_status: undefined,
 });
 
 export const useAppContext = () => useContext(AppContext);
@@ -32,6 +34,7 @@ export const useAppContext = () => useContext(AppContext);
 export const AppContextProvider = ({ children }: AppContextProviderProps) => {
   const [settings, setSettings] = useState<Settings | undefined>(undefined);
   const [appInfo, setAppInfo] = useState<AppInfo | undefined>(undefined);
+  const [eeLogStatus, setEELogStatus] = useState<EELogStatusDto | undefined>(undefined);
   const [initializstatus, setInitializstatus] = useState<string>("Initializing..");
 
 
@@ -58,6 +61,7 @@ export const AppContextProvider = ({ children }: AppContextProviderProps) => {
       }
       setSettings({ ...data.settings })
       setAppInfo(data.app_info);
+      setEELogStatus(data.ee_log);
 
 
       const { shouldUpdate, manifest } = await checkUpdate()
@@ -81,7 +85,10 @@ export const AppContextProvider = ({ children }: AppContextProviderProps) => {
       });
     },
     onError(error: RustError) {
-      SendNotificationToWindow(useTranslateRustError("title", { component: error.component }), useTranslateRustError("message", { loc: error.component }));
+      const message = error.wfm_error
+        ? useTranslateRustError(`wfm.${error.wfm_error.kind}`, { ...error.wfm_error })
+        : useTranslateRustError("message", { loc: error.component });
+      SendNotificationToWindow(useTranslateRustError("title", { component: error.component }), message);
     }
   })
 
@@ -102,11 +109,12 @@ export const AppContextProvider = ({ children }: AppContextProviderProps) => {
   useEffect(() => {
     OnTauriEvent("set_initializstatus", (data: { status: string }) => setInitializstatus(data.status));
     OnTauriUpdateDataEvent<Settings>("settings", ({ data, operation }) => handleUpdateSettings(operation, data));
+    OnTauriEvent("EELogParser:Status", (data: EELogStatusDto) => setEELogStatus(data));
     return () => { }
   }, []);
 
   return (
-    <AppContext.Provider value={{ settings, app_info: appInfo }}>
+    <AppContext.Provider value={{ settings, app_info: appInfo, ee_log_status: eeLogStatus }}>
       <SplashScreen opened={isFetching} text={initializstatus} />
       {children}
     </AppContext.Provider>
@@ -1,6 +1,8 @@
 import { createContext, useContext, useEffect, useState } from "react";
 import { Wfm } from '$types/index';
-import { OnTauriUpdateDataEvent, OnSocketEvent } from "../utils";
+import { OnTauriEvent, OnTauriUpdateDataEvent, OnSocketEvent } from "../utils";
+import { notifications } from "@mantine/notifications";
+import i18next from "i18next";
 import { useMutation } from "@tanstack/react-query";
 import api from "@api/index";
 
@@ -44,6 +46,16 @@ export const AuthContextProvider = ({ children }: TauriContextProviderProps) =>
   // Hook on tauri events from rust side
   useEffect(() => {
     OnTauriUpdateDataEvent<Wfm.UserDto>("user", ({ data, operation }) => handleUpdateUser(operation, data));
+    // Background work is paused by the rust side until the user logs in again
+    OnTauriEvent("Client:Session", (data: { expired: boolean }) => {
+      if (!data.expired) return;
+      notifications.show({
+        title: i18next.t('context.tauri.notifications.session_expired'),
+        message: i18next.t('context.tauri.notifications.session_expired_message'),
+        color: 'red',
+        autoClose: 10000,
+      });
+    });
     OnSocketEvent("USER/SET_STATUS", (data: string) => {
       switch (data) {
         case "online":
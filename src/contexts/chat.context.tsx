@@ -75,8 +75,8 @@ export const ChatContextProvider = ({ children }: ChatContextProviderProps) => {
     }
   }
 
+  // Notifications for new messages are sent by the rust side websocket
   const AddChatMessage = async (chat_id: string, message: Wfm.ChatMessage) => {
-    await api.chat.on_new_wfm_message(message);
     setState((preState) => {
       const newState = { ...preState };
       const foundChat = newState.chats.find((item) => item.id === chat_id);
@@ -104,8 +104,10 @@ export const ChatContextProvider = ({ children }: ChatContextProviderProps) => {
       setState((preState) => {
         const newState = { ...preState };
         const foundChat = newState.chats.find((item) => item.id === data?.id);
-        if (foundChat)
+        if (foundChat && foundChat.unread_count > 0) {
           foundChat.unread_count = 0;
+          api.chat.mark_read(foundChat.id);
+        }
         newState.aktive_chat = data;
         return newState;
       })
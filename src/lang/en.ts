@@ -166,6 +166,19 @@ export const en = {
                   sell: "Sell",
                 },
               },
+              lot_order: {
+                label: "Lot Order",
+                fifo_description: "Sales take from the oldest purchase first",
+                lifo_description: "Sales take from the newest purchase first",
+                cheapest_description: "Sales take from the cheapest purchase first",
+                expensive_description: "Sales take from the most expensive purchase first",
+                options: {
+                  fifo: "Oldest first",
+                  lifo: "Newest first",
+                  cheapest: "Cheapest first",
+                  expensive: "Most expensive first",
+                },
+              },
               stock_mode: {
                 label: "Stock Mode",
                 all_description: "Will process all items",
@@ -271,6 +284,70 @@ export const en = {
                 },
               }
             },
+            on_whisper_offer: {
+              title: "On Whisper Offer",
+              tooltip: {
+                discord: "Send a notification to discord",
+                system: "Send a notification to the system",
+              },
+              fields: {
+                title: {
+                  label: "Title",
+                  description: "The title of the notification",
+                },
+                content: {
+                  label: "Content",
+                  description: "Use <PLAYER_NAME>, <ORDER_TYPE>, <ITEM_NAME>, <PRICE> and <MATCH> as placeholders",
+                },
+                webhook: {
+                  label: "Discord Webhook",
+                  description: "The webhook to send notifications to",
+                },
+                user_ids: {
+                  label: "User IDs",
+                  description: "The user ids to ping separated by comma",
+                },
+              }
+            },
+            save: "Save",
+          },
+          ee_log: {
+            title: "EE.log",
+            fields: {
+              custom_folder: {
+                label: "Log Folder",
+                description: "Folder that holds EE.log, leave empty to search the known locations",
+                placeholder: "C:\\Users\\<user>\\AppData\\Local\\Warframe",
+              },
+              on_game_session: {
+                label: "Follow the game session",
+                description: "Read from EE.log when the game starts, logs in and shuts down",
+              },
+              auto_status: {
+                label: "Set the warframe.market status",
+                description: "In game while logged in, online while the game starts",
+              },
+              closed_status: {
+                label: "Status when the game is closed",
+                description: "The warframe.market status set after the game shuts down",
+                options: {
+                  invisible: "Invisible",
+                  online: "Online",
+                },
+              },
+              pause_live_scraper: {
+                label: "Pause the live scraper",
+                description: "Stops the live scraper while the game is closed and starts it again on login",
+              },
+              hide_orders: {
+                label: "Hide my orders",
+                description: "Hides the visible orders while the game is closed and shows them again on login",
+              },
+            },
+            status: {
+              found: "Reading <blue>{{path}}</blue>",
+              not_found: "EE.log was not found, searched in:",
+            },
             save: "Save",
           },
           logging: {
@@ -500,6 +577,7 @@ export const en = {
         title: "Warframe Market - Login",
         email: "Email",
         password: "Password",
+        remember_me: "Stay logged in when the session expires",
         submit: "Login",
       },
     },
@@ -787,6 +865,17 @@ export const en = {
     rust: {
       title: "Error in {{component}}",
       message: "There was an error at {{loc}}. Please check the logs for more information.",
+      wfm: {
+        not_found: "Warframe Market could not find it ({{field}}), it may already be gone.",
+        already_exists: "It already exists on Warframe Market.",
+        validation: "Warframe Market did not accept {{field}}: {{message}}",
+        unauthorized: "Your Warframe Market session has expired. Please log in again.",
+        banned: "Your Warframe Market account is banned.",
+        rate_limited: "Too many requests to Warframe Market. Please wait a moment.",
+        server_error: "Warframe Market is having problems ({{status_code}}). Please try again later.",
+        unreachable: "Warframe Market could not be reached. Please check your connection.",
+        unknown: "Warframe Market returned an error: {{message}}",
+      },
     },
   }
 }
@@ -5,9 +5,10 @@ import {
   Button,
 } from "@mantine/core";
 import { useState } from "react";
-import { useAuthContext, useSocketContextContext } from "../../contexts";
+import { useAuthContext } from "../../contexts";
 import { SendSocketEvent } from "../../utils";
 import { useTranslatePage } from "../../hooks";
+import api from "@api/index";
 
 interface ChatBoxProps {
   // Exlude user names from chat name
@@ -15,7 +16,6 @@ interface ChatBoxProps {
 }
 export const ChatBox = ({ id }: ChatBoxProps) => {
   const useTranslateChatBox = (key: string, context?: { [key: string]: any }, i18Key?: boolean) => useTranslatePage(`chats.msgbox.${key}`, { ...context }, i18Key)
-  const { socket } = useSocketContextContext();
   const { user } = useAuthContext();
   const th = useMantineTheme();
   const [msg, setMsg] = useState<string>("");
@@ -23,14 +23,8 @@ export const ChatBox = ({ id }: ChatBoxProps) => {
 
   const SendMsg = () => {
     const temp_id = Math.random().toString(36).substring(7);
-    socket?.send(JSON.stringify({
-      type: "@WS/chats/SEND_MESSAGE",
-      payload: {
-        chat_id: id,
-        temp_id: temp_id,
-        message: msg,
-      }
-    }));
+    // Messages that can not be sent right now are queued and retried by the rust side
+    api.chat.send_message(temp_id, id, msg);
     const chat = {
       chat_id: id,
       message: `<p>${msg}<p/>`,
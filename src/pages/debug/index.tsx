@@ -3,6 +3,7 @@ import { ImportAlgoTrader } from "./importAlgoTrader";
 import { ResetData } from "./resetData";
 import { Transactions } from "./transactions";
 import { Logging } from "./logging";
+import { Requests } from "./requests";
 export default function DebugPage() {
 
   return (
@@ -12,6 +13,7 @@ export default function DebugPage() {
         <Tabs.Tab value="warframe_algo_trader">Warframe Algo Trader</Tabs.Tab>
         <Tabs.Tab value="reset_data">Reset Data</Tabs.Tab>
         <Tabs.Tab value="transactions">Transactions</Tabs.Tab>
+        <Tabs.Tab value="requests">Requests</Tabs.Tab>
       </Tabs.List>
 
       <Tabs.Panel value="logging">
@@ -26,6 +28,9 @@ export default function DebugPage() {
       <Tabs.Panel value="transactions">
         <Transactions />
       </Tabs.Panel>
+      <Tabs.Panel value="requests">
+        <Requests />
+      </Tabs.Panel>
     </Tabs>
   );
 }
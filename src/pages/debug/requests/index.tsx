@@ -0,0 +1,83 @@
+import { Card, Group, NumberInput, Button, Table, Text, Stack } from "@mantine/core";
+import { useQuery } from "@tanstack/react-query";
+import { useEffect, useState } from "react";
+import { notifications } from "@mantine/notifications";
+import api from "@api/index";
+import { useAppContext } from "@contexts/index";
+import { Settings, Wfm } from "$types/index";
+
+export const Requests = () => {
+  const { settings } = useAppContext();
+  const [rate, setRate] = useState<number>(1);
+  const [burst, setBurst] = useState<number>(3);
+
+  const { data: status } = useQuery<Wfm.RequestStatusDto>({
+    queryKey: ['request_status'],
+    queryFn: () => api.base.get_request_status(),
+    refetchInterval: 2000,
+  });
+
+  useEffect(() => {
+    if (!settings?.requests) return;
+    setRate(settings.requests.rate);
+    setBurst(settings.requests.burst);
+  }, [settings?.requests]);
+
+  const handleSave = async () => {
+    if (!settings) return;
+    await api.base.updatesettings({ ...settings, requests: { rate, burst } } as Settings);
+    notifications.show({
+      title: "Request limits saved",
+      message: `${rate} requests per second, bursts of ${burst}`,
+      color: 'green',
+      autoClose: 5000,
+    });
+  }
+
+  return (
+    <Stack>
+      <Card>
+        <Group align="flex-end">
+          <NumberInput label="Requests per second" min={0.1} step={0.1} precision={1} value={rate} onChange={(v) => setRate(Number(v) || 0.1)} />
+          <NumberInput label="Burst" min={1} step={1} value={burst} onChange={(v) => setBurst(Number(v) || 1)} />
+          <Button onClick={handleSave}>Save</Button>
+        </Group>
+        <Group mt="md">
+          <Text size="sm">Current rate: {status?.rate.toFixed(2) ?? "-"} / {status?.base_rate.toFixed(2) ?? "-"}</Text>
+          <Text size="sm">Burst: {status?.burst ?? "-"}</Text>
+          {status && Object.entries(status.waiting).map(([priority, count]) => (
+            <Text key={priority} size="sm">{priority}: {count} waiting</Text>
+          ))}
+        </Group>
+      </Card>
+      <Card>
+        <Table striped>
+          <thead>
+            <tr>
+              <th>Endpoint</th>
+              <th>Requests</th>
+              <th>Errors</th>
+              <th>Rate limited</th>
+              <th>Last status</th>
+              <th>Last request</th>
+              <th>Total wait (ms)</th>
+            </tr>
+          </thead>
+          <tbody>
+            {status?.endpoints.map((endpoint) => (
+              <tr key={endpoint.endpoint}>
+                <td>{endpoint.endpoint}</td>
+                <td>{endpoint.requests}</td>
+                <td>{endpoint.errors}</td>
+                <td>{endpoint.rate_limited}</td>
+                <td>{endpoint.last_status ?? "-"}</td>
+                <td>{endpoint.last_request ?? "-"}</td>
+                <td>{endpoint.total_wait_ms}</td>
+              </tr>
+            ))}
+          </tbody>
+        </Table>
+      </Card>
+    </Stack>
+  );
+}
@@ -29,6 +29,28 @@ export interface StockItemDto extends StockEntryDto {
   owned: number;
 }
 
+export interface StockLotDto {
+  id: number;
+  stock_item_id: number;
+  quantity: number;
+  remaining: number;
+  unit_price: number;
+  acquired: string;
+  transaction_id?: number;
+}
+
+export interface StockEventDto {
+  id: number;
+  stock_type: "item" | "riven";
+  stock_id: number;
+  field: string;
+  old_value?: string;
+  new_value?: string;
+  source: "user" | "live_scraper" | "trade_detection" | "import";
+  reason?: string;
+  created: string;
+}
+
 export interface StockRivenDto extends StockEntryDto {
   attributes: Wfm.RivenAttributeDto[];
   mastery_rank: number;
@@ -94,6 +116,78 @@ export interface TransactionEntryDto {
   properties?: any
 }
 
+export interface TradeItemDto {
+  name: string;
+  wfm_id?: string;
+  wfm_url_name?: string;
+  display_name: string;
+  quantity: number;
+  rank: number;
+}
+export interface TradeEntryDto {
+  id: number;
+  user_name: string;
+  trade_type: "Sale" | "Purchase" | "Trade" | "Unknown";
+  total_platinum: number;
+  whisper_id?: number;
+  logs: string[];
+  created: string;
+  offerings: TradeItemDto[];
+  receiving: TradeItemDto[];
+}
+export interface TradeQueryDto {
+  partner?: string;
+  item?: string;
+  trade_type?: string;
+  from_date?: string;
+  to_date?: string;
+  page?: number;
+  limit?: number;
+}
+export interface TradePageDto {
+  total: number;
+  page: number;
+  limit: number;
+  results: TradeEntryDto[];
+}
+export type CostMethod = "fifo" | "average";
+export type ProfitPeriod = "day" | "week" | "month" | "year";
+export interface ProfitQueryDto {
+  method?: CostMethod;
+  period?: ProfitPeriod;
+  from_date?: string;
+  to_date?: string;
+}
+export interface ProfitSummaryDto {
+  key: string;
+  bought: number;
+  sold: number;
+  spent: number;
+  revenue: number;
+  cost_of_sales: number;
+  realized_profit: number;
+  trade_tax: number;
+  unmatched: number;
+  open_quantity: number;
+  open_cost: number;
+  unrealized_profit?: number;
+}
+export interface ProfitItemSummaryDto extends ProfitSummaryDto {
+  url: string;
+  name: string;
+  item_type: string;
+  rank: number;
+  tags: string[];
+}
+export interface ProfitReportDto {
+  method: CostMethod;
+  period: ProfitPeriod;
+  total: ProfitSummaryDto;
+  items: ProfitItemSummaryDto[];
+  tags: ProfitSummaryDto[];
+  periods: ProfitSummaryDto[];
+  skipped: number[];
+}
 export interface TransactionItemEntryDto extends TransactionEntryDto {
   properties?: Omit<StockItemDto, 'owned'>
 }
@@ -102,3 +196,48 @@ export interface TransactionRivenDto extends TransactionEntryDto {
   properties: StockRivenDto
 }
 
+
+export type DataTable = "stock_item" | "stock_riven" | "transaction";
+export type DataFormat = "csv" | "json";
+export interface ImportDataDto {
+  table: DataTable;
+  path: string;
+  // File column to field, like { "Item": "name" }
+  mapping?: Record<string, string>;
+  mode: "merge" | "replace";
+  dry_run: boolean;
+}
+export interface UnresolvedRowDto {
+  row: number;
+  name?: string;
+  reason: string;
+}
+export interface ImportReportDto {
+  table: DataTable;
+  mode: "merge" | "replace";
+  dry_run: boolean;
+  total: number;
+  imported: number;
+  skipped: number;
+  replaced: number;
+  backup?: string;
+  unresolved: UnresolvedRowDto[];
+}
+export interface BackupInfoDto {
+  name: string;
+  path: string;
+  reason: string;
+  size: number;
+  created: string;
+}
+export interface IntegrityIssueDto {
+  kind: string;
+  table: string;
+  id?: number;
+  message: string;
+}
+export interface IntegrityReportDto {
+  ok: boolean;
+  integrity_check: string[];
+  issues: IntegrityIssueDto[];
+}
@@ -14,6 +14,12 @@ export interface SetupResponse {
   riven_attributes: Wfm.RivenAttributeInfoDto[];
   chats: Wfm.ChatData[];
   app_info: AppInfo
+  ee_log: EELogStatusDto;
+}
+export interface EELogStatusDto {
+  found: boolean;
+  path: string;
+  searched_folders: string[];
 }
 export interface AppInfo {
   app_author: string;
@@ -36,8 +42,15 @@ export interface RustError {
   backtrace: string;
   log_level: string;
   extra_data: any;
+  wfm_error?: WfmError;
 }
 
+export type WfmError =
+  | { kind: "not_found" | "already_exists" | "validation" | "unknown"; field: string; message: string }
+  | { kind: "unauthorized" | "banned" | "unreachable"; message: string }
+  | { kind: "rate_limited"; retry_after?: number }
+  | { kind: "server_error"; status_code: number };
+
 export interface ScraperState {
   is_running: boolean;
   last_run: Date | null;
@@ -54,6 +67,10 @@ export interface Settings {
   dev_mode: boolean;
   live_scraper: LiveScraperSettings;
   notifications: Notifications;
+  ee_log: EELogSettings;
+  endpoints: EndpointSettings;
+  backup: BackupSettings;
+  requests: RequestSettings;
 }
 
 export interface LiveScraperSettings {
@@ -72,6 +89,7 @@ export interface StockItemSettings {
   report_to_wfm: boolean;
   auto_trade: boolean;
   order_mode: string;
+  lot_order: string;
   blacklist: string[];
   whitelist: string[];
 }
@@ -90,5 +108,32 @@ export interface NotificationBase {
 export interface Notifications {
   on_new_conversation: NotificationBase;
   on_wfm_chat_message: NotificationBase;
+  on_whisper_offer: NotificationBase;
 }
 
+
+export interface BackupSettings {
+  enabled: boolean;
+  interval_hours: number;
+  keep: number;
+}
+export interface RequestSettings {
+  rate: number;
+  burst: number;
+}
+export interface EndpointSettings {
+  wfm_api: string;
+  relics_run: string;
+  wfm_socket: string;
+}
+export interface EELogSettings {
+  events: { [key: string]: boolean };
+  custom_folder: string;
+  game_session: GameSessionSettings;
+}
+export interface GameSessionSettings {
+  auto_status: boolean;
+  closed_status: string;
+  pause_live_scraper: boolean;
+  hide_orders: boolean;
+}
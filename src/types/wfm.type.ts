@@ -262,4 +262,78 @@ export namespace Wfm {
     raw_message: string;
   }
 
-}
\ No newline at end of file
+  export interface ChatOutboxMessage {
+    id: string;
+    chat_id: string;
+    message: string;
+    created: string;
+    attempts: number;
+    last_error?: string;
+  }
+
+  export type ChatSendStatus =
+    | { status: "sent", message: ChatMessage }
+    | { status: "queued", message: ChatOutboxMessage };
+
+  export interface UserProfileDto {
+    id: string;
+    ingame_name: string;
+    avatar?: string;
+    background?: string;
+    about?: string;
+    reputation: number;
+    region: string;
+    platform: string;
+    status: string;
+    last_seen?: string;
+    banned: boolean;
+    role: string;
+  }
+
+  export interface UserReviewDto {
+    id: string;
+    user_from: {
+      id: string;
+      ingame_name: string;
+      avatar?: string;
+      reputation: number;
+    };
+    text: string;
+    review_type: "good" | "bad";
+    date: string;
+    hidden: boolean;
+  }
+
+  export interface UserSummaryDto {
+    profile: UserProfileDto;
+    reviews: UserReviewDto[];
+    recent_positive_reviews: number;
+    recent_negative_reviews: number;
+    orders: OrderDto[];
+  }
+
+  export interface EndpointStatsDto {
+    endpoint: string;
+    requests: number;
+    errors: number;
+    rate_limited: number;
+    last_status?: number;
+    last_request?: string;
+    total_wait_ms: number;
+  }
+
+  export interface RequestStatusDto {
+    rate: number;
+    base_rate: number;
+    burst: number;
+    waiting: Record<"interactive" | "mutation" | "background" | "scraping", number>;
+    endpoints: EndpointStatsDto[];
+  }
+
+  export interface RecorderStatusDto {
+    mode: "off" | "recording" | "replaying";
+    path?: string;
+    exchanges: number;
+  }
+
+}
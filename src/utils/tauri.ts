@@ -34,6 +34,11 @@ export const OnTauriEvent = <T>(event: string, callback: (data: T) => void) => {
   OnTauriEvent<{ type: string, operation: string, data: any }>("Client:Update", ({ type, operation, data }) => {
     listener.fire(`Client:Update:${type}`, { operation, data });
   });
+
+  // Events received on the warframe.market websocket by the rust side
+  OnTauriEvent<{ event: string, payload: any }>("Client:Socket", ({ event, payload }) => {
+    SendSocketEvent(event, payload);
+  });
 })();
 
 /**